@@ -0,0 +1,48 @@
+//! Uses the crate as a Rust library: a descriptor hook tags every decorated
+//! method with the HTTP route its decorator registers, so a router can read
+//! it from the `_applyDecs` descriptor without parsing the source again.
+//!
+//! Run with `cargo run --example descriptor_hook`.
+
+use decorator_transformer::{
+    transform_with_descriptor_hook, DecoratorKind, DecoratorMetadata, ExtraDescriptorFields,
+};
+use serde_json::json;
+
+const SOURCE: &str = r#"
+class Users {
+    @route("/users") list() {}
+    @route("/users/:id") show() {}
+    @inject service;
+}
+"#;
+
+fn main() {
+    let hook = |member: &DecoratorMetadata| {
+        if member.kind != DecoratorKind::Method {
+            return None;
+        }
+        let path = member
+            .decorators
+            .iter()
+            .find_map(|decorator| decorator.strip_prefix("route(")?.strip_suffix(')'))?;
+        Some(ExtraDescriptorFields {
+            fields: vec![json!({ "route": path.trim_matches('"') })],
+        })
+    };
+
+    let result = transform_with_descriptor_hook(
+        "users.js".to_string(),
+        SOURCE.to_string(),
+        "{}".to_string(),
+        &hook,
+    )
+    .expect("options are valid");
+    assert!(result.errors.is_empty(), "{:?}", result.errors);
+
+    let compact: String = result.code.split_whitespace().collect();
+    assert!(compact.contains(r#"[route("/users"),2,"list",false,{"route":"/users"}]"#));
+    assert!(compact.contains(r#"[route("/users/:id"),2,"show",false,{"route":"/users/:id"}]"#));
+    assert!(compact.contains(r#"[inject,0,"service",false]"#));
+    println!("{}", result.code);
+}
@@ -1,5 +1,228 @@
+use sha2::{Digest, Sha256};
+use std::sync::LazyLock;
+
+/// Bundled runtime helpers, all built from `helpers.js`.
+const HELPERS: &str = include_str!("helpers.js");
+
+/// Helper versions, oldest first, keyed by the decorator proposal version
+/// whose `_applyDecs` contract they implement. Each version is the previous
+/// one with its `(from, to)` replacements applied.
+///
+/// "2023-11" attaches `Symbol.metadata` to classes with only member
+/// decorators, shares it with their class decorators and inherits it from
+/// the parent class.
+const HELPER_VERSIONS: &[(&str, &[(&str, &str)])] = &[
+    ("2023-05", &[]),
+    (
+        "2023-11",
+        &[
+            (
+                "  if (metadata !== undefined) {
+    metadataValue = metadata[metadataSymbol];
+  }
+  metadataValue = objectCreate(metadataValue == null ? null : metadataValue);
+",
+                "  if (Object.prototype.hasOwnProperty.call(targetClass, metadataSymbol)) {
+    // Class decorators are applied by a second call, after the member
+    // decorators attached their metadata; share that object.
+    metadataValue = targetClass[metadataSymbol];
+  } else {
+    // Inherits from the metadata of the parent class, if any.
+    metadataValue = (metadata !== undefined ? metadata : Object.getPrototypeOf(targetClass))[
+      metadataSymbol
+    ];
+    metadataValue = objectCreate(metadataValue == null ? null : metadataValue);
+  }
+",
+            ),
+            ("  if (!classDecorators) {", "  if (!classDecorators || !classDecorators.length) {"),
+        ],
+    ),
+];
+
+/// The version emitted unless `helper_version` pins another.
+const DEFAULT_HELPER_VERSION: &str = "2023-05";
+
+static HELPER_SOURCES: LazyLock<Vec<(&str, String)>> = LazyLock::new(|| {
+    let mut source = HELPERS.to_string();
+    HELPER_VERSIONS
+        .iter()
+        .map(|(name, replacements)| {
+            for (from, to) in *replacements {
+                assert_eq!(source.matches(from).count(), 1, "{name}: {from}");
+                source = source.replacen(from, to, 1);
+            }
+            (*name, source.clone())
+        })
+        .collect()
+});
+
 pub fn generate_helper_functions() -> &'static str {
-    include_str!("helpers.js")
+    let (_, source) = HELPER_SOURCES
+        .iter()
+        .find(|(name, _)| *name == DEFAULT_HELPER_VERSION)
+        .unwrap();
+    source
+}
+
+pub fn helper_functions_for_version(version: Option<&str>) -> Result<&'static str, String> {
+    let Some(version) = version else {
+        return Ok(generate_helper_functions());
+    };
+    HELPER_SOURCES
+        .iter()
+        .find(|(name, _)| *name == version)
+        .map(|(_, source)| source.as_str())
+        .ok_or_else(|| {
+            let known: Vec<&str> = HELPER_VERSIONS.iter().map(|(name, _)| *name).collect();
+            format!(
+                "Unknown helper_version \"{}\" (available: {})",
+                version,
+                known.join(", ")
+            )
+        })
+}
+
+/// Number of top-level helper functions in a bundled helper source.
+pub fn helper_function_count(helpers: &str) -> u32 {
+    helpers
+        .lines()
+        .filter(|line| line.starts_with("function ") || line.starts_with("var "))
+        .count() as u32
+}
+
+/// Helpers generated code calls directly, and so the names a shared helper
+/// module exports.
+pub const EXPORTED_HELPERS: &[&str] = &["_applyDecs", "_toPropertyKey", "_setFunctionName"];
+/// Top-level functions of the bundled helpers that only other helpers call.
+pub const INTERNAL_HELPERS: &[&str] = &["_toPrimitive", "_checkInRHS"];
+pub const LEGACY_EXPORTED_HELPERS: &[&str] = &["__decorate", "__param"];
+
+/// Source of a standalone module holding `helpers` and exporting `names`,
+/// for `external_helpers` consumers to import from.
+pub fn shared_helper_module(helpers: &str, names: &[&str]) -> String {
+    format!("{}\nexport {{ {} }};\n", helpers.trim_end(), names.join(", "))
+}
+
+/// Lowercase hex SHA-256 of a helper module's source, for cache keys and
+/// subresource integrity over the emitted helper chunk.
+pub fn helper_module_hash(module: &str) -> String {
+    Sha256::digest(module.as_bytes())
+        .iter()
+        .map(|byte| format!("{byte:02x}"))
+        .collect()
+}
+
+/// `import { ... } from "specifier";` for `names` from a shared helper module,
+/// each one in `renames` imported under its local name.
+pub fn helper_import(
+    names: &[&str],
+    renames: &[(&str, &str)],
+    specifier: &str,
+    quote: char,
+) -> String {
+    let specifiers: Vec<String> = names
+        .iter()
+        .map(|name| match renames.iter().find(|(helper, _)| helper == name) {
+            Some((_, local)) => format!("{name} as {local}"),
+            None => name.to_string(),
+        })
+        .collect();
+    format!(
+        "import {{ {} }} from {quote}{}{quote};",
+        specifiers.join(", "),
+        specifier.replace('\\', "\\\\").replace(quote, &format!("\\{quote}"))
+    )
+}
+
+/// `helpers` with every whole-identifier occurrence of a renamed helper
+/// replaced by its local name.
+pub fn rename_helpers(helpers: &str, renames: &[(&str, &str)]) -> String {
+    let is_identifier_char = |c: char| c.is_ascii_alphanumeric() || c == '_' || c == '$';
+    let mut renamed = helpers.to_string();
+    for (helper, local) in renames {
+        let mut result = String::with_capacity(renamed.len());
+        let mut rest = renamed.as_str();
+        while let Some(index) = rest.find(helper) {
+            let end = index + helper.len();
+            // `this.__decorate` in the legacy helpers is a property, not a
+            // reference.
+            let standalone = !rest[..index].ends_with(|c| is_identifier_char(c) || c == '.')
+                && !rest[end..].starts_with(is_identifier_char);
+            result.push_str(&rest[..index]);
+            result.push_str(if standalone { local } else { helper });
+            rest = &rest[end..];
+        }
+        result.push_str(rest);
+        renamed = result;
+    }
+    renamed
+}
+
+/// `__decorate` helper emitted for `legacy_decorators`.
+pub fn legacy_helper_functions() -> &'static str {
+    include_str!("legacy_helpers.js")
+}
+
+/// `__param` helper, emitted after `__decorate` when a parameter is decorated.
+pub fn legacy_param_helper() -> &'static str {
+    include_str!("legacy_param_helper.js")
+}
+
+/// `_privateStorageGet`/`_privateStorageSet`, emitted after the bundled
+/// helpers when `private_access` is `"weakmap"` and a private accessor is
+/// decorated.
+pub fn private_storage_helpers() -> &'static str {
+    include_str!("private_storage_helpers.js")
+}
+
+pub const PRIVATE_STORAGE_HELPERS: &[&str] = &["_privateStorageGet", "_privateStorageSet"];
+
+const HELPERS_START: &str = "/* @decorator-helpers start ";
+const HELPERS_END: &str = "/* @decorator-helpers end */";
+
+/// `helpers` between start and end marker comments, the start one keyed by a
+/// hash of the content, for `dedupe_helpers_marker`.
+pub fn mark_helpers(helpers: &str) -> String {
+    // FNV-1a, so the key is the same for every build.
+    let hash = helpers.bytes().fold(0xcbf2_9ce4_8422_2325u64, |hash, byte| {
+        (hash ^ u64::from(byte)).wrapping_mul(0x0100_0000_01b3)
+    });
+    format!(
+        "{HELPERS_START}{hash:016x} */\n{}\n{HELPERS_END}",
+        helpers.trim_end()
+    )
+}
+
+/// Removes every marked helper block whose key an earlier block already had,
+/// markers included, from code concatenating several transformed modules.
+pub fn dedupe_marked_helpers(code: &str) -> String {
+    let mut seen = Vec::new();
+    let mut result = String::with_capacity(code.len());
+    let mut rest = code;
+    while let Some(start) = rest.find(HELPERS_START) {
+        let key_start = start + HELPERS_START.len();
+        let Some(key_len) = rest[key_start..].find(" */") else {
+            break;
+        };
+        let Some(end) = rest[key_start..].find(HELPERS_END) else {
+            break;
+        };
+        let mut block_end = key_start + end + HELPERS_END.len();
+        let key = &rest[key_start..key_start + key_len];
+        if seen.contains(&key) {
+            result.push_str(&rest[..start]);
+            if rest[block_end..].starts_with('\n') {
+                block_end += 1;
+            }
+        } else {
+            seen.push(key);
+            result.push_str(&rest[..block_end]);
+        }
+        rest = &rest[block_end..];
+    }
+    result.push_str(rest);
+    result
 }
 
 #[cfg(test)]
@@ -16,6 +239,29 @@ mod tests {
         assert!(helpers.contains("function _checkInRHS"));
     }
 
+    #[test]
+    fn test_helper_versions() {
+        assert_eq!(
+            helper_functions_for_version(None).unwrap(),
+            generate_helper_functions()
+        );
+        assert_eq!(helper_functions_for_version(Some("2023-05")).unwrap(), HELPERS);
+        let latest = helper_functions_for_version(Some("2023-11")).unwrap();
+        assert!(latest.contains("if (!classDecorators || !classDecorators.length) {"));
+        assert!(latest.contains("metadataValue = targetClass[metadataSymbol];"));
+        let err = helper_functions_for_version(Some("2022-03")).unwrap_err();
+        assert!(err.contains("2022-03"));
+        assert!(err.contains("available: 2023-05, 2023-11"));
+    }
+
+    #[test]
+    fn test_helper_function_count() {
+        assert_eq!(helper_function_count(generate_helper_functions()), 5);
+        assert_eq!(helper_function_count(legacy_helper_functions()), 1);
+        assert_eq!(helper_function_count(legacy_param_helper()), 1);
+        assert_eq!(helper_function_count(private_storage_helpers()), 2);
+    }
+
     #[test]
     fn test_helpers_are_readable() {
         let helpers = generate_helper_functions();
@@ -44,4 +290,20 @@ mod tests {
             line_count
         );
     }
+
+    #[test]
+    fn test_dedupe_marked_helpers() {
+        let helpers = mark_helpers("function _a() {}\n");
+        assert!(helpers.starts_with("/* @decorator-helpers start "));
+        assert!(helpers.ends_with("function _a() {}\n/* @decorator-helpers end */"));
+        assert_eq!(helpers, mark_helpers("function _a() {}\n"));
+
+        let other = mark_helpers("function _b() {}");
+        let bundle = format!("{helpers}\nA;\n{helpers}\nB;\n{other}\nC;\n");
+        assert_eq!(
+            dedupe_marked_helpers(&bundle),
+            format!("{helpers}\nA;\nB;\n{other}\nC;\n")
+        );
+        assert_eq!(dedupe_marked_helpers("A;\n"), "A;\n");
+    }
 }
@@ -96,7 +96,7 @@ function _applyDecs(
    * @param {Array} allInitializers - All initializers for this decoration pass
    * @param {boolean} isStatic - Whether this is a static member
    * @param {boolean} isPrivate - Whether this is a private member
-   * @param {boolean} hasPrivateGetter - Whether there's a private getter
+   * @param {boolean} isField - Whether this is a field (decorated value is an initializer)
    * @param {Function} privateAccessValidator - Validator for private access
    */
   function applyDecorator(
@@ -109,7 +109,7 @@ function _applyDecs(
     allInitializers,
     isStatic,
     isPrivate,
-    hasPrivateGetter,
+    isField,
     privateAccessValidator
   ) {
     /**
@@ -156,7 +156,7 @@ function _applyDecs(
       descriptorKey = isGetter ? "get" : (isSetter || isAccessor ? "set" : "value");
       
       if (isPrivate) {
-        if (hasPrivateGetter || isAccessor) {
+        if (isField || isAccessor) {
           descriptor = {
             get: _setFunctionName(function () {
               return getter(this);
@@ -169,15 +169,15 @@ function _applyDecs(
           descriptor[descriptorKey] = getter;
         }
         
-        if (!hasPrivateGetter) {
+        if (!isField) {
           _setFunctionName(descriptor[descriptorKey], memberName, isMethod ? "" : descriptorKey);
         }
-      } else if (!hasPrivateGetter) {
+      } else if (!isField) {
         descriptor = Object.getOwnPropertyDescriptor(target, memberName) || {};
       }
       
       // Check for duplicate decorators
-      if (!hasPrivateGetter && !isPrivate) {
+      if (!isField && !isPrivate) {
         const registryKey = decoratorRegistry[+isStatic][memberName];
         if (registryKey && (registryKey ^ memberKind) !== 7) {
           throw Error(
@@ -286,12 +286,12 @@ function _applyDecs(
         } else {
           const decoratorReturnCheck = assertCallable(
             decoratedValue,
-            (hasPrivateGetter ? "field" : "method") + " decorators",
+            (isField ? "field" : "method") + " decorators",
             "return"
           );
           
           if (decoratorReturnCheck) {
-            if (hasPrivateGetter) {
+            if (isField) {
               accessorInitializers.unshift(decoratedValue);
             } else {
               descriptor[descriptorKey] = decoratedValue;
@@ -310,7 +310,7 @@ function _applyDecs(
     }
     
     // Set up the descriptor or private accessors
-    if (!hasPrivateGetter && !isClassDecorator) {
+    if (!isField && !isClassDecorator) {
       if (isPrivate) {
         if (isAccessor) {
           allInitializers.splice(
@@ -353,10 +353,12 @@ function _applyDecs(
   
   appliedDecorators = [];
   
+  // Always occupies a slot, so the proto and static initializers are the
+  // last two entries of the result whether or not they exist.
   const addClassInitializer = function (initializer, isStatic) {
-    if (initializer) {
-      appliedDecorators.push(createInitializerWrapper(initializer, isStatic, 0));
-    }
+    appliedDecorators.push(
+      initializer && createInitializerWrapper(initializer, isStatic, 0)
+    );
   };
   
   /**
@@ -384,7 +386,7 @@ function _applyDecs(
           appliedDecorators,
           !!isStatic,
           isPrivateMember,
-          isPrivate,
+          kind === 0,
           isStatic && isPrivateMember
             ? function (obj) { return _checkInRHS(obj) === targetClass; }
             : parentClass
@@ -0,0 +1,420 @@
+use oxc_allocator::{Allocator, Box as ArenaBox, CloneIn, TakeIn, Vec as ArenaVec};
+use oxc_ast::{ast::*, AstBuilder, NONE};
+use oxc_ast_visit::{walk_mut, VisitMut};
+use oxc_span::{Atom, GetSpan, Span, SPAN};
+
+use crate::transformer::strip_abstract;
+use crate::{Located, TransformStats};
+
+pub struct LegacyOutcome {
+    pub needs_helpers: bool,
+    pub needs_param_helper: bool,
+    pub errors: Vec<Located>,
+    pub stats: TransformStats,
+}
+
+/// Lowers decorators the way TypeScript does under `experimentalDecorators`:
+/// members are decorated with `__decorate([...], C.prototype, "m", null)`
+/// after the class, and class decorators reassign the class binding.
+/// Parameter decorators are wrapped in `__param(index, dec)` and applied
+/// with their method's decorators, or with the class decorators for
+/// constructor parameters.
+///
+/// Only class declarations can be decorated in this mode; decorators on
+/// class expressions and private members are reported as errors. The
+/// helpers are called by their name in `renames`, if any.
+pub fn transform_legacy<'a>(
+    program: &mut Program<'a>,
+    allocator: &'a Allocator,
+    renames: &[(&str, &str)],
+) -> LegacyOutcome {
+    let ast = AstBuilder::new(allocator);
+    let helper = |name: &'static str| {
+        renames
+            .iter()
+            .find(|(helper, _)| *helper == name)
+            .map_or(Atom::from(name), |(_, local)| ast.atom(local))
+    };
+    let mut transformer = LegacyTransformer {
+        ast,
+        decorate: helper("__decorate"),
+        param: helper("__param"),
+        needs_helpers: false,
+        needs_param_helper: false,
+        errors: Vec::new(),
+        stats: TransformStats::default(),
+        anonymous_classes: 0,
+    };
+    transformer.visit_program(program);
+    LegacyOutcome {
+        needs_helpers: transformer.needs_helpers,
+        needs_param_helper: transformer.needs_param_helper,
+        errors: transformer.errors,
+        stats: transformer.stats,
+    }
+}
+
+#[derive(Clone, Copy)]
+enum ExportKind {
+    None,
+    Named,
+    Default,
+}
+
+struct LegacyTransformer<'a> {
+    ast: AstBuilder<'a>,
+    decorate: Atom<'a>,
+    param: Atom<'a>,
+    needs_helpers: bool,
+    needs_param_helper: bool,
+    errors: Vec<Located>,
+    stats: TransformStats,
+    anonymous_classes: usize,
+}
+
+impl<'a> LegacyTransformer<'a> {
+    fn has_decorators(class: &Class<'a>) -> bool {
+        !class.decorators.is_empty()
+            || class.body.body.iter().any(|element| match element {
+                ClassElement::MethodDefinition(m) => {
+                    !m.decorators.is_empty()
+                        || m.value.params.items.iter().any(|p| !p.decorators.is_empty())
+                }
+                ClassElement::PropertyDefinition(p) => !p.decorators.is_empty(),
+                ClassElement::AccessorProperty(a) => !a.decorators.is_empty(),
+                _ => false,
+            })
+    }
+
+    fn lower_statement(&mut self, statement: Statement<'a>, out: &mut ArenaVec<'a, Statement<'a>>) {
+        match statement {
+            Statement::ClassDeclaration(class) if Self::has_decorators(&class) => {
+                let span = class.span;
+                self.lower_class(class, ExportKind::None, span, out);
+            }
+            Statement::ExportNamedDeclaration(mut export)
+                if matches!(
+                    &export.declaration,
+                    Some(Declaration::ClassDeclaration(class)) if Self::has_decorators(class)
+                ) =>
+            {
+                let Some(Declaration::ClassDeclaration(class)) = export.declaration.take() else {
+                    unreachable!()
+                };
+                self.lower_class(class, ExportKind::Named, export.span, out);
+            }
+            Statement::ExportDefaultDeclaration(export)
+                if matches!(
+                    &export.declaration,
+                    ExportDefaultDeclarationKind::ClassDeclaration(class)
+                        if Self::has_decorators(class)
+                ) =>
+            {
+                let export = export.unbox();
+                let ExportDefaultDeclarationKind::ClassDeclaration(class) = export.declaration
+                else {
+                    unreachable!()
+                };
+                self.lower_class(class, ExportKind::Default, export.span, out);
+            }
+            statement => out.push(statement),
+        }
+    }
+
+    fn lower_class(
+        &mut self,
+        mut class: ArenaBox<'a, Class<'a>>,
+        export: ExportKind,
+        span: Span,
+        out: &mut ArenaVec<'a, Statement<'a>>,
+    ) {
+        self.needs_helpers = true;
+        self.stats.classes_transformed += 1;
+        let name = self.ensure_class_name(&mut class);
+        let decorators = class.decorators.take_in(self.ast.allocator);
+        self.stats.class_decorators += decorators.len() as u32;
+        let mut decorators: Vec<Expression<'a>> =
+            decorators.into_iter().map(|d| d.expression).collect();
+        let constructor = class.body.body.iter_mut().find_map(|element| match element {
+            ClassElement::MethodDefinition(m) if m.kind.is_constructor() => Some(m),
+            _ => None,
+        });
+        if let Some(constructor) = constructor {
+            decorators.extend(self.param_decorators(&mut constructor.value.params));
+        }
+        let member_decorations = self.decorate_members(&mut class, name);
+
+        if decorators.is_empty() {
+            out.push(self.class_declaration_statement(class, export, span));
+            out.extend(member_decorations);
+            return;
+        }
+
+        // Class decorators may replace the class, so TypeScript rebinds it:
+        // `let C = class C {}; C = __decorate([dec], C);`.
+        strip_abstract(&mut class);
+        class.r#type = ClassType::ClassExpression;
+        let binding = self.ast.binding_pattern(
+            self.ast.binding_pattern_kind_binding_identifier(SPAN, name),
+            NONE,
+            false,
+        );
+        let declarator = self.ast.variable_declarator(
+            SPAN,
+            VariableDeclarationKind::Let,
+            binding,
+            Some(Expression::ClassExpression(class)),
+            false,
+        );
+        out.push(Statement::from(self.ast.declaration_variable(
+            span,
+            VariableDeclarationKind::Let,
+            self.ast.vec1(declarator),
+            false,
+        )));
+        out.extend(member_decorations);
+
+        let decorate =
+            self.decorate_call(decorators, self.ast.expression_identifier(SPAN, name), None);
+        let assignment = self.ast.expression_assignment(
+            SPAN,
+            AssignmentOperator::Assign,
+            AssignmentTarget::from(
+                self.ast
+                    .simple_assignment_target_assignment_target_identifier(SPAN, name),
+            ),
+            decorate,
+        );
+        out.push(self.ast.statement_expression(SPAN, assignment));
+
+        match export {
+            ExportKind::None => {}
+            ExportKind::Named => {
+                let specifier = self.ast.export_specifier(
+                    SPAN,
+                    self.ast.module_export_name_identifier_reference(SPAN, name),
+                    self.ast.module_export_name_identifier_name(SPAN, name),
+                    ImportOrExportKind::Value,
+                );
+                out.push(Statement::from(
+                    self.ast.module_declaration_export_named_declaration(
+                        SPAN,
+                        None,
+                        self.ast.vec1(specifier),
+                        None,
+                        ImportOrExportKind::Value,
+                        NONE,
+                    ),
+                ));
+            }
+            ExportKind::Default => {
+                out.push(Statement::from(
+                    self.ast.module_declaration_export_default_declaration(
+                        SPAN,
+                        ExportDefaultDeclarationKind::from(
+                            self.ast.expression_identifier(SPAN, name),
+                        ),
+                    ),
+                ));
+            }
+        }
+    }
+
+    // Decorated `export default class {}` needs a binding to decorate; like
+    // TypeScript, it is named `default_1`.
+    fn ensure_class_name(&mut self, class: &mut Class<'a>) -> Atom<'a> {
+        if let Some(id) = &class.id {
+            return id.name;
+        }
+        self.anonymous_classes += 1;
+        let name = self
+            .ast
+            .atom(&format!("default_{}", self.anonymous_classes));
+        class.id = Some(self.ast.binding_identifier(SPAN, name));
+        name
+    }
+
+    fn class_declaration_statement(
+        &self,
+        class: ArenaBox<'a, Class<'a>>,
+        export: ExportKind,
+        span: Span,
+    ) -> Statement<'a> {
+        match export {
+            ExportKind::None => Statement::ClassDeclaration(class),
+            ExportKind::Named => {
+                Statement::from(self.ast.module_declaration_export_named_declaration(
+                    span,
+                    Some(Declaration::ClassDeclaration(class)),
+                    self.ast.vec(),
+                    None,
+                    ImportOrExportKind::Value,
+                    NONE,
+                ))
+            }
+            ExportKind::Default => {
+                Statement::from(self.ast.module_declaration_export_default_declaration(
+                    span,
+                    ExportDefaultDeclarationKind::ClassDeclaration(class),
+                ))
+            }
+        }
+    }
+
+    // TypeScript decorates instance members before static ones, each group in
+    // source order.
+    fn decorate_members(&mut self, class: &mut Class<'a>, name: Atom<'a>) -> Vec<Statement<'a>> {
+        let mut statements = Vec::new();
+        for is_static in [false, true] {
+            for element in class.body.body.iter_mut() {
+                if element.r#static() != is_static {
+                    continue;
+                }
+                let (decorators, params, key, is_property) = match element {
+                    ClassElement::MethodDefinition(m) if !m.kind.is_constructor() => {
+                        let m = &mut **m;
+                        (&mut m.decorators, Some(&mut m.value.params), &m.key, false)
+                    }
+                    ClassElement::PropertyDefinition(p) => {
+                        let p = &mut **p;
+                        (&mut p.decorators, None, &p.key, true)
+                    }
+                    ClassElement::AccessorProperty(a) => {
+                        let a = &mut **a;
+                        (&mut a.decorators, None, &a.key, false)
+                    }
+                    _ => continue,
+                };
+                let has_param_decorators = params
+                    .as_ref()
+                    .is_some_and(|params| params.items.iter().any(|p| !p.decorators.is_empty()));
+                if decorators.is_empty() && !has_param_decorators {
+                    continue;
+                }
+                let decorators = decorators.take_in(self.ast.allocator);
+                self.stats.member_decorators += decorators.len() as u32;
+                let mut decorators: Vec<Expression<'a>> =
+                    decorators.into_iter().map(|d| d.expression).collect();
+                if let Some(params) = params {
+                    decorators.extend(self.param_decorators(params));
+                }
+                let Some(key) = self.member_key(key) else {
+                    self.errors.push((
+                        format!(
+                            "Legacy decorators are not supported on private members ({})",
+                            key.span().start
+                        ),
+                        key.span().start,
+                    ));
+                    continue;
+                };
+                let mut target = self.ast.expression_identifier(SPAN, name);
+                if !is_static {
+                    target = Expression::from(self.ast.member_expression_static(
+                        SPAN,
+                        target,
+                        self.ast.identifier_name(SPAN, "prototype"),
+                        false,
+                    ));
+                }
+                // `null` makes `__decorate` read the current descriptor;
+                // properties have none on the prototype.
+                let descriptor = if is_property {
+                    self.ast.void_0(SPAN)
+                } else {
+                    self.ast.expression_null_literal(SPAN)
+                };
+                let call = self.decorate_call(decorators, target, Some((key, descriptor)));
+                statements.push(self.ast.statement_expression(SPAN, call));
+            }
+        }
+        statements
+    }
+
+    // `__param(i, dec)` for each decorator on the `i`th parameter, in order.
+    fn param_decorators(&mut self, params: &mut FormalParameters<'a>) -> Vec<Expression<'a>> {
+        let mut wrapped = Vec::new();
+        for (index, param) in params.items.iter_mut().enumerate() {
+            for decorator in param.decorators.take_in(self.ast.allocator) {
+                self.needs_param_helper = true;
+                let mut arguments = self.ast.vec();
+                arguments.push(Argument::from(self.ast.expression_numeric_literal(
+                    SPAN,
+                    index as f64,
+                    None,
+                    NumberBase::Decimal,
+                )));
+                arguments.push(Argument::from(decorator.expression));
+                wrapped.push(self.ast.expression_call(
+                    SPAN,
+                    self.ast.expression_identifier(SPAN, self.param),
+                    NONE,
+                    arguments,
+                    false,
+                ));
+            }
+        }
+        wrapped
+    }
+
+    fn member_key(&self, key: &PropertyKey<'a>) -> Option<Expression<'a>> {
+        if let Some(name) = key.static_name() {
+            return Some(
+                self.ast
+                    .expression_string_literal(SPAN, self.ast.atom(&name), None),
+            );
+        }
+        key.as_expression()
+            .map(|expr| expr.clone_in(self.ast.allocator))
+    }
+
+    fn decorate_call(
+        &self,
+        decorators: Vec<Expression<'a>>,
+        target: Expression<'a>,
+        member: Option<(Expression<'a>, Expression<'a>)>,
+    ) -> Expression<'a> {
+        let elements =
+            self.ast
+                .vec_from_iter(decorators.into_iter().map(ArrayExpressionElement::from));
+        let mut arguments = self.ast.vec();
+        arguments.push(Argument::from(self.ast.expression_array(SPAN, elements)));
+        arguments.push(Argument::from(target));
+        if let Some((key, descriptor)) = member {
+            arguments.push(Argument::from(key));
+            arguments.push(Argument::from(descriptor));
+        }
+        self.ast.expression_call(
+            SPAN,
+            self.ast.expression_identifier(SPAN, self.decorate),
+            NONE,
+            arguments,
+            false,
+        )
+    }
+}
+
+impl<'a> VisitMut<'a> for LegacyTransformer<'a> {
+    fn visit_statements(&mut self, it: &mut ArenaVec<'a, Statement<'a>>) {
+        let statements = it.take_in(self.ast.allocator);
+        for statement in statements {
+            self.lower_statement(statement, it);
+        }
+        walk_mut::walk_statements(self, it);
+    }
+
+    fn visit_class(&mut self, it: &mut Class<'a>) {
+        // Declarations were lowered by `visit_statements` before their
+        // bodies were walked, so any decorator left here is on an expression.
+        if Self::has_decorators(it) {
+            self.errors.push((
+                format!(
+                    "Legacy decorators are only supported on class declarations ({})",
+                    it.span.start
+                ),
+                it.span.start,
+            ));
+        }
+        walk_mut::walk_class(self, it);
+    }
+}
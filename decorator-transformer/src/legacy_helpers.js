@@ -0,0 +1,28 @@
+/**
+ * Legacy (TypeScript `experimentalDecorators`) Decorator Runtime Helpers
+ *
+ * These helpers match the ones TypeScript emits and `tslib` exports, so code
+ * produced with `legacy_decorators` behaves like `tsc` output.
+ *
+ * @see https://www.typescriptlang.org/docs/handbook/decorators.html
+ */
+
+/**
+ * Apply legacy decorators to a class or one of its members.
+ *
+ * Called with two arguments for a class, and with four for a member, where
+ * `desc` is `null` for methods and accessors (read the current descriptor)
+ * and `void 0` for properties.
+ *
+ * @param {Array<Function>} decorators - Decorators, applied last to first
+ * @param {Object} target - The class, or its prototype for instance members
+ * @param {PropertyKey} [key] - Member name
+ * @param {PropertyDescriptor|null|undefined} [desc] - Member descriptor
+ * @returns {*} The decorated class or descriptor
+ */
+var __decorate = (this && this.__decorate) || function (decorators, target, key, desc) {
+  var c = arguments.length, r = c < 3 ? target : desc === null ? desc = Object.getOwnPropertyDescriptor(target, key) : desc, d;
+  if (typeof Reflect === "object" && typeof Reflect.decorate === "function") r = Reflect.decorate(decorators, target, key, desc);
+  else for (var i = decorators.length - 1; i >= 0; i--) if (d = decorators[i]) r = (c < 3 ? d(r) : c > 3 ? d(target, key, r) : d(target, key)) || r;
+  return c > 3 && r && Object.defineProperty(target, key, r), r;
+};
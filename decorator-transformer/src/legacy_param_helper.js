@@ -0,0 +1,11 @@
+/**
+ * Adapt a parameter decorator for `__decorate`, which calls it with the
+ * target and member name only.
+ *
+ * @param {number} paramIndex - Position of the decorated parameter
+ * @param {Function} decorator - Parameter decorator `(target, key, index)`
+ * @returns {Function} Decorator to pass to `__decorate`
+ */
+var __param = (this && this.__param) || function (paramIndex, decorator) {
+  return function (target, key) { decorator(target, key, paramIndex); };
+};
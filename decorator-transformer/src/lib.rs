@@ -1,20 +1,40 @@
-use oxc_allocator::Allocator;
+use oxc_allocator::{Allocator, CloneIn, Vec as ArenaVec};
 use oxc_ast::AstBuilder;
 use oxc_ast::{
-    ast::{ClassElement, Declaration, Program, Statement, VariableDeclarationKind},
+    ast::{
+        ArrowFunctionExpression, Decorator, Directive, ImportOrExportKind, Program, Statement,
+        StaticMemberExpression, VariableDeclarationKind,
+    },
     NONE,
 };
-use oxc_codegen::Codegen;
+use oxc_ast_visit::{walk, walk_mut, Visit, VisitMut};
+use oxc_codegen::{Codegen, CodegenOptions, CodegenReturn, Context, Gen};
+use oxc_diagnostics::OxcDiagnostic;
 use oxc_parser::Parser;
 use oxc_semantic::SemanticBuilder;
-use oxc_span::{SourceType, SPAN};
+use oxc_span::{Atom, GetSpan, SourceType, Span, SPAN};
 use oxc_traverse::traverse_mut;
 use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+use std::path::PathBuf;
 
 mod codegen;
+mod legacy;
+mod metadata;
+mod source_map;
+mod strip_types;
 mod transformer;
-use codegen::generate_helper_functions;
-use transformer::{DecoratorTransformer, TransformerState};
+use codegen::{
+    dedupe_marked_helpers, helper_function_count, helper_functions_for_version, helper_import,
+    helper_module_hash, legacy_helper_functions, legacy_param_helper, mark_helpers,
+    private_storage_helpers, rename_helpers, shared_helper_module, EXPORTED_HELPERS,
+    LEGACY_EXPORTED_HELPERS, PRIVATE_STORAGE_HELPERS,
+};
+use transformer::{
+    AfterClassStatements, ClassTemps, DecoratorTransformer,
+    HoistedDescriptors, TransformerState,
+};
+pub use transformer::{DecoratorKind, DecoratorMetadata, DescriptorHook, ExtraDescriptorFields};
 
 wit_bindgen::generate!({
     world: "transformer",
@@ -23,12 +43,308 @@ wit_bindgen::generate!({
     },
 });
 
-#[derive(Serialize, Deserialize, Debug)]
+// Written out because `additional_derives` would also derive `Default` for
+// the `severity` enum, which has no default variant.
+#[allow(clippy::derivable_impls)]
+impl Default for TransformStats {
+    fn default() -> Self {
+        Self {
+            classes_transformed: 0,
+            member_decorators: 0,
+            class_decorators: 0,
+            helpers_emitted: 0,
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct TransformOptions {
     #[serde(default = "default_true")]
     pub source_maps: bool,
+    #[serde(default)]
+    pub verify_output: bool,
+    #[serde(default)]
+    pub hoist_descriptors: bool,
+    #[serde(default)]
+    pub source_type: Option<SourceKind>,
+    #[serde(default)]
+    pub banner: Option<String>,
+    #[serde(default)]
+    pub force_strict: bool,
+    #[serde(default)]
+    pub helper_version: Option<String>,
+    #[serde(default)]
+    pub strip_types: bool,
+    #[serde(default)]
+    pub quote_style: QuoteStyle,
+    #[serde(default)]
+    pub legacy_decorators: bool,
+    #[serde(default)]
+    pub marker_decorators: Vec<String>,
+    #[serde(default)]
+    pub external_helpers: Option<String>,
+    #[serde(default)]
+    pub check_decorator_bindings: bool,
+    #[serde(default)]
+    pub newline: Newline,
+    #[serde(default)]
+    pub decoration_site: DecorationSite,
+    #[serde(default)]
+    pub emit_metadata: bool,
+    #[serde(default)]
+    pub auto_import: BTreeMap<String, String>,
+    #[serde(default)]
+    pub private_access: PrivateAccess,
+    #[serde(default)]
+    pub warn_decorator_order: bool,
+    #[serde(default)]
+    pub shared_helpers: bool,
+    #[serde(default)]
+    pub collapse_init_vars: bool,
+    #[serde(default)]
+    pub member_installation: MemberInstallation,
+    #[serde(default)]
+    pub node_compat: bool,
+    #[serde(default)]
+    pub dedupe_helpers_marker: bool,
+    #[serde(default)]
+    pub error_mode: ErrorMode,
+    #[serde(default)]
+    pub lazy_decorators: bool,
+    #[serde(default)]
+    pub polyfill_symbol_metadata: bool,
+    #[serde(default)]
+    pub spec_compliant: bool,
+    #[serde(default)]
+    pub helpers: HelperMode,
+    #[serde(default)]
+    pub include_classes: Option<Vec<String>>,
+    #[serde(default)]
+    pub verbose_output: bool,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum QuoteStyle {
+    Single,
+    #[default]
+    Double,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum Newline {
+    #[default]
+    Lf,
+    Crlf,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum PrivateAccess {
+    #[default]
+    Native,
+    Weakmap,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "kebab-case")]
+pub enum DecorationSite {
+    #[default]
+    StaticBlock,
+    AfterClass,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "kebab-case")]
+pub enum ErrorMode {
+    #[default]
+    Collect,
+    FailFast,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "kebab-case")]
+pub enum MemberInstallation {
+    #[default]
+    ClassBody,
+    DefineProperty,
+}
+
+/// `helpers` option: `"Inline"` or `{"Import": {"module": "..."}}`, the
+/// latter an alternative spelling of `external_helpers`.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq, Default)]
+pub enum HelperMode {
+    #[default]
+    Inline,
+    Import { module: String },
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum SourceKind {
+    Js,
+    Ts,
+    Jsx,
+    Tsx,
+}
+
+impl From<SourceKind> for SourceType {
+    fn from(kind: SourceKind) -> Self {
+        match kind {
+            SourceKind::Js => SourceType::mjs(),
+            SourceKind::Ts => SourceType::ts(),
+            SourceKind::Jsx => SourceType::jsx(),
+            SourceKind::Tsx => SourceType::tsx(),
+        }
+    }
+}
+
+impl Default for TransformOptions {
+    fn default() -> Self {
+        Self {
+            source_maps: true,
+            verify_output: false,
+            hoist_descriptors: false,
+            source_type: None,
+            banner: None,
+            force_strict: false,
+            helper_version: None,
+            strip_types: false,
+            quote_style: QuoteStyle::Double,
+            legacy_decorators: false,
+            marker_decorators: Vec::new(),
+            external_helpers: None,
+            check_decorator_bindings: false,
+            newline: Newline::Lf,
+            decoration_site: DecorationSite::StaticBlock,
+            emit_metadata: false,
+            auto_import: BTreeMap::new(),
+            private_access: PrivateAccess::Native,
+            warn_decorator_order: false,
+            shared_helpers: false,
+            collapse_init_vars: false,
+            member_installation: MemberInstallation::ClassBody,
+            node_compat: false,
+            dedupe_helpers_marker: false,
+            error_mode: ErrorMode::Collect,
+            lazy_decorators: false,
+            polyfill_symbol_metadata: false,
+            spec_compliant: false,
+            helpers: HelperMode::Inline,
+            include_classes: None,
+            verbose_output: false,
+        }
+    }
+}
+
+impl TransformOptions {
+    /// Overrides the options that `spec_compliant` implies: decorators are
+    /// applied in the class's static block with native private access, and
+    /// `Symbol.metadata` is polyfilled where the module uses metadata.
+    fn apply_spec_compliance(mut self) -> Self {
+        if self.spec_compliant {
+            self.decoration_site = DecorationSite::StaticBlock;
+            self.member_installation = MemberInstallation::ClassBody;
+            self.private_access = PrivateAccess::Native;
+            self.lazy_decorators = false;
+            self.polyfill_symbol_metadata = true;
+        }
+        self
+    }
+
+    pub(crate) fn codegen_options(&self) -> CodegenOptions {
+        CodegenOptions {
+            single_quote: self.quote_style == QuoteStyle::Single,
+            ..CodegenOptions::default()
+        }
+    }
+
+    /// Where transformed code gets its helpers from, per `helpers`,
+    /// `external_helpers` and `shared_helpers`.
+    pub fn helper_strategy(&self) -> Result<HelperStrategy, String> {
+        let external_helpers = match (&self.helpers, &self.external_helpers) {
+            (HelperMode::Import { module }, Some(other)) if module != other => {
+                return Err("helpers and external_helpers name different modules".to_string());
+            }
+            (HelperMode::Import { module }, _) => Some(module),
+            (HelperMode::Inline, external_helpers) => external_helpers.as_ref(),
+        };
+        match (external_helpers, self.shared_helpers) {
+            (Some(_), true) => {
+                Err("external_helpers and shared_helpers cannot be combined".to_string())
+            }
+            (Some(module), false) => Ok(HelperStrategy::Runtime {
+                module: module.clone(),
+            }),
+            (None, true) => Ok(HelperStrategy::Shared),
+            (None, false) => Ok(HelperStrategy::Inline),
+        }
+    }
+
+    // With `error_mode: "fail-fast"`, the first collected error becomes the
+    // `Err` of the transform.
+    fn check_errors(&self, result: TransformResult) -> Result<TransformResult, String> {
+        match (self.error_mode, result.errors.first()) {
+            (ErrorMode::FailFast, Some(error)) => Err(error.clone()),
+            _ => Ok(result),
+        }
+    }
+
+    // The helper block prepended to a transformed module: the bundled
+    // `helpers`, an import of the `used` ones, or nothing when they are
+    // shared.
+    fn helper_prelude(
+        &self,
+        helpers: &str,
+        used: &[&str],
+        renames: &[(&str, &str)],
+        source_type: SourceType,
+    ) -> Result<Option<String>, String> {
+        match self.helper_strategy()? {
+            HelperStrategy::Inline if self.dedupe_helpers_marker => Ok(Some(mark_helpers(helpers))),
+            HelperStrategy::Inline => Ok(Some(helpers.to_string())),
+            HelperStrategy::Runtime { module } => {
+                if !source_type.is_module() {
+                    return Err("external_helpers requires an ES module".to_string());
+                }
+                let quote = match self.quote_style {
+                    QuoteStyle::Single => '\'',
+                    QuoteStyle::Double => '"',
+                };
+                Ok(Some(helper_import(used, renames, &module, quote)))
+            }
+            HelperStrategy::Shared => Ok(None),
+        }
+    }
+
+    // The helpers a shared helper module exports for these options.
+    fn exported_helpers(&self) -> Vec<&'static str> {
+        if self.legacy_decorators {
+            LEGACY_EXPORTED_HELPERS.to_vec()
+        } else if self.private_access == PrivateAccess::Weakmap {
+            [EXPORTED_HELPERS, PRIVATE_STORAGE_HELPERS].concat()
+        } else {
+            EXPORTED_HELPERS.to_vec()
+        }
+    }
 }
 
+/// How transformed modules get the runtime helpers they call.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum HelperStrategy {
+    /// Prepended to every transformed module (the default).
+    Inline,
+    /// Imported from `module` (`external_helpers`, or `helpers` set to
+    /// `Import`).
+    Runtime { module: String },
+    /// Neither emitted nor imported (`shared_helpers`): the host provides
+    /// them once for a whole batch, e.g. as a shared chunk in scope.
+    Shared,
+}
+
+pub const VERSION: &str = env!("CARGO_PKG_VERSION");
+
 fn default_true() -> bool {
     true
 }
@@ -38,476 +354,3799 @@ pub fn transform(
     source_text: String,
     options: String,
 ) -> Result<TransformResult, String> {
+    transform_with_hook(&filename, &source_text, &options, None)
+}
+
+/// Like [`transform`] for hosts holding UTF-8 buffers: `source` and
+/// `options` are validated once and borrowed rather than copied into
+/// `String`s. The returned `code` converts to bytes with `into_bytes`
+/// without copying.
+pub fn transform_bytes(
+    filename: &str,
+    source: &[u8],
+    options: &[u8],
+) -> Result<TransformResult, String> {
+    let source_text =
+        std::str::from_utf8(source).map_err(|e| format!("Source is not valid UTF-8: {}", e))?;
+    let options =
+        std::str::from_utf8(options).map_err(|e| format!("Options are not valid UTF-8: {}", e))?;
+    transform_with_hook(filename, source_text, options, None)
+}
+
+/// Like [`transform`], calling `hook` for every decorated member so it can
+/// append extra fields to that member's descriptor.
+pub fn transform_with_descriptor_hook(
+    filename: String,
+    source_text: String,
+    options: String,
+    hook: &DescriptorHook<'_>,
+) -> Result<TransformResult, String> {
+    transform_with_hook(&filename, &source_text, &options, Some(hook))
+}
+
+/// Where the helpers module that [`transform_many`] emits belongs, relative
+/// to the root the batch's filenames are relative to.
+pub const BATCH_HELPERS_MODULE: &str = "decorator-helpers.js";
+
+/// Transforms a batch of `(filename, source)` files with the same options,
+/// returning their results in order followed by the [`helper_module`] for
+/// the options; each file's errors stay in its own result, and a file that
+/// fails outright fails the batch with its name. With inline helpers, ES
+/// modules import them from [`BATCH_HELPERS_MODULE`] by relative path, and
+/// scripts inline their own.
+pub fn transform_many(
+    files: Vec<(String, String)>,
+    options: String,
+) -> Result<Vec<TransformResult>, String> {
+    let opts = parse_options(&options)?;
+    let inline = opts.helper_strategy()? == HelperStrategy::Inline;
+    let mut results = files
+        .iter()
+        .map(|(filename, source_text)| {
+            let file_opts = if inline && resolve_source_type(filename, &opts).is_module() {
+                TransformOptions {
+                    external_helpers: Some(relative_specifier(filename, BATCH_HELPERS_MODULE)),
+                    ..opts.clone()
+                }
+            } else {
+                opts.clone()
+            };
+            transform_with_options(filename, source_text, &file_opts, None)
+                .map_err(|e| format!("{}: {}", filename, e))
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+    let helpers = helper_module(options)?;
+    results.push(TransformResult {
+        stats: TransformStats {
+            helpers_emitted: helper_function_count(&helpers),
+            ..TransformStats::default()
+        },
+        code: helpers,
+        map: None,
+        errors: vec![],
+        stripped_types: vec![],
+        metadata: None,
+        warnings: vec![],
+        diagnostics: vec![],
+    });
+    Ok(results)
+}
+
+// Import specifier for the module at `to` from the one at `from`, both
+// paths relative to the same root: `./b.js` for a sibling, `../lib/b.js`
+// across directories.
+fn relative_specifier(from: &str, to: &str) -> String {
+    let components = |path: &str| -> Vec<String> {
+        path.replace('\\', "/")
+            .split('/')
+            .filter(|component| *component != ".")
+            .map(str::to_string)
+            .collect()
+    };
+    let mut from_dir = components(from);
+    from_dir.pop();
+    let to = components(to);
+    let common = from_dir
+        .iter()
+        .zip(&to[..to.len() - 1])
+        .take_while(|(a, b)| a == b)
+        .count();
+    let prefix = match from_dir.len() - common {
+        0 => "./".to_string(),
+        up => "../".repeat(up),
+    };
+    format!("{}{}", prefix, to[common..].join("/"))
+}
+
+/// Keeps only the first copy of each helper block in `code` that several
+/// modules transformed with `dedupe_helpers_marker` were concatenated into.
+pub fn dedupe_helpers(code: String) -> String {
+    dedupe_marked_helpers(&code)
+}
+
+/// Source of the shared module that `external_helpers` imports from: the
+/// helpers for `options` (their `helper_version`, or `__decorate` with
+/// `legacy_decorators`) followed by an `export` of the ones transformed code
+/// calls.
+pub fn helper_module(options: String) -> Result<String, String> {
+    let opts = parse_options(&options)?;
+    let helpers = if opts.legacy_decorators {
+        format!("{}\n{}", legacy_helper_functions(), legacy_param_helper())
+    } else {
+        let helpers = helper_functions_for_version(opts.helper_version.as_deref())?;
+        match opts.private_access {
+            PrivateAccess::Native => helpers.to_string(),
+            PrivateAccess::Weakmap => format!("{}\n{}", helpers, private_storage_helpers()),
+        }
+    };
+    let module = shared_helper_module(&helpers, &opts.exported_helpers());
+    Ok(apply_newline(module, &opts))
+}
+
+/// SHA-256 (lowercase hex) of the `helper_module` source for `options`: the
+/// same for every call with the same helper set, so a host can key a cache
+/// or an integrity check on the shared helper chunk.
+pub fn helper_hash(options: String) -> Result<String, String> {
+    helper_module(options).map(|module| helper_module_hash(&module))
+}
+
+/// Runs the stage-3 and the legacy transform on a single parse of
+/// `source_text`, returning `(stage3, legacy)` so a migration can compare
+/// them. `legacy_decorators` in `options` is ignored.
+pub fn transform_dual(
+    filename: String,
+    source_text: String,
+    options: String,
+) -> Result<(TransformResult, TransformResult), String> {
     let opts = parse_options(&options)?;
+    let helpers = helper_functions_for_version(opts.helper_version.as_deref())?;
     let allocator = Allocator::default();
-    let source_type = SourceType::from_path(&filename).unwrap_or_default();
+    let source_type = resolve_source_type(&filename, &opts);
 
     let parser = Parser::new(&allocator, &source_text, source_type);
     let mut parse_result = parser.parse();
-
     if !parse_result.errors.is_empty() {
-        return Ok(TransformResult {
-            code: source_text.clone(),
-            map: None,
-            errors: parse_result
-                .errors
-                .iter()
-                .map(|e| format!("{:?}", e))
-                .collect(),
-        });
+        let result = opts.check_errors(parse_error_result(&source_text, &parse_result.errors))?;
+        return Ok((result.clone(), result));
     }
 
-    let mut transformer = DecoratorTransformer::new(&allocator);
-    if !transformer.check_for_decorators(&parse_result.program) {
-        return generate_result(&parse_result.program, &opts, vec![]);
-    }
-    let semantic = SemanticBuilder::new().build(&parse_result.program);
-    let scoping = semantic.semantic.into_scoping();
-    traverse_mut(
-        &mut transformer,
-        &allocator,
+    let stripped_types = match strip_types_if_enabled(&mut parse_result.program, &allocator, &opts)
+    {
+        Ok(stripped_types) => stripped_types,
+        Err(errors) => {
+            let result = opts.check_errors(error_result(&source_text, errors))?;
+            return Ok((result.clone(), result));
+        }
+    };
+    let mut legacy_program = parse_result.program.clone_in(&allocator);
+    let stage3_opts = TransformOptions {
+        legacy_decorators: false,
+        ..opts.clone()
+    };
+    let legacy_opts = TransformOptions {
+        legacy_decorators: true,
+        ..opts
+    };
+    let (stage3, _) = transform_program(
         &mut parse_result.program,
-        scoping,
-        TransformerState,
-    );
-    inject_variable_declarations_ast(&mut parse_result.program, &allocator);
-    let mut codegen_result = Codegen::new().build(&parse_result.program);
-    let class_decorator_info = transformer.get_class_decorator_strings();
-    if !class_decorator_info.is_empty() {
-        codegen_result.code =
-            apply_class_decorator_replacements_string(&codegen_result.code, &class_decorator_info);
-    }
-    if transformer.needs_helpers() {
-        codegen_result.code = format!("{}\n{}", generate_helper_functions(), codegen_result.code);
-    }
-    Ok(TransformResult {
-        code: codegen_result.code,
-        map: if opts.source_maps {
-            codegen_result.map.map(|m| m.to_json_string())
-        } else {
-            None
-        },
-        errors: transformer.errors,
-    })
+        &allocator,
+        &filename,
+        &stage3_opts,
+        helpers,
+        None,
+        stripped_types.clone(),
+    )?;
+    let (legacy, _) = transform_program(
+        &mut legacy_program,
+        &allocator,
+        &filename,
+        &legacy_opts,
+        helpers,
+        None,
+        stripped_types,
+    )?;
+    Ok((stage3, legacy))
 }
 
-fn inject_variable_declarations_ast<'a>(program: &mut Program<'a>, allocator: &'a Allocator) {
-    let ast = AstBuilder::new(allocator);
-    let mut insertions: Vec<(usize, Statement<'a>)> = Vec::new();
-    for (i, stmt) in program.body.iter().enumerate() {
-        let has_static_block = match stmt {
-            Statement::ClassDeclaration(class) => class_has_static_block(class),
-            Statement::ExportDefaultDeclaration(export) => {
-                matches!(&export.declaration, oxc_ast::ast::ExportDefaultDeclarationKind::ClassDeclaration(class) if class_has_static_block(class))
-            }
-            Statement::ExportNamedDeclaration(export) => {
-                matches!(&export.declaration, Some(Declaration::ClassDeclaration(class)) if class_has_static_block(class))
-            }
-            _ => false,
-        };
-        if has_static_block {
-            let var_decl = create_init_variables_declaration(&ast);
-            insertions.push((i, var_decl));
-        }
-    }
-    for (index, decl) in insertions.into_iter().rev() {
-        program.body.insert(index, decl);
+fn transform_with_hook(
+    filename: &str,
+    source_text: &str,
+    options: &str,
+    hook: Option<&DescriptorHook<'_>>,
+) -> Result<TransformResult, String> {
+    transform_with_options(filename, source_text, &parse_options(options)?, hook)
+}
+
+fn transform_with_options(
+    filename: &str,
+    source_text: &str,
+    opts: &TransformOptions,
+    hook: Option<&DescriptorHook<'_>>,
+) -> Result<TransformResult, String> {
+    let helpers = helper_functions_for_version(opts.helper_version.as_deref())?;
+    let allocator = Allocator::default();
+    let source_type = resolve_source_type(filename, opts);
+
+    let parser = Parser::new(&allocator, source_text, source_type);
+    let mut parse_result = parser.parse();
+
+    if !parse_result.errors.is_empty() {
+        return opts.check_errors(parse_error_result(source_text, &parse_result.errors));
     }
+
+    let stripped_types = match strip_types_if_enabled(&mut parse_result.program, &allocator, opts) {
+        Ok(stripped_types) => stripped_types,
+        Err(errors) => return opts.check_errors(error_result(source_text, errors)),
+    };
+    transform_program(
+        &mut parse_result.program,
+        &allocator,
+        filename,
+        opts,
+        helpers,
+        hook,
+        stripped_types,
+    )
+    .map(|(result, _)| result)
 }
 
-fn class_has_static_block(class: &oxc_ast::ast::Class) -> bool {
-    class
-        .body
-        .body
-        .iter()
-        .any(|element| matches!(element, ClassElement::StaticBlock(_)))
+fn parse_error_result(source_text: &str, errors: &[OxcDiagnostic]) -> TransformResult {
+    error_result(source_text, parse_error_messages(source_text, errors))
 }
 
-fn create_init_variables_declaration<'a>(ast: &AstBuilder<'a>) -> Statement<'a> {
-    let init_proto_binding = ast.binding_pattern(
-        ast.binding_pattern_kind_binding_identifier(SPAN, "_initProto"),
-        NONE,
-        false,
-    );
-    let init_proto_declarator = ast.variable_declarator(
-        SPAN,
-        VariableDeclarationKind::Let,
-        init_proto_binding,
-        None,
-        false,
-    );
-    let init_class_binding = ast.binding_pattern(
-        ast.binding_pattern_kind_binding_identifier(SPAN, "_initClass"),
-        NONE,
-        false,
-    );
-    let init_class_declarator = ast.variable_declarator(
-        SPAN,
-        VariableDeclarationKind::Let,
-        init_class_binding,
-        None,
-        false,
-    );
-    let mut declarators = ast.vec();
-    declarators.push(init_proto_declarator);
-    declarators.push(init_class_declarator);
-    let declaration =
-        ast.declaration_variable(SPAN, VariableDeclarationKind::Let, declarators, false);
-    Statement::from(declaration)
+// The source unchanged, with `errors` that kept it from being transformed.
+fn error_result(source_text: &str, errors: Vec<Located>) -> TransformResult {
+    let mut result = TransformResult {
+        code: source_text.to_string(),
+        map: None,
+        errors: vec![],
+        stripped_types: vec![],
+        stats: TransformStats::default(),
+        metadata: None,
+        warnings: vec![],
+        diagnostics: vec![],
+    };
+    result.report(source_text, errors, Severity::Error);
+    result
 }
 
-fn parse_options(options: &str) -> Result<TransformOptions, String> {
-    if options.is_empty() {
-        Ok(TransformOptions { source_maps: true })
-    } else {
-        serde_json::from_str(options).map_err(|e| format!("Invalid options: {}", e))
+// The parser's diagnostics, preceded by an explanation for each decorator
+// placed between `export` and what it exports, which the parser only
+// reports as a confusing token error.
+fn parse_error_messages(source_text: &str, errors: &[OxcDiagnostic]) -> Vec<Located> {
+    let mut messages = malformed_export_decorators(source_text);
+    messages.extend(errors.iter().map(|e| {
+        let offset = e
+            .labels
+            .as_ref()
+            .and_then(|labels| labels.first())
+            .map_or(0, |label| label.offset() as u32);
+        (e.message.to_string(), offset)
+    }));
+    messages
+}
+
+/// A message and the UTF-8 byte offset into the source it is about.
+pub(crate) type Located = (String, u32);
+
+impl TransformResult {
+    // Adds `messages` ahead of those already in `errors` or `warnings`, and
+    // to `diagnostics` with the line and column they point at, which stay
+    // in source order.
+    fn report(&mut self, source_text: &str, messages: Vec<Located>, severity: Severity) {
+        self.diagnostics.extend(messages.iter().map(|(message, offset)| {
+            let (line, column) = line_column(source_text, *offset);
+            TransformError {
+                message: message.clone(),
+                line,
+                column,
+                severity,
+            }
+        }));
+        self.diagnostics
+            .sort_by_key(|diagnostic| (diagnostic.line, diagnostic.column));
+        let list = match severity {
+            Severity::Error => &mut self.errors,
+            Severity::Warning => &mut self.warnings,
+        };
+        list.splice(0..0, messages.into_iter().map(|(message, _)| message));
     }
 }
 
-fn generate_result<'a>(
-    program: &Program<'a>,
-    opts: &TransformOptions,
-    errors: Vec<String>,
-) -> Result<TransformResult, String> {
-    let codegen_result = Codegen::new().build(program);
-    Ok(TransformResult {
-        code: codegen_result.code,
-        map: if opts.source_maps {
-            codegen_result.map.map(|m| m.to_json_string())
-        } else {
-            None
-        },
-        errors,
-    })
+// The 1-based line and 0-based column, in UTF-16 code units as editors and
+// source maps count them, of byte `offset` in `source_text`.
+fn line_column(source_text: &str, offset: u32) -> (u32, u32) {
+    let mut offset = (offset as usize).min(source_text.len());
+    while !source_text.is_char_boundary(offset) {
+        offset -= 1;
+    }
+    let before = &source_text[..offset];
+    let line_start = before.rfind('\n').map_or(0, |i| i + 1);
+    let line = before.matches('\n').count() + 1;
+    let column = before[line_start..].encode_utf16().count();
+    (line as u32, column as u32)
 }
 
-fn apply_class_decorator_replacements_string(
-    code: &str,
-    class_info: &[(String, Vec<String>)],
-) -> String {
-    let mut result = code.to_string();
-    for (class_name, decorator_strings) in class_info {
-        let decorators = decorator_strings.join(", ");
-        let export_default_pattern = format!("export default class {}", class_name);
-        if let Some(export_pos) = result.find(&export_default_pattern) {
-            if let Some(class_end) = find_class_end(&result, export_pos) {
-                let class_body_start = export_pos + export_default_pattern.len();
-                let before = result[..export_pos].to_string();
-                let class_body = result[class_body_start..class_end].to_string();
-                let after = result[class_end..].to_string();
-                result = format!(
-                    "{}let {} = class {}{}{}",
-                    before, class_name, class_name, class_body, after
-                );
-                let new_class_end = before.len()
-                    + format!("let {} = class {}{}", class_name, class_name, class_body).len();
-                let decorator_call = format!(
-                    ";\n{} = _applyDecs({}, [], [{}]).c[0];\nexport default {};",
-                    class_name, class_name, decorators, class_name
-                );
-                result.insert_str(new_class_end, &decorator_call);
-            }
+fn malformed_export_decorators(source_text: &str) -> Vec<Located> {
+    let bytes = source_text.as_bytes();
+    let is_ident = |b: u8| b.is_ascii_alphanumeric() || b == b'_' || b == b'$';
+    let skip_ws = |mut i: usize| {
+        while i < bytes.len() && bytes[i].is_ascii_whitespace() {
+            i += 1;
+        }
+        i
+    };
+    let mut messages = Vec::new();
+    for (start, _) in source_text.match_indices("export") {
+        let end = start + "export".len();
+        if (start > 0 && is_ident(bytes[start - 1])) || bytes.get(end).is_some_and(|&b| is_ident(b)) {
             continue;
         }
-        let export_pattern = format!("export class {}", class_name);
-        if let Some(export_pos) = result.find(&export_pattern) {
-            if let Some(class_end) = find_class_end(&result, export_pos) {
-                let class_body_start = export_pos + export_pattern.len();
-                let before = result[..export_pos].to_string();
-                let class_body = result[class_body_start..class_end].to_string();
-                let after = result[class_end..].to_string();
-                result = format!(
-                    "{}let {} = class {}{}{}",
-                    before, class_name, class_name, class_body, after
-                );
-                let new_class_end = before.len()
-                    + format!("let {} = class {}{}", class_name, class_name, class_body).len();
-                let decorator_call = format!(
-                    ";\n{} = _applyDecs({}, [], [{}]).c[0];\nexport {{ {} }};",
-                    class_name, class_name, decorators, class_name
-                );
-                result.insert_str(new_class_end, &decorator_call);
+        // Skip `@a.b(...)` decorators, balancing parentheses.
+        let mut i = skip_ws(end);
+        let decorators_start = i;
+        while bytes.get(i) == Some(&b'@') {
+            i += 1;
+            while i < bytes.len() && (is_ident(bytes[i]) || bytes[i] == b'.') {
+                i += 1;
+            }
+            if bytes.get(i) == Some(&b'(') {
+                let mut depth = 0;
+                while i < bytes.len() {
+                    match bytes[i] {
+                        b'(' => depth += 1,
+                        b')' => depth -= 1,
+                        _ => {}
+                    }
+                    i += 1;
+                    if depth == 0 {
+                        break;
+                    }
+                }
             }
+            i = skip_ws(i);
+        }
+        if i == decorators_start {
             continue;
         }
-        let class_pattern = format!("class {}", class_name);
-        if let Some(class_pos) = result.find(&class_pattern) {
-            if let Some(class_end) = find_class_end(&result, class_pos) {
-                result.insert_str(class_pos, &format!("let {} = ", class_name));
-                let insert_len = format!("let {} = ", class_name).len();
-                let new_class_end = class_end + insert_len;
-                let decorator_call = format!(
-                    ";\n{} = _applyDecs({}, [], [{}]).c[0];",
-                    class_name, class_name, decorators
-                );
-                result.insert_str(new_class_end, &decorator_call);
-            }
+        let word_end = (i..bytes.len()).find(|&j| !is_ident(bytes[j])).unwrap_or(bytes.len());
+        let decorators = source_text[decorators_start..i].trim_end();
+        match &source_text[i..word_end] {
+            "class" | "abstract" => {}
+            "default" => messages.push((
+                format!(
+                    "Decorators must follow `export default`, not precede `default` (at offset {start}): write `export default {decorators} class` or `{decorators} export default class`"
+                ),
+                start as u32,
+            )),
+            _ => messages.push((
+                format!(
+                    "Decorators after `export` (at offset {start}) can only decorate a class declaration: `{decorators}` is followed by `{}`",
+                    &source_text[i..word_end]
+                ),
+                start as u32,
+            )),
         }
     }
-    result
+    messages
 }
 
-fn find_class_end(code: &str, start_pos: usize) -> Option<usize> {
-    let class_code = &code[start_pos..];
-    let mut brace_count = 0;
-    let mut in_class = false;
-    for (i, ch) in class_code.char_indices() {
-        if ch == '{' {
-            in_class = true;
-            brace_count += 1;
-        } else if ch == '}' {
-            brace_count -= 1;
-            if in_class && brace_count == 0 {
-                return Some(start_pos + i + 1);
-            }
-        }
+fn strip_types_if_enabled<'a>(
+    program: &mut Program<'a>,
+    allocator: &'a Allocator,
+    opts: &TransformOptions,
+) -> Result<Vec<SourceSpan>, Vec<Located>> {
+    if !opts.strip_types {
+        return Ok(vec![]);
     }
-    None
+    let stripped = strip_types::strip_types(program, allocator)?;
+    Ok(stripped
+        .into_iter()
+        .map(|span| SourceSpan {
+            start: span.start,
+            end: span.end,
+        })
+        .collect())
 }
 
-struct Component;
+fn transform_program<'a>(
+    program: &mut Program<'a>,
+    allocator: &'a Allocator,
+    filename: &str,
+    opts: &TransformOptions,
+    helpers: &str,
+    hook: Option<&'a DescriptorHook<'a>>,
+    stripped_types: Vec<SourceSpan>,
+) -> Result<(TransformResult, Head), String> {
+    inject_auto_imports(program, allocator, opts)?;
+    // Checked before lowering, while the decorators are still in the AST.
+    let unresolved = if opts.check_decorator_bindings {
+        transformer::unresolved_decorators(program)
+    } else {
+        vec![]
+    };
+    let warnings = if opts.warn_decorator_order {
+        transformer::call_decorator_warnings(program)
+    } else {
+        vec![]
+    };
+    let metadata = opts
+        .emit_metadata
+        .then(|| serde_json::to_string(&metadata::collect_metadata(program)))
+        .transpose()
+        .map_err(|e| format!("Failed to serialize metadata: {}", e))?;
+    let (mut result, head) = lower_program(
+        program,
+        allocator,
+        filename,
+        opts,
+        helpers,
+        hook,
+        stripped_types,
+    )?;
+    result.report(program.source_text, unresolved, Severity::Error);
+    result.report(program.source_text, warnings, Severity::Warning);
+    result.metadata = metadata;
+    Ok((opts.check_errors(result)?, head))
+}
 
-impl Guest for Component {
-    fn transform(
-        filename: String,
-        source_text: String,
-        options: String,
-    ) -> Result<TransformResult, String> {
-        transform(filename, source_text, options)
+// Adds `import { name } from "module"` for each decorator named in
+// `auto_import` that the module uses without declaring or importing,
+// grouped per module in order of first use.
+fn inject_auto_imports<'a>(
+    program: &mut Program<'a>,
+    allocator: &'a Allocator,
+    opts: &TransformOptions,
+) -> Result<(), String> {
+    if opts.auto_import.is_empty() {
+        return Ok(());
+    }
+    let mut imports: Vec<(&str, Vec<&str>)> = Vec::new();
+    let names = transformer::unresolved_decorator_names(program);
+    for name in &names {
+        let Some(module) = opts.auto_import.get(name) else {
+            continue;
+        };
+        match imports.iter_mut().find(|(m, _)| m == module) {
+            Some((_, names)) => names.push(name),
+            None => imports.push((module, vec![name])),
+        }
+    }
+    if imports.is_empty() {
+        return Ok(());
     }
+    if !program.source_type.is_module() {
+        return Err("auto_import requires an ES module".to_string());
+    }
+    let ast = AstBuilder::new(allocator);
+    let declarations = imports.into_iter().map(|(module, names)| {
+        let specifiers = names.into_iter().map(|name| {
+            let name = ast.atom(name);
+            ast.import_declaration_specifier_import_specifier(
+                SPAN,
+                ast.module_export_name_identifier_name(SPAN, name),
+                ast.binding_identifier(SPAN, name),
+                ImportOrExportKind::Value,
+            )
+        });
+        Statement::from(ast.module_declaration_import_declaration(
+            SPAN,
+            Some(ast.vec_from_iter(specifiers)),
+            ast.string_literal(SPAN, ast.atom(module), None),
+            None,
+            NONE,
+            ImportOrExportKind::Value,
+        ))
+    });
+    program.body.splice(0..0, declarations.collect::<Vec<_>>());
+    Ok(())
 }
 
-#[cfg(test)]
-mod tests {
-    use super::*;
-
-    #[test]
-    fn test_basic_parsing() {
-        let code = "const x = 1;";
-        let result = transform("test.js".to_string(), code.to_string(), "{}".to_string());
-        assert!(result.is_ok());
+fn lower_program<'a>(
+    program: &mut Program<'a>,
+    allocator: &'a Allocator,
+    filename: &str,
+    opts: &TransformOptions,
+    helpers: &str,
+    hook: Option<&'a DescriptorHook<'a>>,
+    stripped_types: Vec<SourceSpan>,
+) -> Result<(TransformResult, Head), String> {
+    if opts.legacy_decorators {
+        return transform_program_legacy(program, allocator, filename, opts, stripped_types);
     }
+    let polyfill_metadata = opts.polyfill_symbol_metadata && uses_metadata(program);
+    let Some(transformer) = run_decorator_transform(program, allocator, opts, hook) else {
+        return Ok(generate_result(program, allocator, filename, opts, stripped_types));
+    };
+    let mut stats = transformer.stats;
+    let mut below = Vec::new();
+    if transformer.needs_helpers() {
+        if polyfill_metadata {
+            below.push(SYMBOL_METADATA_POLYFILL.to_string());
+        }
+        stats.helpers_emitted = push_helpers(
+            &mut below,
+            &emitted_helpers(helpers, &transformer),
+            &transformer.used_helpers(),
+            &transformer.helper_renames(),
+            program.source_type,
+            opts,
+        )?;
+    }
+    let head = Head::new(program, opts, true, below);
+    let (codegen_result, body_lines) = print_program(program, allocator, filename, opts, &head);
+    let mut result = finish_result(
+        codegen_result,
+        body_lines,
+        &transformer.get_class_name_spans(),
+        program,
+        opts,
+        transformer.errors,
+        stripped_types,
+    )?;
+    result.report(program.source_text, transformer.warnings, Severity::Warning);
+    result.stats = stats;
+    Ok((result, head))
+}
 
-    #[test]
-    fn test_class_decorator_detected() {
-        let code = r#"
-            function dec(target) { return target; }
-            @dec
-            class C {}
-        "#;
-        let result = transform("test.js".to_string(), code.to_string(), "{}".to_string());
-        assert!(result.is_ok());
-        if let Ok(res) = result {
-            assert!(res.code.contains("class C"));
-
-            assert_eq!(res.errors.len(), 0);
+/// Lines the output starts with, after any hashbang: `above` the directive
+/// prologue (the banner and a forced `"use strict"`) and `below` it (the
+/// `Symbol.metadata` polyfill and the helpers or their import).
+/// `transform_edits` inserts them at the same places in the source.
+#[derive(Default)]
+struct Head {
+    above: Vec<String>,
+    below: Vec<String>,
+}
 
-            assert!(!res.code.contains("@dec"));
+impl Head {
+    fn new(
+        program: &Program<'_>,
+        opts: &TransformOptions,
+        transformed: bool,
+        below: Vec<String>,
+    ) -> Self {
+        let mut above = Vec::new();
+        if let Some(banner) = &opts.banner {
+            above.push(banner.replace("{version}", VERSION));
         }
+        if transformed && needs_strict_directive(program, program.source_type, opts) {
+            above.push(USE_STRICT.to_string());
+        }
+        Head { above, below }
     }
+}
 
-    #[test]
-    fn test_decorator_removal() {
-        let code = r#"
-            @classDecorator
-            class MyClass {
-                @methodDecorator
-                method() {}
-                
-                @fieldDecorator
-                field = 1;
+fn join_lines(lines: &[String]) -> String {
+    let lines: Vec<&str> = lines.iter().map(|line| line.trim_end()).collect();
+    lines.join("\n")
+}
+
+// Prints `program` with `head` added, returning the code and the number of
+// lines printed for the program alone, for `output_map`.
+fn print_program<'a>(
+    program: &mut Program<'a>,
+    allocator: &'a Allocator,
+    filename: &str,
+    opts: &TransformOptions,
+    head: &Head,
+) -> (CodegenReturn, usize) {
+    let prologue = take_prologue(program, allocator, !head.below.is_empty());
+    let mut codegen_result = Codegen::new()
+        .with_options(source_map_codegen_options(filename, opts))
+        .build(program);
+    let body_lines = count_lines(&codegen_result.code);
+    if !head.below.is_empty() {
+        codegen_result.code = prepend_to_code(&codegen_result.code, &join_lines(&head.below));
+    }
+    restore_prologue(&mut codegen_result.code, program, prologue);
+    if !head.above.is_empty() {
+        codegen_result.code = prepend_to_code(&codegen_result.code, &join_lines(&head.above));
+    }
+    (codegen_result, body_lines)
+}
+
+// Takes the directive prologue out of `program` when lines will be printed
+// below it, since below them a directive such as `"use strict"` would be an
+// ordinary statement. `restore_prologue` puts it back above them.
+fn take_prologue<'a>(
+    program: &mut Program<'a>,
+    allocator: &'a Allocator,
+    prints_below: bool,
+) -> ArenaVec<'a, Directive<'a>> {
+    if !prints_below {
+        return ArenaVec::new_in(allocator);
+    }
+    std::mem::replace(&mut program.directives, ArenaVec::new_in(allocator))
+}
+
+fn restore_prologue<'a>(
+    code: &mut String,
+    program: &mut Program<'a>,
+    prologue: ArenaVec<'a, Directive<'a>>,
+) {
+    if prologue.is_empty() {
+        return;
+    }
+    let directives: Vec<String> = prologue
+        .iter()
+        .map(|directive| format!("{};", directive.expression.span.source_text(program.source_text)))
+        .collect();
+    *code = prepend_to_code(code, &directives.join("\n"));
+    program.directives = prologue;
+}
+
+// Adds the helper prelude per the helper strategy to `lines`, returning the
+// number of helper functions inlined.
+fn push_helpers(
+    lines: &mut Vec<String>,
+    helpers: &str,
+    used: &[&str],
+    renames: &[(&str, &str)],
+    source_type: SourceType,
+    opts: &TransformOptions,
+) -> Result<u32, String> {
+    let Some(prelude) = opts.helper_prelude(helpers, used, renames, source_type)? else {
+        return Ok(0);
+    };
+    lines.push(prelude);
+    Ok(match opts.helper_strategy()? {
+        HelperStrategy::Inline => helper_function_count(helpers),
+        _ => 0,
+    })
+}
+
+// The bundled helpers, followed by the WeakMap storage helpers when the
+// transformed code calls them, under the names the transformed code uses.
+fn emitted_helpers(helpers: &str, transformer: &DecoratorTransformer<'_>) -> String {
+    let helpers = if transformer.uses_private_storage() {
+        format!("{}\n{}", helpers, private_storage_helpers())
+    } else {
+        helpers.to_string()
+    };
+    rename_helpers(&helpers, &transformer.helper_renames())
+}
+
+fn transform_program_legacy<'a>(
+    program: &mut Program<'a>,
+    allocator: &'a Allocator,
+    filename: &str,
+    opts: &TransformOptions,
+    stripped_types: Vec<SourceSpan>,
+) -> Result<(TransformResult, Head), String> {
+    let renames = if opts.shared_helpers {
+        vec![]
+    } else {
+        legacy_helper_renames(program)
+    };
+    let renames: Vec<(&str, &str)> = renames
+        .iter()
+        .map(|(helper, name)| (*helper, name.as_str()))
+        .collect();
+    let mut outcome = legacy::transform_legacy(program, allocator, &renames);
+    let mut below = Vec::new();
+    if outcome.needs_helpers {
+        let mut helpers = legacy_helper_functions().to_string();
+        let mut used = vec!["__decorate"];
+        if outcome.needs_param_helper {
+            helpers.push('\n');
+            helpers.push_str(legacy_param_helper());
+            used.push("__param");
+        }
+        outcome.stats.helpers_emitted = push_helpers(
+            &mut below,
+            &rename_helpers(&helpers, &renames),
+            &used,
+            &renames,
+            program.source_type,
+            opts,
+        )?;
+    }
+    let head = Head::new(program, opts, true, below);
+    let (codegen_result, body_lines) = print_program(program, allocator, filename, opts, &head);
+    let mut result = finish_result(
+        codegen_result,
+        body_lines,
+        &[],
+        program,
+        opts,
+        outcome.errors,
+        stripped_types,
+    )?;
+    result.stats = outcome.stats;
+    Ok((result, head))
+}
+
+// Renames for the legacy helpers whose names the module already uses, as
+// `deconflict_helpers` does for the stage-3 ones.
+fn legacy_helper_renames(program: &Program<'_>) -> Vec<(&'static str, String)> {
+    let source_text = program.source_text;
+    if !LEGACY_EXPORTED_HELPERS.iter().any(|helper| source_text.contains(helper)) {
+        return vec![];
+    }
+    let scoping = SemanticBuilder::new().build(program).semantic.into_scoping();
+    transformer::deconflicted_helper_names(&scoping, LEGACY_EXPORTED_HELPERS)
+}
+
+fn finish_result(
+    codegen_result: CodegenReturn,
+    body_lines: usize,
+    class_names: &[(Span, String)],
+    program: &Program<'_>,
+    opts: &TransformOptions,
+    errors: Vec<Located>,
+    stripped_types: Vec<SourceSpan>,
+) -> Result<TransformResult, String> {
+    if opts.verify_output {
+        verify_generated_code(&codegen_result.code, program.source_type)?;
+    }
+    let map = output_map(&codegen_result, body_lines, class_names, program);
+    let mut result = TransformResult {
+        code: apply_newline(codegen_result.code, opts),
+        map,
+        errors: vec![],
+        stripped_types,
+        stats: TransformStats::default(),
+        metadata: None,
+        warnings: vec![],
+        diagnostics: vec![],
+    };
+    result.report(program.source_text, errors, Severity::Error);
+    Ok(result)
+}
+
+fn source_map_codegen_options(filename: &str, opts: &TransformOptions) -> CodegenOptions {
+    CodegenOptions {
+        source_map_path: opts.source_maps.then(|| PathBuf::from(filename)),
+        ..opts.codegen_options()
+    }
+}
+
+fn count_lines(code: &str) -> usize {
+    code.matches('\n').count()
+}
+
+// Everything added after codegen is prepended as whole lines (after any
+// hashbang), so the mappings only need moving down by the added line count.
+fn output_map(
+    codegen_result: &CodegenReturn,
+    body_lines: usize,
+    class_names: &[(Span, String)],
+    program: &Program<'_>,
+) -> Option<String> {
+    let map = codegen_result.map.as_ref()?;
+    let map = source_map::add_names(map, program.source_text, class_names);
+    let first_line = u32::from(program.hashbang.is_some());
+    let added_lines = count_lines(&codegen_result.code) - body_lines;
+    Some(source_map::shift_lines(&map, first_line, added_lines as u32).to_json_string())
+}
+
+/// Returns the edits that turn `source_text` into the transformed module,
+/// one per changed top-level statement plus the lines added at the top
+/// (helpers, banner, strict directive), so editors can patch large files
+/// incrementally. Ranges are UTF-8 byte offsets into the original source.
+/// The module goes through the same pipeline as in [`transform`], so every
+/// option applies.
+pub fn transform_edits(
+    filename: String,
+    source_text: String,
+    options: String,
+) -> Result<Vec<TextEdit>, String> {
+    let opts = parse_options(&options)?;
+    let helpers = helper_functions_for_version(opts.helper_version.as_deref())?;
+    let allocator = Allocator::default();
+    let source_type = resolve_source_type(&filename, &opts);
+    let mut parse_result = Parser::new(&allocator, &source_text, source_type).parse();
+    if !parse_result.errors.is_empty() {
+        let messages: Vec<String> = parse_error_messages(&source_text, &parse_result.errors)
+            .into_iter()
+            .map(|(message, _)| message)
+            .collect();
+        return Err(messages.join("\n"));
+    }
+
+    let original = parse_result.program.clone_in(&allocator);
+    let stripped_types = match strip_types_if_enabled(&mut parse_result.program, &allocator, &opts)
+    {
+        Ok(stripped_types) => stripped_types,
+        // `transform` leaves the source unchanged.
+        Err(errors) => {
+            opts.check_errors(error_result(&source_text, errors))?;
+            return Ok(Vec::new());
+        }
+    };
+    let (_, head) = transform_program(
+        &mut parse_result.program,
+        &allocator,
+        &filename,
+        &opts,
+        helpers,
+        None,
+        stripped_types,
+    )?;
+    Ok(program_edits(&original, &parse_result.program, &head, &opts))
+}
+
+// The edits turning the source of `original` into the output for `lowered`,
+// the same program after the transform: `head` inserted where `transform`
+// prints it, and each top-level statement that prints differently replaced
+// along with the statements the transform added next to it.
+fn program_edits(
+    original: &Program<'_>,
+    lowered: &Program<'_>,
+    head: &Head,
+    opts: &TransformOptions,
+) -> Vec<TextEdit> {
+    let source_text = original.source_text;
+    let mut edits = Vec::new();
+    let mut insert = |offset: usize, text: String| {
+        edits.push(TextEdit {
+            start: offset as u32,
+            end: offset as u32,
+            text: apply_newline(text, opts),
+        });
+    };
+    let after_hashbang = original.hashbang.as_ref().map_or(0, |hashbang| {
+        source_text[hashbang.span.end as usize..]
+            .find('\n')
+            .map_or(source_text.len(), |i| hashbang.span.end as usize + i + 1)
+    });
+    // The directive prologue only takes effect at the very start of the
+    // script, so the helpers go below it.
+    match original.directives.last() {
+        Some(directive) => {
+            if !head.above.is_empty() {
+                insert(after_hashbang, format!("{}\n", join_lines(&head.above)));
             }
-        "#;
+            if !head.below.is_empty() {
+                insert(directive.span.end as usize, format!("\n{}\n", join_lines(&head.below)));
+            }
+        }
+        None => {
+            let lines = [head.above.as_slice(), head.below.as_slice()].concat();
+            if !lines.is_empty() {
+                insert(after_hashbang, format!("{}\n", join_lines(&lines)));
+            }
+        }
+    }
+
+    let print = |stmt: &Statement| {
+        let mut codegen = Codegen::new().with_options(opts.codegen_options());
+        stmt.print(&mut codegen, Context::default());
+        codegen.into_source_text()
+    };
+    let unchanged: Vec<String> = original.body.iter().map(print).collect();
+    let index: HashMap<(u32, u32), usize> = original
+        .body
+        .iter()
+        .enumerate()
+        .map(|(i, stmt)| ((stmt.span().start, stmt.span().end), i))
+        .collect();
+    // The output of each original statement: the statements keeping its
+    // span, which a class rebound for its class decorators shares with what
+    // follows it, and the statements the transform added. Those join the
+    // previous statement when it changed, like the code applying decorators
+    // after a class, and the next one otherwise, like the declarations
+    // hoisted above a class.
+    let mut outputs = vec![String::new(); original.body.len()];
+    let mut added = vec![false; original.body.len()];
+    let mut pending = String::new();
+    let mut previous: Option<usize> = None;
+    for stmt in &lowered.body {
+        let printed = print(stmt);
+        let span = stmt.span();
+        match index.get(&(span.start, span.end)).filter(|_| !span.is_unspanned()) {
+            Some(&i) => {
+                if !pending.is_empty() {
+                    outputs[i].push_str(&std::mem::take(&mut pending));
+                    added[i] = true;
+                }
+                outputs[i].push_str(&printed);
+                previous = Some(i);
+            }
+            None => match previous {
+                Some(i) if outputs[i] != unchanged[i] => {
+                    outputs[i].push_str(&printed);
+                    added[i] = true;
+                }
+                _ => pending.push_str(&printed),
+            },
+        }
+    }
+    if !pending.is_empty() {
+        match previous {
+            Some(i) => {
+                outputs[i].push_str(&pending);
+                added[i] = true;
+            }
+            None => insert(source_text.len(), format!("\n{}", pending.trim_end())),
+        }
+    }
+    for (i, (stmt, output)) in original.body.iter().zip(outputs).enumerate() {
+        if !added[i] && output == unchanged[i] {
+            continue;
+        }
+        let range = decorated_statement_range(stmt).unwrap_or(stmt.span());
+        edits.push(TextEdit {
+            start: range.start,
+            end: range.end,
+            text: apply_newline(output.trim_end().to_string(), opts),
+        });
+    }
+    edits.sort_by_key(|edit| edit.start);
+    edits
+}
+
+// The original range of a top-level statement containing decorators,
+// widened to cover decorators written before `export`.
+fn decorated_statement_range(stmt: &Statement) -> Option<Span> {
+    let mut finder = DecoratorSpans::default();
+    finder.visit_statement(stmt);
+    finder
+        .spans
+        .into_iter()
+        .reduce(|a, b| a.merge(b))
+        .map(|decorators| decorators.merge(stmt.span()))
+}
+
+#[derive(Default)]
+struct DecoratorSpans {
+    spans: Vec<Span>,
+}
+
+impl<'a> Visit<'a> for DecoratorSpans {
+    fn visit_decorator(&mut self, decorator: &Decorator<'a>) {
+        self.spans.push(decorator.span);
+    }
+}
+
+/// Whether the module reads or writes decorator metadata, through
+/// `context.metadata` or `Symbol.metadata`. Decorators imported from other
+/// modules store their metadata without the polyfill, so it is only needed
+/// where the module itself touches `metadata`.
+fn uses_metadata(program: &Program) -> bool {
+    let mut finder = MetadataFinder::default();
+    finder.visit_program(program);
+    finder.found
+}
+
+#[derive(Default)]
+struct MetadataFinder {
+    found: bool,
+}
+
+impl<'a> Visit<'a> for MetadataFinder {
+    fn visit_static_member_expression(&mut self, it: &StaticMemberExpression<'a>) {
+        self.found |= it.property.name == "metadata";
+        walk::walk_static_member_expression(self, it);
+    }
+}
+
+fn resolve_source_type(filename: &str, opts: &TransformOptions) -> SourceType {
+    match opts.source_type {
+        Some(kind) => SourceType::from(kind),
+        None => SourceType::from_path(filename).unwrap_or_default(),
+    }
+}
+
+fn run_decorator_transform<'a>(
+    program: &mut Program<'a>,
+    allocator: &'a Allocator,
+    opts: &TransformOptions,
+    hook: Option<&'a DescriptorHook<'a>>,
+) -> Option<DecoratorTransformer<'a>> {
+    let mut transformer = DecoratorTransformer::new(allocator, opts.clone());
+    if let Some(hook) = hook {
+        transformer = transformer.with_descriptor_hook(hook);
+    }
+    if !transformer.check_for_decorators(program) {
+        return None;
+    }
+    let semantic = SemanticBuilder::new().build(program);
+    let scoping = semantic.semantic.into_scoping();
+    // Shared helpers are provided under their own names by the host.
+    if !opts.shared_helpers {
+        transformer.deconflict_helpers(&scoping);
+    }
+    traverse_mut(
+        &mut transformer,
+        allocator,
+        program,
+        scoping,
+        TransformerState,
+    );
+    inject_variable_declarations_ast(
+        program,
+        allocator,
+        transformer.take_hoisted_descriptors(),
+        transformer.take_class_temps(),
+        transformer.take_after_class_statements(),
+        opts.collapse_init_vars,
+    );
+    Some(transformer)
+}
+
+fn inject_variable_declarations_ast<'a>(
+    program: &mut Program<'a>,
+    allocator: &'a Allocator,
+    hoisted_descriptors: Vec<HoistedDescriptors<'a>>,
+    class_temps: Vec<ClassTemps<'a>>,
+    after_class_statements: Vec<AfterClassStatements<'a>>,
+    collapse_init_vars: bool,
+) {
+    let ast = AstBuilder::new(allocator);
+    // With `collapse_init_vars`, the temporaries of every class and the
+    // index of the first statement needing any.
+    let mut collapsed: Option<(usize, Vec<Atom<'a>>)> = None;
+    let mut hoisted_descriptors: Vec<_> = hoisted_descriptors.into_iter().map(Some).collect();
+    let mut after_class_statements: Vec<_> =
+        after_class_statements.into_iter().map(Some).collect();
+    // Classes nested in a block or function body get their temporaries
+    // declared there, next to the class.
+    let mut nested = NestedTempInjector {
+        ast,
+        class_temps: class_temps.into_iter().map(Some).collect(),
+    };
+    for stmt in program.body.iter_mut() {
+        nested.visit_statement(stmt);
+    }
+    let mut insertions: Vec<(usize, Statement<'a>)> = Vec::new();
+    for (i, stmt) in program.body.iter().enumerate() {
+        let stmt_span = stmt.span();
+        let temps = nested.claim_temps(stmt_span);
+        if collapse_init_vars && !temps.is_empty() {
+            let (_, all) = collapsed.get_or_insert_with(|| (i, Vec::new()));
+            for name in temps {
+                if !all.contains(&name) {
+                    all.push(name);
+                }
+            }
+        } else if !temps.is_empty() {
+            let var_decl = create_init_variables_declaration(&ast, &temps);
+            insertions.push((i, var_decl));
+        }
+        for slot in hoisted_descriptors.iter_mut() {
+            if slot
+                .as_ref()
+                .is_some_and(|h| spans_overlap(h.class_span, stmt_span))
+            {
+                if let Some(hoisted) = slot.take() {
+                    insertions.push((i, create_hoisted_descriptors_declaration(&ast, hoisted)));
+                }
+            }
+        }
+        for slot in after_class_statements.iter_mut() {
+            if slot
+                .as_ref()
+                .is_some_and(|a| spans_overlap(a.class_span, stmt_span))
+            {
+                if let Some(after) = slot.take() {
+                    insertions.extend(after.statements.into_iter().map(|stmt| (i + 1, stmt)));
+                }
+            }
+        }
+    }
+    if let Some((index, temps)) = collapsed {
+        let position = insertions.partition_point(|&(i, _)| i < index);
+        insertions.insert(position, (index, create_init_variables_declaration(&ast, &temps)));
+    }
+    for (index, decl) in insertions.into_iter().rev() {
+        program.body.insert(index, decl);
+    }
+}
+
+struct NestedTempInjector<'a> {
+    ast: AstBuilder<'a>,
+    class_temps: Vec<Option<ClassTemps<'a>>>,
+}
+
+impl<'a> NestedTempInjector<'a> {
+    // Takes the temporaries of the classes in the statement at `stmt_span`.
+    // Statements inside a class end before it does, so they claim nothing
+    // of that class.
+    fn claim_temps(&mut self, stmt_span: Span) -> Vec<Atom<'a>> {
+        let mut temps = Vec::new();
+        for slot in self.class_temps.iter_mut() {
+            if slot.as_ref().is_some_and(|t| {
+                spans_overlap(t.class_span, stmt_span) && t.class_span.end <= stmt_span.end
+            }) {
+                for name in slot.take().unwrap().names {
+                    if !temps.contains(&name) {
+                        temps.push(name);
+                    }
+                }
+            }
+        }
+        temps
+    }
+}
+
+impl<'a> VisitMut<'a> for NestedTempInjector<'a> {
+    fn visit_statements(&mut self, it: &mut ArenaVec<'a, Statement<'a>>) {
+        // Innermost lists first, so each class is claimed by the list that
+        // holds it.
+        walk_mut::walk_statements(self, it);
+        let mut insertions = Vec::new();
+        for (i, stmt) in it.iter().enumerate() {
+            let temps = self.claim_temps(stmt.span());
+            if !temps.is_empty() {
+                insertions.push((i, create_init_variables_declaration(&self.ast, &temps)));
+            }
+        }
+        for (index, decl) in insertions.into_iter().rev() {
+            it.insert(index, decl);
+        }
+    }
+    fn visit_arrow_function_expression(&mut self, it: &mut ArrowFunctionExpression<'a>) {
+        // A concise body is a single expression, with no room for a
+        // declaration; its classes are claimed by the enclosing statement.
+        if it.expression {
+            for stmt in it.body.statements.iter_mut() {
+                walk_mut::walk_statement(self, stmt);
+            }
+        } else {
+            walk_mut::walk_arrow_function_expression(self, it);
+        }
+    }
+}
+
+// A class decorated before `export` starts before the export statement, so
+// classes are matched to statements by overlap rather than containment.
+fn spans_overlap(a: Span, b: Span) -> bool {
+    a.start < b.end && b.start < a.end
+}
+
+fn create_hoisted_descriptors_declaration<'a>(
+    ast: &AstBuilder<'a>,
+    hoisted: HoistedDescriptors<'a>,
+) -> Statement<'a> {
+    let name = ast.allocator.alloc_str(&hoisted.name);
+    let binding = ast.binding_pattern(
+        ast.binding_pattern_kind_binding_identifier(SPAN, name),
+        NONE,
+        false,
+    );
+    let declarator = ast.variable_declarator(
+        SPAN,
+        VariableDeclarationKind::Const,
+        binding,
+        Some(hoisted.descriptors),
+        false,
+    );
+    let declaration = ast.declaration_variable(
+        SPAN,
+        VariableDeclarationKind::Const,
+        ast.vec1(declarator),
+        false,
+    );
+    Statement::from(declaration)
+}
+
+fn create_init_variables_declaration<'a>(
+    ast: &AstBuilder<'a>,
+    temps: &[Atom<'a>],
+) -> Statement<'a> {
+    let mut declarators = ast.vec();
+    for &name in temps {
+        let binding = ast.binding_pattern(
+            ast.binding_pattern_kind_binding_identifier(SPAN, name),
+            NONE,
+            false,
+        );
+        declarators.push(ast.variable_declarator(
+            SPAN,
+            VariableDeclarationKind::Let,
+            binding,
+            None,
+            false,
+        ));
+    }
+    let declaration =
+        ast.declaration_variable(SPAN, VariableDeclarationKind::Let, declarators, false);
+    Statement::from(declaration)
+}
+
+fn parse_options(options: &str) -> Result<TransformOptions, String> {
+    if options.is_empty() {
+        Ok(TransformOptions::default())
+    } else {
+        serde_json::from_str(options)
+            .map(TransformOptions::apply_spec_compliance)
+            .map_err(|e| format!("Invalid options: {}", e))
+    }
+}
+
+// The output for a program with nothing to decorate: only the banner is
+// added.
+fn generate_result<'a>(
+    program: &mut Program<'a>,
+    allocator: &'a Allocator,
+    filename: &str,
+    opts: &TransformOptions,
+    stripped_types: Vec<SourceSpan>,
+) -> (TransformResult, Head) {
+    let head = Head::new(program, opts, false, vec![]);
+    let (codegen_result, body_lines) = print_program(program, allocator, filename, opts, &head);
+    let map = output_map(&codegen_result, body_lines, &[], program);
+    let result = TransformResult {
+        code: apply_newline(codegen_result.code, opts),
+        map,
+        errors: vec![],
+        stripped_types,
+        stats: TransformStats::default(),
+        metadata: None,
+        warnings: vec![],
+        diagnostics: vec![],
+    };
+    (result, head)
+}
+
+// Inserts `prefix` on its own line(s) at the top of `code`, keeping a
+// hashbang line first so the output stays executable.
+fn prepend_to_code(code: &str, prefix: &str) -> String {
+    let split = if code.starts_with("#!") {
+        code.find('\n').map_or(code.len(), |i| i + 1)
+    } else {
+        0
+    };
+    let (hashbang, rest) = code.split_at(split);
+    let separator = if hashbang.is_empty() || hashbang.ends_with('\n') {
+        ""
+    } else {
+        "\n"
+    };
+    format!("{}{}{}\n{}", hashbang, separator, prefix.trim_end(), rest)
+}
+
+const USE_STRICT: &str = "\"use strict\";";
+
+// For `polyfill_symbol_metadata`: the key `_applyDecs` stores class
+// metadata under, for runtimes predating `Symbol.metadata`.
+const SYMBOL_METADATA_POLYFILL: &str = "Symbol.metadata ??= Symbol(\"Symbol.metadata\");";
+
+// Modules are always strict, and a script that already opts in keeps its own
+// directive.
+fn needs_strict_directive(
+    program: &Program<'_>,
+    source_type: SourceType,
+    opts: &TransformOptions,
+) -> bool {
+    opts.force_strict
+        && !source_type.is_module()
+        && !program
+            .directives
+            .iter()
+            .any(|d| d.directive.as_str() == "use strict")
+}
+
+// Converts bare `\n` line endings to `\r\n` for `newline: "crlf"`. Line
+// terminators inside template literals normalize back to `\n`, so their
+// values are unchanged.
+fn apply_newline(code: String, opts: &TransformOptions) -> String {
+    if opts.newline == Newline::Lf {
+        return code;
+    }
+    let mut converted = String::with_capacity(code.len() + code.len() / 16);
+    let mut prev = '\0';
+    for c in code.chars() {
+        if c == '\n' && prev != '\r' {
+            converted.push('\r');
+        }
+        converted.push(c);
+        prev = c;
+    }
+    converted
+}
+
+fn verify_generated_code(code: &str, source_type: SourceType) -> Result<(), String> {
+    let allocator = Allocator::default();
+    let parse_result = Parser::new(&allocator, code, source_type).parse();
+    let Some(error) = parse_result.errors.first() else {
+        return Ok(());
+    };
+    let offset = error
+        .labels
+        .as_ref()
+        .and_then(|labels| labels.first())
+        .map_or(0, |label| label.offset())
+        .min(code.len());
+    let line_start = code[..offset].rfind('\n').map_or(0, |i| i + 1);
+    let line_end = code[offset..].find('\n').map_or(code.len(), |i| offset + i);
+    Err(format!(
+        "Generated code failed to parse: {}\n  {}",
+        error.message,
+        code[line_start..line_end].trim()
+    ))
+}
+
+struct Component;
+
+impl Guest for Component {
+    fn transform(
+        filename: String,
+        source_text: String,
+        options: String,
+    ) -> Result<TransformResult, String> {
+        transform(filename, source_text, options)
+    }
+
+    fn transform_edits(
+        filename: String,
+        source_text: String,
+        options: String,
+    ) -> Result<Vec<TextEdit>, String> {
+        transform_edits(filename, source_text, options)
+    }
+
+    fn helper_module(options: String) -> Result<String, String> {
+        helper_module(options)
+    }
+
+    fn helper_hash(options: String) -> Result<String, String> {
+        helper_hash(options)
+    }
+
+    fn dedupe_helpers(code: String) -> String {
+        dedupe_helpers(code)
+    }
+
+    fn transform_many(
+        files: Vec<(String, String)>,
+        options: String,
+    ) -> Result<Vec<TransformResult>, String> {
+        transform_many(files, options)
+    }
+
+    fn transform_dual(
+        filename: String,
+        source_text: String,
+        options: String,
+    ) -> Result<(TransformResult, TransformResult), String> {
+        transform_dual(filename, source_text, options)
+    }
+
+    fn transform_bytes(
+        filename: String,
+        source: Vec<u8>,
+        options: Vec<u8>,
+    ) -> Result<TransformResult, String> {
+        transform_bytes(&filename, &source, &options)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_basic_parsing() {
+        let code = "const x = 1;";
+        let result = transform("test.js".to_string(), code.to_string(), "{}".to_string());
+        assert!(result.is_ok());
+    }
+
+    #[test]
+    fn test_class_decorator_detected() {
+        let code = r#"
+            function dec(target) { return target; }
+            @dec
+            class C {}
+        "#;
+        let result = transform("test.js".to_string(), code.to_string(), "{}".to_string());
+        assert!(result.is_ok());
+        if let Ok(res) = result {
+            assert!(res.code.contains("class C"));
+
+            assert_eq!(res.errors.len(), 0);
+
+            assert!(!res.code.contains("@dec"));
+        }
+    }
+
+    #[test]
+    fn test_decorator_removal() {
+        let code = r#"
+            @classDecorator
+            class MyClass {
+                @methodDecorator
+                method() {}
+                
+                @fieldDecorator
+                field = 1;
+            }
+        "#;
+        let result = transform("test.js".to_string(), code.to_string(), "{}".to_string());
+        assert!(result.is_ok());
+        if let Ok(res) = result {
+            assert!(!res.code.contains("@classDecorator"));
+            assert!(!res.code.contains("@methodDecorator"));
+            assert!(!res.code.contains("@fieldDecorator"));
+
+            assert!(res.code.contains("class MyClass"));
+            assert!(res.code.contains("method()"));
+            assert!(res.code.contains("field = _init_field(this, 1)"));
+            assert_eq!(res.errors.len(), 0);
+        }
+    }
+
+    #[test]
+    fn test_complex_decorator_scenario() {
+        let code = r#"
+            function logged(value, context) {
+                console.log(`Decorating ${context.name}`);
+                return value;
+            }
+            
+            function bound(value, context) {
+                return value;
+            }
+            
+            @logged
+            class Controller {
+                @logged
+                static staticMethod() {
+                    return 'static';
+                }
+                
+                @bound
+                @logged
+                instanceMethod() {
+                    return 'instance';
+                }
+                
+                @logged
+                get value() {
+                    return this._value;
+                }
+                
+                @logged
+                set value(v) {
+                    this._value = v;
+                }
+                
+                @logged
+                accessor data = 42;
+                
+                @logged
+                #privateMethod() {
+                    return 'private';
+                }
+            }
+        "#;
+
+        let result = transform("test.js".to_string(), code.to_string(), "{}".to_string());
+
+        assert!(result.is_ok());
+        if let Ok(res) = result {
+            assert!(!res.code.contains("@logged"));
+            assert!(!res.code.contains("@bound"));
+
+            assert!(res.code.contains("class Controller"));
+            assert!(res.code.contains("static staticMethod()"));
+            assert!(res.code.contains("instanceMethod()"));
+            assert!(res.code.contains("get value()"));
+            assert!(res.code.contains("set value("));
+            assert!(res.code.contains("get data()"));
+            assert!(res.code.contains("set data(v)"));
+            assert!(res.code.contains("#privateMethod()"));
+
+            assert!(res.code.contains("function logged"));
+            assert!(res.code.contains("function bound"));
+
+            assert_eq!(res.errors.len(), 0);
+        }
+    }
+
+    #[test]
+    fn test_helper_injection_on_decorator_presence() {
+        let code = r#"
+            function logged(value, { kind, name }) {
+                if (kind === "method") {
+                    return function (...args) {
+                        console.log(`calling ${name}`);
+                        return value.call(this, ...args);
+                    };
+                }
+            }
+
+            class C {
+                @logged
+                m(arg) {
+                    return arg * 2;
+                }
+            }
+        "#;
+
+        let result = transform("test.js".to_string(), code.to_string(), "{}".to_string());
+
+        assert!(result.is_ok());
+        if let Ok(res) = result {
+            assert!(res.code.contains("function _applyDecs"));
+            assert!(res.code.contains("function _toPropertyKey"));
+            assert!(res.code.contains("function _toPrimitive"));
+            assert!(res.code.contains("function _setFunctionName"));
+            assert!(res.code.contains("function _checkInRHS"));
+
+            assert!(res.code.contains("static {"));
+            assert!(res.code.contains("_applyDecs(this"));
+            assert!(res.code.contains("[_initProto] = _applyDecs(this"));
+
+            assert!(res.code.contains("class C"));
+            assert!(res.code.contains("function logged"));
+            assert!(!res.code.contains("@logged")); // Decorator syntax removed
+
+            assert_eq!(res.errors.len(), 0);
+        }
+    }
+
+    #[test]
+    fn test_no_helper_injection_without_decorators() {
+        let code = r#"
+            class C {
+                m(arg) {
+                    return arg * 2;
+                }
+            }
+        "#;
+
+        let result = transform("test.js".to_string(), code.to_string(), "{}".to_string());
+
+        assert!(result.is_ok());
+        if let Ok(res) = result {
+            assert!(!res.code.contains("function _applyDecs"));
+            assert!(!res.code.contains("function _toPropertyKey"));
+
+            assert!(res.code.contains("class C"));
+
+            assert_eq!(res.errors.len(), 0);
+        }
+    }
+
+    #[test]
+    fn test_method_decorator_detected() {
+        let code = "class C { @dec method() {} }";
+        let result = transform("test.js".to_string(), code.to_string(), "{}".to_string());
+        assert!(result.is_ok());
+    }
+
+    #[test]
+    fn test_verify_output_accepts_valid_transform() {
+        let code = "@dec export default class C { @dec m() {} }";
+        let result = transform(
+            "test.js".to_string(),
+            code.to_string(),
+            r#"{"verify_output": true}"#.to_string(),
+        );
+        assert!(result.is_ok(), "Valid output should pass verification");
+    }
+
+    #[test]
+    fn test_verify_output_reports_invalid_splice() {
+        let spliced = "let _initProto, _initClass;\nexport default let C = class C {};\n";
+        let err = verify_generated_code(spliced, SourceType::mjs())
+            .expect_err("Invalid splice should be detected");
+        assert!(err.contains("Generated code failed to parse"));
+        assert!(
+            err.contains("export default let C = class C {};"),
+            "Error should include the offending snippet: {}",
+            err
+        );
+    }
+
+    #[test]
+    fn test_hoist_descriptors_shares_identical_arrays() {
+        let code = r#"
+class A { @dec m() {} }
+class B { @dec m() {} }
+class D { @other m() {} }
+"#;
+        let result = transform(
+            "test.js".to_string(),
+            code.to_string(),
+            r#"{"hoist_descriptors": true}"#.to_string(),
+        );
+
+        assert!(result.is_ok());
+        if let Ok(res) = result {
+            assert_eq!(res.code.matches("const _descriptors =").count(), 1);
+            assert_eq!(
+                res.code.matches("_applyDecs(this, _descriptors,").count(),
+                2
+            );
+            assert!(res.code.contains("const _descriptors2 ="));
+            assert_eq!(
+                res.code.matches("_applyDecs(this, _descriptors2,").count(),
+                1
+            );
+
+            let decl_pos = res.code.find("const _descriptors =").unwrap();
+            let class_pos = res.code.find("class A").unwrap();
+            assert!(decl_pos < class_pos, "Constant must be declared before use");
+        }
+    }
+
+    #[test]
+    fn test_hoist_descriptors_keeps_evaluation_order() {
+        let code = r#"const order = [];
+const log = (name) => { order.push(name); return (value) => value; };
+const plain = (value) => value;
+class A extends (order.push("extends"), Object) { @log("m") m() {} }
+class B { @plain m() {} @plain [order.push("key")]() {} }
+class C { @plain m() {} }
+console.log(JSON.stringify(order));
+"#;
+        let res = transform(
+            "test.js".to_string(),
+            code.to_string(),
+            r#"{"hoist_descriptors": true}"#.to_string(),
+        )
+        .unwrap();
+        assert!(res.errors.is_empty(), "{:?}", res.errors);
+        // Only `C`'s decorators can be evaluated before the class: `A`'s is a
+        // call and `B` assigns its computed key's temporaries in the body.
+        assert_eq!(res.code.matches("_applyDecs(this, _descriptors, [])").count(), 1);
+        assert!(res.code.contains("const _descriptors = [[\n\tplain,"), "{}", res.code);
+        assert!(res.code.contains("\t\t[_initProto] = _applyDecs(this, [[\n\t\t\tlog(\"m\"),"));
+
+        let Ok(output) = std::process::Command::new("node").arg("-e").arg(&res.code).output()
+        else {
+            eprintln!("node is not installed, skipping");
+            return;
+        };
+        assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+        assert_eq!(String::from_utf8_lossy(&output.stdout).trim_end(), r#"["extends","m","key"]"#);
+    }
+
+    #[test]
+    fn test_source_type_override_without_extension() {
+        let code = r#"
+@dec
+class View {
+    @dec
+    render(): JSX.Element {
+        return <div class="view" />;
+    }
+}
+"#;
+        let result = transform(
+            "virtual-module".to_string(),
+            code.to_string(),
+            r#"{"source_type": "tsx"}"#.to_string(),
+        );
+
+        assert!(result.is_ok());
+        if let Ok(res) = result {
+            assert_eq!(res.errors.len(), 0, "Should parse as TSX: {:?}", res.errors);
+            assert!(res.code.contains("render(): JSX.Element"));
+            assert!(res.code.contains("<div class=\"view\" />"));
+            assert!(!res.code.contains("@dec"));
+        }
+    }
+
+    #[test]
+    fn test_banner_after_hashbang() {
+        let code = "#!/usr/bin/env node\n@dec class C {}\n";
+        let result = transform(
+            "test.js".to_string(),
+            code.to_string(),
+            r#"{"banner": "/* transformed by vite-oxc-decorator-stage-3 v{version} */"}"#
+                .to_string(),
+        );
+
+        assert!(result.is_ok());
+        if let Ok(res) = result {
+            let mut lines = res.code.lines();
+            assert_eq!(lines.next(), Some("#!/usr/bin/env node"));
+            assert_eq!(
+                lines.next().unwrap(),
+                format!(
+                    "/* transformed by vite-oxc-decorator-stage-3 v{} */",
+                    VERSION
+                )
+            );
+            let helpers_pos = res.code.find("function _applyDecs").unwrap();
+            let banner_pos = res.code.find("/* transformed by").unwrap();
+            assert!(banner_pos < helpers_pos);
+        }
+    }
+
+    #[test]
+    fn test_force_strict_for_script() {
+        let code = "#!/usr/bin/env node\n@dec class C {}\n";
+        let result = transform(
+            "test.cjs".to_string(),
+            code.to_string(),
+            r#"{"force_strict": true, "banner": "/* banner */"}"#.to_string(),
+        );
+
+        assert!(result.is_ok());
+        if let Ok(res) = result {
+            let mut lines = res.code.lines();
+            assert_eq!(lines.next(), Some("#!/usr/bin/env node"));
+            assert_eq!(lines.next(), Some("/* banner */"));
+            assert_eq!(lines.next(), Some("\"use strict\";"));
+            assert_eq!(res.code.matches("use strict").count(), 1);
+        }
+    }
+
+    #[test]
+    fn test_force_strict_skips_modules_and_undecorated_scripts() {
+        let options = r#"{"force_strict": true}"#.to_string();
+        let module = transform(
+            "test.mjs".to_string(),
+            "@dec class C {}\n".to_string(),
+            options.clone(),
+        )
+        .unwrap();
+        assert!(!module.code.contains("use strict"));
+
+        let plain = transform(
+            "test.cjs".to_string(),
+            "class C {}\n".to_string(),
+            options.clone(),
+        )
+        .unwrap();
+        assert!(!plain.code.contains("use strict"));
+
+        let default = transform(
+            "test.cjs".to_string(),
+            "@dec class C {}\n".to_string(),
+            "{}".to_string(),
+        )
+        .unwrap();
+        assert!(!default.code.contains("use strict"));
+    }
+
+    #[test]
+    fn test_helpers_go_below_directive_prologue() {
+        let code = "\"use strict\";\n@dec class C {}\n";
+        for options in ["{}", r#"{"force_strict": true}"#, r#"{"legacy_decorators": true}"#] {
+            let res =
+                transform("test.cjs".to_string(), code.to_string(), options.to_string()).unwrap();
+            assert!(res.errors.is_empty(), "{:?}", res.errors);
+            assert!(res.code.starts_with("\"use strict\";\n/**"), "{}", res.code);
+            assert_eq!(res.code.matches("use strict").count(), 1);
+        }
+
+        let edits =
+            transform_edits("test.cjs".to_string(), code.to_string(), "{}".to_string()).unwrap();
+        let mut patched = code.to_string();
+        for edit in edits.iter().rev() {
+            patched.replace_range(edit.start as usize..edit.end as usize, &edit.text);
+        }
+        assert!(patched.starts_with("\"use strict\";\n/**"), "{}", patched);
+    }
+
+    #[test]
+    fn test_pinned_helper_version() {
+        let code = "@dec class C {}\n";
+        let res = transform(
+            "test.js".to_string(),
+            code.to_string(),
+            r#"{"helper_version": "2023-05"}"#.to_string(),
+        )
+        .unwrap();
+        let pinned = codegen::helper_functions_for_version(Some("2023-05")).unwrap();
+        assert!(res.code.starts_with(pinned.trim_end()));
+        assert!(res.code.contains("function _applyDecs(\n  targetClass,"));
+
+        let err = transform(
+            "test.js".to_string(),
+            code.to_string(),
+            r#"{"helper_version": "1999-01"}"#.to_string(),
+        )
+        .unwrap_err();
+        assert!(err.contains("Unknown helper_version \"1999-01\""));
+    }
+
+    #[test]
+    fn test_strip_types_reports_stripped_spans() {
+        let code = "class C {\n  @dec field: number = 1;\n}\n";
+        let res = transform(
+            "test.ts".to_string(),
+            code.to_string(),
+            r#"{"strip_types": true}"#.to_string(),
+        )
+        .unwrap();
+        assert!(res.errors.is_empty(), "errors: {:?}", res.errors);
+        assert!(!res.code.contains(": number"));
+        assert_eq!(res.stripped_types.len(), 1);
+        let span = &res.stripped_types[0];
+        assert_eq!(&code[span.start as usize..span.end as usize], ": number");
+
+        let res = transform("test.ts".to_string(), code.to_string(), "{}".to_string()).unwrap();
+        assert!(res.stripped_types.is_empty());
+        assert!(res.code.contains("field: number"));
+    }
+
+    #[test]
+    fn test_strip_types_lowers_parameter_properties() {
+        let code = "class A {\n    constructor(private a: number, readonly b = 2, c?: string) {}\n}\n\
+                    class B extends A {\n    @dec m() {}\n    constructor(public override x: number) {\n        \
+                    log();\n        super(x);\n    }\n}\n";
+        let res = transform(
+            "test.ts".to_string(),
+            code.to_string(),
+            r#"{"strip_types": true}"#.to_string(),
+        )
+        .unwrap();
+        assert!(res.errors.is_empty(), "errors: {:?}", res.errors);
+        assert!(res
+            .code
+            .contains("\tconstructor(a, b = 2, c) {\n\t\tthis.a = a;\n\t\tthis.b = b;\n\t}"));
+        let derived = &res.code[res.code.find("class B").unwrap()..];
+        let ctor = &derived[derived.find("constructor(x)").unwrap()..];
+        assert!(ctor.contains("log();\n\t\tsuper(x);\n"), "{}", ctor);
+        assert!(
+            ctor.contains("super(x);\n\t\tthis.x = x;\n\t\tif (_initProto) _initProto(this);\n\t}"),
+            "{}",
+            ctor
+        );
+
+        let code = "class B extends A {\n    constructor(private x: number) {\n        \
+                    if (x) super();\n    }\n}\n";
+        let res = transform(
+            "test.ts".to_string(),
+            code.to_string(),
+            r#"{"strip_types": true}"#.to_string(),
+        )
+        .unwrap();
+        assert_eq!(
+            res.errors,
+            ["Parameter properties of a derived class need a `super()` call at the top level \
+              of its constructor"]
+        );
+        assert_eq!(res.code, code);
+    }
+
+    #[test]
+    fn test_strip_types_parameter_properties_before_initializers() {
+        let code = r#"const log = [];
+const dec = (_, context) => { context.addInitializer(function () { log.push(this.a); }); };
+class C {
+    @dec m() {}
+    constructor(private a: number) {}
+}
+new C(1);
+console.log(JSON.stringify(log));
+"#;
+        let res = transform(
+            "test.ts".to_string(),
+            code.to_string(),
+            r#"{"strip_types": true}"#.to_string(),
+        )
+        .unwrap();
+        assert!(res.errors.is_empty(), "errors: {:?}", res.errors);
+        assert!(
+            res.code.contains(
+                "\tconstructor(a) {\n\t\tthis.a = a;\n\t\tif (_initProto) _initProto(this);\n\t}"
+            ),
+            "{}",
+            res.code
+        );
+
+        // The initializer added by `@dec` sees the parameter property.
+        let Ok(output) = std::process::Command::new("node").arg("-e").arg(&res.code).output()
+        else {
+            eprintln!("node is not installed, skipping");
+            return;
+        };
+        assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+        assert_eq!(String::from_utf8_lossy(&output.stdout).trim_end(), "[1]");
+    }
+
+    #[test]
+    fn test_strip_types_elides_type_only_imports() {
+        let code = "import { type T } from \"./t.js\";\nimport { type U, v } from \"./v.js\";\n\
+                    import {} from \"./side.js\";\nimport \"./effect.js\";\n@dec class C {}\n";
+        let res = transform(
+            "test.ts".to_string(),
+            code.to_string(),
+            r#"{"strip_types": true}"#.to_string(),
+        )
+        .unwrap();
+        assert!(res.errors.is_empty(), "errors: {:?}", res.errors);
+        assert!(!res.code.contains("./t.js"), "{}", res.code);
+        assert!(res.code.contains("import { v } from \"./v.js\";"), "{}", res.code);
+        assert!(res.code.contains("import {} from \"./side.js\";"), "{}", res.code);
+        assert!(res.code.contains("import \"./effect.js\";"), "{}", res.code);
+        let stripped: Vec<&str> = res
+            .stripped_types
+            .iter()
+            .map(|span| &code[span.start as usize..span.end as usize])
+            .collect();
+        assert_eq!(stripped, ["import { type T } from \"./t.js\";", "type U"]);
+    }
+
+    #[test]
+    fn test_strip_types_rejects_runtime_typescript() {
+        let run = |code: &str| {
+            transform(
+                "test.ts".to_string(),
+                code.to_string(),
+                r#"{"strip_types": true}"#.to_string(),
+            )
+            .unwrap()
+        };
+        let res = run("enum E { A }\nexport const enum F { B = 1 }\n@dec class C {}\n");
+        assert_eq!(
+            res.errors,
+            [
+                "strip_types cannot remove enums, which have a runtime meaning",
+                "strip_types cannot remove enums, which have a runtime meaning"
+            ]
+        );
+        let lines: Vec<u32> = res.diagnostics.iter().map(|d| d.line).collect();
+        assert_eq!(lines, [1, 2]);
+        assert!(res.code.starts_with("enum E { A }"));
+
+        let res = run("namespace N { export const x = 1; }\nimport fs = require(\"fs\");\n");
+        assert_eq!(res.errors.len(), 2, "{:?}", res.errors);
+        assert!(res.errors[0].contains("namespaces with values"));
+        assert!(res.errors[1].contains("`import =` declarations"));
+
+        // Ambient and type-only declarations are still removed.
+        let res = run("declare enum D { A }\nnamespace T { export type X = 1; }\nconst a = 1;\n");
+        assert!(res.errors.is_empty(), "{:?}", res.errors);
+        assert_eq!(res.code, "const a = 1;\n");
+    }
+
+    #[test]
+    fn test_nested_destructuring_in_class_decorated_method() {
+        let code = r#"
+@dec
+class C {
+    @log m({ a: { b: [c, { d = "}" }] } }, e = `${ { f: 1 }.f }}`) {
+        const { g: { h: { i } } } = this; // }
+        const [{ j }, [{ k }]] = [{ j: 1 }, [{ k: 2 }]];
+        return { b, c, d, e, i, j, k };
+    }
+}
+const after = 1;
+"#;
+        let res = transform(
+            "test.js".to_string(),
+            code.to_string(),
+            r#"{"verify_output": true}"#.to_string(),
+        )
+        .unwrap();
+        assert!(res.errors.is_empty(), "errors: {:?}", res.errors);
+        let class_code = &res.code[res.code.find("let C = class C").unwrap()..];
+        assert!(
+            class_code.contains("};\n[C, _classInit] = _applyDecs(C, [], [dec]).c;\n_classInit();\nconst after = 1;"),
+            "{class_code}"
+        );
+        assert!(class_code.contains("const { g: { h: { i } } } = this;"));
+    }
+
+    #[test]
+    fn test_check_decorator_bindings() {
+        let code = r#"
+import { inject } from "di";
+function log(value) { return value; }
+class C {
+    @loged m() {}
+    @log n() {}
+    @inject("a") x;
+    @config.value y;
+}
+"#;
+        let res = transform(
+            "test.js".to_string(),
+            code.to_string(),
+            r#"{"check_decorator_bindings": true}"#.to_string(),
+        )
+        .unwrap();
+        assert_eq!(res.errors.len(), 2, "errors: {:?}", res.errors);
+        assert!(res.errors[0].contains("Decorator \"loged\" at offset"));
+        assert!(res.errors[1].contains("Decorator \"config\" at offset"));
+        assert!(res.code.contains("_applyDecs(this"));
+
+        let res = transform("test.js".to_string(), code.to_string(), "{}".to_string()).unwrap();
+        assert!(res.errors.is_empty(), "errors: {:?}", res.errors);
+    }
+
+    #[test]
+    fn test_class_with_thousands_of_decorated_members() {
+        let mut code = String::from("@dec\nclass Big {\n");
+        for i in 0..2000 {
+            match i % 4 {
+                0 => code.push_str(&format!("    @dec m{i}() {{}}\n")),
+                1 => code.push_str(&format!("    @dec f{i} = {i};\n")),
+                2 => code.push_str(&format!("    @dec static accessor a{i};\n")),
+                _ => code.push_str(&format!("    @dec [\"k{i}\"]() {{}}\n")),
+            }
+        }
+        code.push_str("}\n");
+
+        let start = std::time::Instant::now();
+        let res = transform("test.js".to_string(), code, "{}".to_string()).unwrap();
+        assert!(start.elapsed() < std::time::Duration::from_secs(10));
+        assert!(res.errors.is_empty(), "{:?}", res.errors);
+        assert_eq!(res.stats.member_decorators, 2000);
+        assert!(res.code.contains("\"m1996\""));
+        assert!(res.code.contains("f1997 = _init_f"));
+        assert!(res.code.contains("[Big, _classInit] = _applyDecs(Big, [], [dec]).c;\n_classInit();"));
+    }
+
+    #[test]
+    fn test_emit_metadata_sidecar() {
+        let code = r#"
+@register("users")
+class Users {
+    @route("/list") list() {}
+    @inject static db;
+    @observed accessor #count = 0;
+    @validate set name(v) {}
+}
+const Anonymous = @tag class {};
+"#;
+        let res = transform(
+            "test.js".to_string(),
+            code.to_string(),
+            r#"{"emit_metadata":true}"#.to_string(),
+        )
+        .unwrap();
+        assert!(res.errors.is_empty(), "{:?}", res.errors);
+        let metadata: serde_json::Value = serde_json::from_str(&res.metadata.unwrap()).unwrap();
+        let classes = metadata["classes"].as_array().unwrap();
+        assert_eq!(classes.len(), 2);
+
+        let users = &classes[0];
+        assert_eq!(users["name"], "Users");
+        assert_eq!(users["decorators"], serde_json::json!([r#"register("users")"#]));
+        let start = users["span"]["start"].as_u64().unwrap() as usize;
+        assert!(code[start..].starts_with("@register(\"users\")\nclass Users"));
+        let members: Vec<_> = users["members"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|m| {
+                (
+                    m["name"].as_str().unwrap(),
+                    m["kind"].as_str().unwrap(),
+                    m["static"].as_bool().unwrap(),
+                    m["private"].as_bool().unwrap(),
+                )
+            })
+            .collect();
+        assert_eq!(
+            members,
+            [
+                ("list", "method", false, false),
+                ("db", "field", true, false),
+                ("count", "accessor", false, true),
+                ("name", "setter", false, false),
+            ]
+        );
+        assert_eq!(users["members"][0]["decorators"], serde_json::json!([r#"route("/list")"#]));
+
+        assert_eq!(classes[1]["name"], serde_json::Value::Null);
+        assert_eq!(classes[1]["decorators"], serde_json::json!(["tag"]));
+
+        let res = transform("test.js".to_string(), code.to_string(), "{}".to_string()).unwrap();
+        assert!(res.metadata.is_none());
+    }
+
+    #[test]
+    fn test_metadata_decorator_sources_are_verbatim() {
+        let code = "class Widget {\n    @dec( import.meta.hot ) m() {}\n    @dec(import.meta.hot)\n    static n() {}\n}\n";
+        let res = transform(
+            "test.js".to_string(),
+            code.to_string(),
+            r#"{"emit_metadata":true}"#.to_string(),
+        )
+        .unwrap();
+        assert!(res.errors.is_empty(), "{:?}", res.errors);
+        let metadata: serde_json::Value = serde_json::from_str(&res.metadata.unwrap()).unwrap();
+        let members = &metadata["classes"][0]["members"];
+        assert_eq!(
+            members[0]["decorator_sources"],
+            serde_json::json!(["@dec( import.meta.hot )"])
+        );
+        assert_eq!(
+            members[1]["decorator_sources"],
+            serde_json::json!(["@dec(import.meta.hot)"])
+        );
+        assert_eq!(members[1]["decorators"], serde_json::json!(["dec(import.meta.hot)"]));
+        assert_eq!(metadata["classes"][0]["decorator_sources"], serde_json::json!([]));
+    }
+
+    #[test]
+    fn test_auto_import_global_decorators() {
+        let code = r#"
+import { log } from "./log.js";
+
+@inject
+class Service {
+    @log m() {}
+    @inject.optional dep;
+}
+"#;
+        let options = r#"{"auto_import": {"inject": "di", "log": "./other.js"}}"#;
+        let res = transform("test.js".to_string(), code.to_string(), options.to_string()).unwrap();
+        assert!(res.errors.is_empty(), "{:?}", res.errors);
+        assert_eq!(res.code.matches("import { inject } from \"di\";").count(), 1);
+        assert!(!res.code.contains("./other.js"));
+        assert!(res.code.contains("import { log } from \"./log.js\";"));
+
+        let res = transform(
+            "test.cjs".to_string(),
+            code.replace("import { log } from \"./log.js\";", "").to_string(),
+            options.to_string(),
+        );
+        assert_eq!(res.unwrap_err(), "auto_import requires an ES module");
+    }
+
+    #[test]
+    fn test_mixed_tab_and_space_indentation() {
+        let code = "function make() {\n\t@dec\n  \tclass Inner {\n  \t\t@dec m() {}\n\t    }\n\treturn Inner;\n}\n\t@dec\n    class Top {\n\t  x = 1;\n    }\n";
+        let res = transform("test.js".to_string(), code.to_string(), "{}".to_string()).unwrap();
+        assert!(res.errors.is_empty(), "{:?}", res.errors);
+        // The bundled helpers keep their own formatting.
+        let module_code = &res.code[res.code.find("function make").unwrap()..];
+        for line in module_code.lines() {
+            let indent = &line[..line.len() - line.trim_start().len()];
+            assert!(indent.chars().all(|c| c == '\t'), "mixed indentation: {line:?}");
+        }
+        assert!(res.code.contains("\n\tlet Inner = class Inner {"));
+        assert!(res.code.contains(
+            "\n\t};\n\t[Inner, _classInit] = _applyDecs(Inner, [], [dec]).c;\n\t_classInit();\n\treturn Inner;"
+        ));
+        assert!(res
+            .code
+            .contains("\n};\n[Top, _classInit2] = _applyDecs(Top, [], [dec]).c;\n_classInit2();"));
+    }
+
+    #[test]
+    fn test_warn_decorator_order() {
+        let code = r#"
+class C {
+    @sideEffect() m() {}
+    @identity n() {}
+}
+"#;
+        let options = r#"{"warn_decorator_order": true}"#;
+        let res = transform("test.js".to_string(), code.to_string(), options.to_string()).unwrap();
+        assert!(res.errors.is_empty(), "{:?}", res.errors);
+        assert_eq!(res.warnings.len(), 1, "{:?}", res.warnings);
+        assert!(res.warnings[0].starts_with("Decorator \"sideEffect()\" at offset 15 is a call"));
+        assert!(!res.warnings.iter().any(|w| w.contains("identity")));
+
+        let res = transform("test.js".to_string(), code.to_string(), "{}".to_string()).unwrap();
+        assert!(res.warnings.is_empty());
+    }
+
+    #[test]
+    fn test_aliased_decorator_imports() {
+        let code = "import { dec as d, other as o } from \"m\";\nimport * as ns from \"n\";\n@o class C { @d m() {} @ns.dec x = 1; }\n";
+        let res = transform(
+            "test.js".to_string(),
+            code.to_string(),
+            r#"{"check_decorator_bindings": true}"#.to_string(),
+        )
+        .unwrap();
+        assert!(res.errors.is_empty(), "{:?}", res.errors);
+        // Descriptors use the local names, never the imported ones.
+        let compact: String = res.code.split_whitespace().collect();
+        assert!(compact.contains("[[d,2,\"m\",false],[ns.dec,0,\"x\",false]]"), "{}", res.code);
+        assert!(res.code.contains("[C, _classInit] = _applyDecs(C, [], [o]).c;\n_classInit();"));
+        let class_code = &res.code[res.code.find("let C").unwrap()..];
+        assert!(!class_code.contains("\tdec,") && !class_code.contains("other"));
+    }
+
+    #[test]
+    fn test_diagnostics_locate_errors() {
+        let code = "const a = 1;\nconst é = \"ü\"; let b = ;\n";
+        let res = transform("test.js".to_string(), code.to_string(), "{}".to_string()).unwrap();
+        assert_eq!(res.errors.len(), 1, "{:?}", res.errors);
+        assert_eq!(res.diagnostics.len(), 1);
+        let diagnostic = &res.diagnostics[0];
+        assert_eq!(diagnostic.message, "Unexpected token");
+        assert_eq!(res.errors[0], "Unexpected token");
+        assert_eq!(diagnostic.severity, Severity::Error);
+        // The `;` after `let b =`, in UTF-16 columns.
+        assert_eq!((diagnostic.line, diagnostic.column), (2, 23));
+
+        let code = "class C {\n    @sideEffect() m() {}\n    @missing n() {}\n}\n";
+        let options = r#"{"warn_decorator_order": true, "check_decorator_bindings": true}"#;
+        let res = transform("test.js".to_string(), code.to_string(), options.to_string()).unwrap();
+        let located: Vec<_> = res
+            .diagnostics
+            .iter()
+            .map(|d| (d.severity, d.line, d.column))
+            .collect();
+        assert_eq!(
+            located,
+            [
+                (Severity::Error, 2, 4),
+                (Severity::Warning, 2, 4),
+                (Severity::Error, 3, 4)
+            ],
+            "{:?}",
+            res.diagnostics
+        );
+        assert_eq!(res.warnings.len(), 1);
+        assert_eq!(res.errors.len(), 2);
+    }
+
+    #[test]
+    fn test_documented_method_decorator_output() {
+        let readme = include_str!("../README.md");
+        let section = &readme[readme.find("## Chaining With oxc Transforms").unwrap()..];
+        let start = section.find("```js\n").unwrap() + "```js\n".len();
+        let documented = &section[start..start + section[start..].find("```").unwrap()];
+
+        let res = transform(
+            "test.js".to_string(),
+            "class C { @dec m() {} }".to_string(),
+            r#"{"external_helpers": "./helpers.js"}"#.to_string(),
+        )
+        .unwrap();
+        assert!(res.errors.is_empty(), "{:?}", res.errors);
+        assert_eq!(res.code, documented);
+    }
+
+    #[test]
+    fn test_output_is_stable_across_runs() {
+        let code = r#"
+@register
+export class A {
+    @log [key]() {}
+    @log static s = 1;
+    @bound #p() {}
+    @observe accessor x;
+}
+class B {
+    @log [key]() {}
+    @log static s = 1;
+}
+const C = @register class {};
+"#;
+        let options = r#"{"hoist_descriptors": true, "external_helpers": "./helpers.js"}"#;
+        let run = || {
+            let res =
+                transform("test.js".to_string(), code.to_string(), options.to_string()).unwrap();
+            assert!(res.errors.is_empty(), "errors: {:?}", res.errors);
+            (res.code, res.map)
+        };
+        let first = run();
+        for _ in 0..5 {
+            assert_eq!(run(), first);
+        }
+    }
+
+    #[test]
+    fn test_crlf_newline() {
+        let code = "// banner\r\nclass C {\n  @dec m() {\n    return `a\r\nb`;\n  }\n}\n";
+        let res = transform(
+            "test.js".to_string(),
+            code.to_string(),
+            r#"{"newline": "crlf"}"#.to_string(),
+        )
+        .unwrap();
+        assert!(res.errors.is_empty(), "errors: {:?}", res.errors);
+        assert!(res.code.contains("function _applyDecs("));
+        assert!(res.code.contains("\r\n"));
+        assert!(!res.code.replace("\r\n", "").contains('\n'));
+        assert!(!res.code.contains("\r\r"));
+
+        let res = transform("test.js".to_string(), code.to_string(), "{}".to_string()).unwrap();
+        assert!(res.code.contains("_applyDecs(this, [[\n"));
+    }
+
+    #[test]
+    fn test_transform_bytes_matches_string_api() {
+        let code = "@sealed\nclass Café {\n  @log m() {}\n}\n";
+        let options = r#"{"source_maps": true}"#;
+        let expected =
+            transform("test.js".to_string(), code.to_string(), options.to_string()).unwrap();
+        let res = transform_bytes("test.js", code.as_bytes(), options.as_bytes()).unwrap();
+        assert_eq!(res.code, expected.code);
+        assert_eq!(res.map, expected.map);
+        assert_eq!(res.errors, expected.errors);
+
+        let err = transform_bytes("test.js", b"class C {}\xff", b"{}").unwrap_err();
+        assert!(err.contains("Source is not valid UTF-8"), "{err}");
+        let err = transform_bytes("test.js", b"class C {}", b"\xff").unwrap_err();
+        assert!(err.contains("Options are not valid UTF-8"), "{err}");
+    }
+
+    #[test]
+    fn test_transform_stats() {
+        let code = r#"
+@register
+class C {
+    @log m() {}
+    @observe field = 1;
+}
+class Plain {}
+"#;
+        let res = transform("test.js".to_string(), code.to_string(), "{}".to_string()).unwrap();
+        assert!(res.errors.is_empty(), "errors: {:?}", res.errors);
+        assert_eq!(res.stats.classes_transformed, 1);
+        assert_eq!(res.stats.member_decorators, 2);
+        assert_eq!(res.stats.class_decorators, 1);
+        assert_eq!(res.stats.helpers_emitted, 5);
+
+        let res = transform(
+            "test.js".to_string(),
+            "class Plain {}".to_string(),
+            "{}".to_string(),
+        )
+        .unwrap();
+        assert_eq!(res.stats.classes_transformed, 0);
+        assert_eq!(res.stats.helpers_emitted, 0);
+    }
+
+    #[test]
+    fn test_strip_types_decorated_readonly_array_field() {
+        let code = "class C {\n  @dec readonly items: readonly string[] = [];\n}\n";
+        let res = transform(
+            "test.ts".to_string(),
+            code.to_string(),
+            r#"{"strip_types": true}"#.to_string(),
+        )
+        .unwrap();
+        assert!(res.errors.is_empty(), "errors: {:?}", res.errors);
+        let class_code = &res.code[res.code.find("class C").unwrap()..];
+        assert!(class_code.contains("\titems = _init_items(this, []);"));
+        assert!(!class_code.contains("readonly"));
+        assert!(!class_code.contains("string"));
+        let compact: String = class_code.split_whitespace().collect();
+        assert!(compact.contains(r#"[dec,0,"items",false]"#));
+        assert!(res
+            .stripped_types
+            .iter()
+            .any(|span| &code[span.start as usize..span.end as usize] == ": readonly string[]"));
+    }
+
+    #[test]
+    fn test_node_compat_replaces_static_blocks() {
+        let code = "class C {\n    #_ = 1;\n    @dec m() {}\n    static { init(this); }\n}\n";
+        let res = transform(
+            "test.js".to_string(),
+            code.to_string(),
+            r#"{"node_compat": true}"#.to_string(),
+        )
+        .unwrap();
+        assert!(res.errors.is_empty(), "{:?}", res.errors);
+        let class_code = &res.code[res.code.find("class C").unwrap()..];
+        assert!(!class_code.contains("static {"), "{}", class_code);
+        assert!(
+            class_code.contains("\tstatic #_2 = (() => {\n\t\t[_initProto] = _applyDecs(this, [["),
+            "{}",
+            class_code
+        );
+        assert!(class_code.contains("\tstatic #_3 = (() => {\n\t\tinit(this);\n\t})();"));
+
+        let res = transform("test.js".to_string(), code.to_string(), "{}".to_string()).unwrap();
+        assert!(res.code.contains("\tstatic {\n\t\t[_initProto] = _applyDecs(this, [["));
+    }
+
+    #[test]
+    fn test_lazy_decorators() {
+        let code = "class A {\n    @dec m() {}\n}\nclass B {\n    @dec static s() {}\n}\n";
+        let res = transform(
+            "test.js".to_string(),
+            code.to_string(),
+            r#"{"lazy_decorators": true, "hoist_descriptors": true}"#.to_string(),
+        )
+        .unwrap();
+        assert!(res.errors.is_empty(), "{:?}", res.errors);
+        let class_b_start = res.code.find("class B").unwrap();
+        let class_a = &res.code[res.code.find("class A").unwrap()..class_b_start];
+        assert!(
+            class_a.contains(
+                "\tstatic {\n\t\t_initProto = (_instance) => {\n\t\t\t_initProto = void 0;\n\t\t\t[_initProto] = _applyDecs(this, [["
+            ),
+            "{}",
+            class_a
+        );
+        assert!(class_a.contains("\t\t\tif (_initProto) _initProto(_instance);\n\t\t};\n\t}"));
+        assert!(class_a.contains("constructor() {\n\t\tif (_initProto) _initProto(this);"));
+
+        // Static members are read before any construction.
+        let class_b = &res.code[class_b_start..];
+        assert!(
+            class_b.contains("\tstatic {\n\t\t[, _initClass] = _applyDecs(this, _descriptors, []).e;"),
+            "{}",
+            class_b
+        );
+        assert_eq!(
+            res.warnings,
+            ["Class at offset 28 is decorated when it is defined: lazy_decorators only defers \
+              classes whose only decorated members are instance methods, getters and setters"]
+        );
+    }
+
+    #[test]
+    fn test_lazy_decorators_fall_back_for_fields() {
+        let code = "class A {\n    @dec m() {}\n    @dec x = 1;\n}\n";
+        let res = transform(
+            "test.js".to_string(),
+            code.to_string(),
+            r#"{"lazy_decorators": true}"#.to_string(),
+        )
+        .unwrap();
+        assert!(res.errors.is_empty(), "{:?}", res.errors);
+        // The field initializer is read on construction, so `A` is decorated
+        // eagerly, as without the option.
+        let eager = transform("test.js".to_string(), code.to_string(), "{}".to_string()).unwrap();
+        assert_eq!(res.code, eager.code);
+        assert!(!res.code.contains("_instance"), "{}", res.code);
+        assert!(res.code.contains("\tstatic {\n\t\t[_init_x, , _initProto] = _applyDecs(this, [["));
+        assert_eq!(res.warnings.len(), 1, "{:?}", res.warnings);
+        assert!(res.warnings[0].starts_with("Class at offset 0 is decorated when it is defined"));
+        assert_eq!((res.diagnostics[0].line, res.diagnostics[0].column), (1, 0));
+        assert!(eager.warnings.is_empty());
+    }
+
+    #[test]
+    fn test_member_installation_define_property() {
+        let code = r#"
+class C extends B {
+    @dec m() {}
+    @dec get x() { return 1; }
+    set x(v) {}
+    @dec static s() {}
+    @dec viaSuper() { return super.m(); }
+    plain() {}
+}
+"#;
+        let res = transform(
+            "test.js".to_string(),
+            code.to_string(),
+            r#"{"member_installation": "define-property"}"#.to_string(),
+        )
+        .unwrap();
+        assert!(res.errors.is_empty(), "{:?}", res.errors);
+        let class_code = &res.code[res.code.find("class C").unwrap()..];
+        assert!(class_code.contains(
+            "Object.defineProperty(this.prototype, \"m\", {\n\t\t\t...Object.getOwnPropertyDescriptor({ m() {} }, \"m\"),\n\t\t\tenumerable: false\n\t\t});"
+        ), "{}", class_code);
+        assert!(class_code.contains("Object.defineProperty(this.prototype, \"x\", {"));
+        assert!(class_code.contains("get x() {\n\t\t\t\t\treturn 1;\n\t\t\t\t},\n\t\t\t\tset x(v) {}"));
+        assert!(class_code.contains("Object.defineProperty(this, \"s\", {"));
+        // Installed before `_applyDecs` looks them up.
+        assert!(class_code.find("Object.defineProperty") < class_code.find("_applyDecs"));
+        // `super` needs the class as home object.
+        assert!(class_code.contains("\tviaSuper() {\n\t\treturn super.m();"));
+        assert!(!class_code.contains("\"viaSuper\", {"));
+        assert!(class_code.contains("\tplain() {}"));
+        assert!(!class_code.contains("\tm() {}"));
+        assert!(!class_code.contains("\tstatic s() {}"));
+    }
+
+    #[test]
+    fn test_tsx_method_returning_jsx() {
+        let code = r#"
+class Card {
+    @bound render(): JSX.Element {
+        return <div className="card">{this.title}<span /></div>;
+    }
+}
+"#;
+        for options in [
+            r#"{"verify_output": true}"#,
+            r#"{"verify_output": true, "strip_types": true}"#,
+        ] {
+            let res =
+                transform("Card.tsx".to_string(), code.to_string(), options.to_string()).unwrap();
+            assert!(res.errors.is_empty(), "{:?}", res.errors);
+            assert!(res.code.contains("[[\n\t\t\tbound,\n\t\t\t2,\n\t\t\t\"render\","));
+            assert!(
+                res.code.contains("return <div className=\"card\">{this.title}<span /></div>;"),
+                "{}",
+                res.code
+            );
+            assert!(!res.code.contains("@bound"));
+        }
+    }
+
+    #[test]
+    fn test_typescript_signatures_survive_decoration() {
+        let code = r#"
+export class Service<T> {
+    @dec private readonly name: string = "x";
+    @dec accessor count: number = 0;
+    @dec protected fetch(id: number, opts?: { retry: boolean }): Promise<T> { return load(id); }
+    @dec static create<U>(this: void, value: U): Service<U> { return make(value); }
+    @dec get label(): string { return this.name; }
+    @dec set label(v: string) {}
+}
+"#;
+        let res = transform(
+            "service.ts".to_string(),
+            code.to_string(),
+            r#"{"verify_output": true}"#.to_string(),
+        )
+        .unwrap();
+        assert!(res.errors.is_empty(), "{:?}", res.errors);
+        let class_code = &res.code[res.code.find("export class Service<T>").unwrap()..];
+        for signature in [
+            "\tprivate readonly name: string = _init_name(this, \"x\");",
+            "\t#count_accessor_storage: number = _init_count(this, 0);",
+            "\tget count(): number {",
+            "\tset count(v: number) {",
+            "\tprotected fetch(id: number, opts?: {\n\t\tretry: boolean;\n\t}): Promise<T> {",
+            "\tstatic create<U>(this: void, value: U): Service<U> {",
+            "\tget label(): string {",
+            "\tset label(v: string) {}",
+        ] {
+            assert!(class_code.contains(signature), "{signature}\n{class_code}");
+        }
+        assert!(!class_code.contains("@dec"));
+    }
+
+    #[test]
+    fn test_sequence_expression_decorators() {
+        let code = "@(log(), dec) class C { @(log(), dec) m() {} }\n";
+        let res = transform("test.js".to_string(), code.to_string(), "{}".to_string()).unwrap();
+        assert!(res.errors.is_empty(), "{:?}", res.errors);
+        assert!(res.code.contains("[[\n\t\t\t(log(), dec),\n\t\t\t2,"), "{}", res.code);
+        assert!(res.code.contains("[C, _classInit] = _applyDecs(C, [], [(log(), dec)]).c;\n_classInit();"));
+        assert!(!res.code.contains("log(), dec]"));
+    }
+
+    #[test]
+    fn test_strip_types_assertions_in_decorator_arguments() {
+        let code = r#"
+@dec(config satisfies Config)
+class C {
+    @dec(x as Y) m() {}
+    @dec(<any>z, w!) n() {}
+    @dec([config satisfies Config]) [key as string]() {}
+}
+"#;
+        let res = transform(
+            "test.ts".to_string(),
+            code.to_string(),
+            r#"{"strip_types": true}"#.to_string(),
+        )
+        .unwrap();
+        assert!(res.errors.is_empty(), "errors: {:?}", res.errors);
+        let class_code = &res.code[res.code.find("let C").unwrap()..];
+        for ts in ["satisfies", " as ", "<any>", "!"] {
+            assert!(!class_code.contains(ts), "{ts:?} left in {class_code}");
+        }
+        let compact: String = class_code.split_whitespace().collect();
+        assert!(compact.contains(r#"[dec(x),2,"m",false]"#));
+        assert!(compact.contains(r#"[dec(z,w),2,"n",false]"#));
+        assert!(compact.contains("_dec=dec([config]),_computedKey=_toPropertyKey(key)"));
+        assert!(compact.contains("[C,_classInit]=_applyDecs(C,[],[dec(config)]).c;_classInit();"));
+    }
+
+    #[test]
+    fn test_single_quote_style() {
+        let code = "class C {\n  @dec m() {}\n}\n";
+        let res = transform(
+            "test.js".to_string(),
+            code.to_string(),
+            r#"{"quote_style": "single"}"#.to_string(),
+        )
+        .unwrap();
+        let class_code = &res.code[res.code.find("class C").unwrap()..];
+        let compact: String = class_code.split_whitespace().collect();
+        assert!(compact.contains("[dec,2,'m',false]"), "{}", class_code);
+
+        let res = transform("test.js".to_string(), code.to_string(), "{}".to_string()).unwrap();
+        let compact: String = res.code.split_whitespace().collect();
+        assert!(compact.contains(r#"[dec,2,"m",false]"#));
+    }
+
+    #[test]
+    fn test_decorated_abstract_class() {
+        let code = "@dec abstract class C {\n  @log m() {}\n  abstract n(): void;\n}\n";
+        let res = transform("test.ts".to_string(), code.to_string(), "{}".to_string()).unwrap();
+        assert!(res.errors.is_empty(), "errors: {:?}", res.errors);
+        let class_code = &res.code[res.code.find("let C = class C").unwrap()..];
+        assert!(!class_code.contains("abstract"));
+        assert!(class_code.contains("[C, _classInit] = _applyDecs(C, [], [dec]).c;\n_classInit();"));
+        assert!(class_code.contains("m() {}"));
+    }
+
+    #[test]
+    fn test_string_literal_key_with_space() {
+        let code = "class C {\n  @dec \"my method\"() {}\n}\n";
+        let res = transform("test.js".to_string(), code.to_string(), "{}".to_string()).unwrap();
+        let class_code = &res.code[res.code.find("class C").unwrap()..];
+        let compact: String = class_code.split_whitespace().collect();
+        assert!(compact.contains(r#"[dec,2,"mymethod",false]"#));
+        assert!(class_code.contains(r#""my method","#));
+        assert!(class_code.contains(r#""my method"() {}"#));
+    }
+
+    #[test]
+    fn test_self_referencing_static_computed_key() {
+        let code = "class C {\n  static KEY = \"k\";\n  @dec static [C.KEY]() {}\n}\n";
+        let res = transform("test.js".to_string(), code.to_string(), "{}".to_string()).unwrap();
+        assert_eq!(res.errors.len(), 1);
+        assert!(res.errors[0].contains("references class \"C\""));
+        assert!(!res.code.contains("_applyDecs(this"));
+
+        let code = "const KEY = \"k\";\nclass C {\n  @dec static [KEY]() {}\n}\n";
+        let res = transform("test.js".to_string(), code.to_string(), "{}".to_string()).unwrap();
+        assert!(res.errors.is_empty());
+    }
+
+    #[test]
+    fn test_error_mode() {
+        let code = "class C {\n  static KEY = \"k\";\n  @dec static [C.KEY]() {}\n}\nclass D {\n  static KEY = \"k\";\n  @dec static [D.KEY]() {}\n}\n";
+        let collect = transform(
+            "test.js".to_string(),
+            code.to_string(),
+            r#"{"error_mode": "collect"}"#.to_string(),
+        )
+        .unwrap();
+        assert_eq!(collect.errors.len(), 2);
+
+        let fail_fast = r#"{"error_mode": "fail-fast"}"#;
+        let err = transform("test.js".to_string(), code.to_string(), fail_fast.to_string())
+            .unwrap_err();
+        assert_eq!(err, collect.errors[0]);
+        let err = transform_edits("test.js".to_string(), code.to_string(), fail_fast.to_string())
+            .unwrap_err();
+        assert_eq!(err, collect.errors[0]);
+        let err = transform("test.js".to_string(), "class {".to_string(), fail_fast.to_string())
+            .unwrap_err();
+        assert!(!err.is_empty());
+
+        let res = transform(
+            "test.js".to_string(),
+            "class C { @dec m() {} }".to_string(),
+            fail_fast.to_string(),
+        )
+        .unwrap();
+        assert!(res.errors.is_empty());
+    }
+
+    #[test]
+    fn test_class_decorator_initializers_run() {
+        let code = r#"function register(value, context) {
+    context.addInitializer(function () { registry.add(this); });
+}
+@register class A { static x = 1; }
+const B = @register class {};
+"#;
+        let res = transform("test.js".to_string(), code.to_string(), "{}".to_string()).unwrap();
+        assert!(res.errors.is_empty(), "{:?}", res.errors);
+        // The initializer runs once `A` is rebound to the decorated class.
+        assert!(res.code.contains("let _classInit;\nlet A = class A {"), "{}", res.code);
+        assert!(res
+            .code
+            .contains("[A, _classInit] = _applyDecs(A, [], [register]).c;\n_classInit();\n"));
+        // A class expression runs it before yielding the decorated class.
+        assert!(res.code.contains(
+            "const B = ([_B, _classInit2] = _applyDecs(_setFunctionName(class {}, \"B\"), [], [register]).c, _classInit2(), _B);"
+        ));
+
+        let spec = transform(
+            "test.js".to_string(),
+            code.to_string(),
+            r#"{"spec_compliant": true}"#.to_string(),
+        )
+        .unwrap();
+        assert!(spec.code.contains("[_A, _classInit] = _applyDecs(this, [], _classDecs).c;"));
+        assert!(spec.code.contains("A = _A;\n_classInit();\n"), "{}", spec.code);
+
+        let marker = transform(
+            "test.js".to_string(),
+            code.to_string(),
+            r#"{"marker_decorators": ["register"]}"#.to_string(),
+        )
+        .unwrap();
+        assert!(marker.code.contains("_applyDecs(A, [], [register]).c[1]();"));
+    }
+
+    #[test]
+    fn test_marker_class_decorators_skip_rebinding() {
+        let code = "@register(\"a\") export class C {}\n@other class D {}\n";
+        let res = transform(
+            "test.js".to_string(),
+            code.to_string(),
+            r#"{"marker_decorators": ["register"]}"#.to_string(),
+        )
+        .unwrap();
+        assert!(res.code.contains("export class C {"));
+        assert!(res.code.contains("_applyDecs(C, [], [register(\"a\")]).c[1]();"));
+        assert!(!res.code.contains("C = _applyDecs(C"));
+        assert!(!res.code.contains("let C = class C"));
+        // Classes with a decorator outside the list are still rebound.
+        assert!(res.code.contains("let D = class D"));
+        assert!(res.code.contains("[D, _classInit] = _applyDecs(D, [], [other]).c;\n_classInit();"));
+    }
+
+    #[test]
+    fn test_no_banner_by_default() {
+        let code = "#!/usr/bin/env node\n@dec class C {}\n";
+        let result = transform("test.js".to_string(), code.to_string(), "{}".to_string());
+
+        assert!(result.is_ok());
+        if let Ok(res) = result {
+            assert!(res.code.starts_with("#!/usr/bin/env node\n"));
+            assert!(!res.code.contains("transformed by"));
+        }
+    }
+
+    #[test]
+    fn test_options_parsing() {
+        let code = "const x = 1;";
+        let result = transform(
+            "test.js".to_string(),
+            code.to_string(),
+            r#"{"source_maps": false}"#.to_string(),
+        );
+        assert!(result.is_ok());
+        if let Ok(res) = result {
+            assert!(res.map.is_none());
+        }
+    }
+
+    #[test]
+    fn test_source_map_names_rebound_class() {
+        let code = "const a = 1;\n@dec\nclass Widget {}\n";
+        let res = transform("widget.js".to_string(), code.to_string(), "{}".to_string()).unwrap();
+        let map: serde_json::Value = serde_json::from_str(res.map.as_deref().unwrap()).unwrap();
+        assert_eq!(map["sources"], serde_json::json!(["widget.js"]));
+        assert_eq!(map["names"], serde_json::json!(["Widget"]));
+
+        // Helpers are prepended, so the first mapping lands on the line of
+        // `const a = 1;` in the output.
+        let first_line = res.code.lines().position(|l| l == "const a = 1;").unwrap();
+        let mappings = map["mappings"].as_str().unwrap();
+        assert_eq!(mappings.chars().take_while(|&c| c == ';').count(), first_line);
+    }
+
+    #[test]
+    fn test_source_map_through_class_decorator_rewrite() {
+        let code = "const x = 1;\n@dec\nclass C {\n  m() {\n    return 42;\n  }\n}\n";
+        let res = transform("c.js".to_string(), code.to_string(), "{}".to_string()).unwrap();
+        let map = oxc_sourcemap::SourceMap::from_json_string(res.map.as_deref().unwrap()).unwrap();
+        let output_line = |text: &str| {
+            res.code
+                .lines()
+                .position(|l| l.trim_start().starts_with(text))
+                .unwrap() as u32
+        };
+        let source_lines = |dst_line: u32| -> Vec<u32> {
+            map.get_source_view_tokens()
+                .filter(|t| t.get_dst_line() == dst_line)
+                .map(|t| t.get_src_line())
+                .collect()
+        };
+
+        // The class, its rebinding and its body all still map to where they
+        // were written: `@dec` on line 1, `return 42;` on line 4.
+        assert_eq!(source_lines(output_line("let C = class C"))[0], 1);
+        assert_eq!(source_lines(output_line("[C, _classInit] = _applyDecs(C")), vec![1]);
+        assert!(source_lines(output_line("return 42;")).iter().all(|&l| l == 4));
+        assert!(!source_lines(output_line("return 42;")).is_empty());
+    }
+
+    #[test]
+    fn test_static_private_fields_beside_decorator_static_block() {
+        let code = r#"class C {
+    static #count = 0;
+    static #_ = 1;
+    @dec static m() { return ++C.#count; }
+    @dec static accessor #a = 5;
+    static { log(C.#count, C.#_, C.#a); }
+}
+"#;
+        for options in ["{}", r#"{"node_compat": true}"#] {
+            let res = transform("test.js".to_string(), code.to_string(), options.to_string())
+                .unwrap();
+            assert!(res.errors.is_empty(), "{options}: {:?}", res.errors);
+
+            // Every private name in the output still resolves to a member
+            // of `C`.
+            let allocator = Allocator::default();
+            let parsed = Parser::new(&allocator, &res.code, SourceType::mjs()).parse();
+            assert!(parsed.errors.is_empty(), "{options}: {:?}", parsed.errors);
+            let semantic = SemanticBuilder::new()
+                .with_check_syntax_error(true)
+                .build(&parsed.program);
+            assert!(semantic.errors.is_empty(), "{options}: {:?}", semantic.errors);
+
+            // The decorator static block comes first, before the user's
+            // private fields are initialized, and the user's static block
+            // is kept after them.
+            let class_code = &res.code[res.code.find("class C {").unwrap()..];
+            let decorations = class_code.find("= _applyDecs(this, [[").unwrap();
+            let count = class_code.find("static #count = 0;").unwrap();
+            let user_block = class_code.find("log(C.#count, C.#_, C.#a);").unwrap();
+            assert!(decorations < count && count < user_block, "{options}: {class_code}");
+            assert!(class_code.contains("static #_ = 1;"));
+            assert!(class_code.contains("return ++C.#count;"));
+        }
+        let compat = transform(
+            "test.js".to_string(),
+            code.to_string(),
+            r#"{"node_compat": true}"#.to_string(),
+        )
+        .unwrap();
+        assert!(compat.code.contains("static #_2 = (() => {\n\t\t[_init_a,"), "{}", compat.code);
+    }
+}
+
+#[cfg(test)]
+mod debug_tests {
+    use super::*;
+
+    #[test]
+    #[ignore] // Only run explicitly
+    fn test_print_transformed_output() {
+        let code = r#"
+function logged(value, { kind, name }) {
+    if (kind === "method") {
+        return function (...args) {
+            console.log(`calling ${name}`);
+            return value.call(this, ...args);
+        };
+    }
+}
+
+class C {
+    @logged
+    m(arg) {
+        return arg * 2;
+    }
+}
+"#;
+
+        let result = transform("test.js".to_string(), code.to_string(), "{}".to_string());
+
+        assert!(result.is_ok());
+        if let Ok(res) = result {
+            println!("\n=== TRANSFORMED CODE ===\n{}\n=== END ===\n", res.code);
+        }
+    }
+
+    #[test]
+    fn test_field_decorator_transformation() {
+        let code = r#"
+function validated(value, { kind, name }) {
+    if (kind === "field") {
+        return function(initialValue) {
+            return initialValue;
+        };
+    }
+}
+
+class C {
+    @validated
+    field = 1;
+}
+"#;
+
+        let result = transform("test.js".to_string(), code.to_string(), "{}".to_string());
+
+        assert!(result.is_ok());
+        if let Ok(res) = result {
+            assert!(res.code.contains("static {"));
+            assert!(res.code.contains("_applyDecs(this"));
+            assert!(res.code.contains("validated"));
+            assert!(res.code.contains("\"field\""));
+
+            assert!(!res.code.contains("@validated"));
+
+            assert_eq!(res.errors.len(), 0);
+        }
+    }
+
+    #[test]
+    fn test_accessor_decorator_transformation() {
+        let code = r#"
+function tracked(value, { kind }) {
+    if (kind === "accessor") {
+        return value;
+    }
+}
+
+class C {
+    @tracked
+    accessor data = 42;
+}
+"#;
+
+        let result = transform("test.js".to_string(), code.to_string(), "{}".to_string());
+
+        assert!(result.is_ok());
+        if let Ok(res) = result {
+            assert!(res.code.contains("static {"));
+            assert!(res.code.contains("tracked"));
+            assert!(res.code.contains("\"data\""));
+            assert!(!res.code.contains("@tracked"));
+            assert_eq!(res.errors.len(), 0);
+        }
+    }
+
+    #[test]
+    fn test_getter_setter_decorator_transformation() {
+        let code = r#"
+function logged(value, { kind }) {
+    return value;
+}
+
+class C {
+    @logged
+    get value() {
+        return this._value;
+    }
+    
+    @logged
+    set value(v) {
+        this._value = v;
+    }
+}
+"#;
+
+        let result = transform("test.js".to_string(), code.to_string(), "{}".to_string());
+
+        assert!(result.is_ok());
+        if let Ok(res) = result {
+            assert!(res.code.contains("static {"));
+            assert!(res.code.contains("logged"));
+            assert!(res.code.contains("\"value\""));
+            assert!(!res.code.contains("@logged"));
+            assert_eq!(res.errors.len(), 0);
+        }
+    }
+
+    #[test]
+    fn test_class_decorator_transformation() {
+        let code = r#"
+function metadata(data) {
+    return function(value, { kind }) {
+        if (kind === "class") {
+            return value;
+        }
+    };
+}
+
+@metadata({ version: "1.0" })
+class MyClass {
+    method() {}
+}
+"#;
+
+        let result = transform("test.js".to_string(), code.to_string(), "{}".to_string());
+
+        assert!(result.is_ok());
+        if let Ok(res) = result {
+            assert!(!res.code.contains("static {"));
+            assert!(res
+                .code
+                .contains("[MyClass, _classInit] = _applyDecs(MyClass, [], [metadata("));
+            assert!(!res.code.contains("@metadata"));
+            assert_eq!(res.errors.len(), 0);
+        }
+    }
+
+    #[test]
+    fn test_multiple_decorators_on_same_member() {
+        let code = r#"
+function first(value) { return value; }
+function second(value) { return value; }
+
+class C {
+    @first
+    @second
+    method() {}
+}
+"#;
+
+        let result = transform("test.js".to_string(), code.to_string(), "{}".to_string());
+
+        assert!(result.is_ok());
+        if let Ok(res) = result {
+            assert!(res.code.contains("first"));
+            assert!(res.code.contains("second"));
+            assert!(res.code.contains("static {"));
+            assert!(!res.code.contains("@first"));
+            assert!(!res.code.contains("@second"));
+            assert_eq!(res.errors.len(), 0);
+        }
+    }
+
+    #[test]
+    fn test_duplicate_decorator_on_same_member() {
+        let code = r#"
+function dec(value) { return value; }
+
+class C {
+    @dec @dec
+    method() {}
+}
+"#;
+
+        let result = transform("test.js".to_string(), code.to_string(), "{}".to_string());
+
+        assert!(result.is_ok());
+        if let Ok(res) = result {
+            assert!(
+                res.code.contains("[dec, dec]"),
+                "Both decorator entries should be kept in the descriptor"
+            );
+            let class_code = &res.code[res.code.find("class C").unwrap()..];
+            assert_eq!(
+                class_code.matches("\"method\"").count(),
+                1,
+                "Decorators on one member should share a single descriptor"
+            );
+            assert_eq!(res.errors.len(), 0);
+        }
+    }
+
+    #[test]
+    fn test_static_member_decorators() {
+        let code = r#"
+function logged(value) { return value; }
+
+class C {
+    @logged
+    static staticMethod() {
+        return 42;
+    }
+    
+    @logged
+    static staticField = 100;
+}
+"#;
+
+        let result = transform("test.js".to_string(), code.to_string(), "{}".to_string());
+
+        assert!(result.is_ok());
+        if let Ok(res) = result {
+            assert!(res.code.contains("static {"));
+            assert!(res.code.contains("logged"));
+            assert!(!res.code.contains("@logged"));
+            assert_eq!(res.errors.len(), 0);
+        }
+    }
+
+    #[test]
+    fn test_static_async_generator_method() {
+        let code = r#"
+class C {
+    @dec static async *m() { yield 1; }
+}
+"#;
+
+        let res = transform("test.js".to_string(), code.to_string(), "{}".to_string()).unwrap();
+        assert!(res.errors.is_empty(), "{:?}", res.errors);
+        let class_code = &res.code[res.code.find("class C").unwrap()..];
+        assert!(class_code.contains("static async *m() {"));
+        let compact: String = class_code.split_whitespace().collect();
+        // 2 (method) + 8 (static).
+        assert!(compact.contains(r#"[dec,10,"m",false]"#));
+        assert!(compact.contains("[,_initClass]=_applyDecs("));
+        assert!(res.code.contains("let _initClass;\nclass C {"));
+    }
+
+    #[test]
+    fn test_static_field_and_accessor_decorators() {
+        let code = r#"
+class C {
+    @dec static field = 1;
+    @dec static accessor x = 2;
+    @dec static #f = 3;
+}
+"#;
+
+        let res = transform("test.js".to_string(), code.to_string(), "{}".to_string()).unwrap();
+        assert!(res.errors.is_empty(), "{:?}", res.errors);
+        // `_applyDecs` tells static members apart by `flags & 8`.
+        assert!(res.code.contains("if ((flags & 8) == isStatic"));
+        let class_code = &res.code[res.code.find("class C").unwrap()..];
+        let compact: String = class_code.split_whitespace().collect();
+        // 0 (field) + 8 (static) and 1 (accessor) + 8 (static).
+        assert!(compact.contains(r#"[dec,8,"field",false]"#), "{compact}");
+        assert!(compact.contains(r#"[dec,9,"x",false]"#), "{compact}");
+        assert!(compact.contains(r#"[dec,8,"f",(o)=>o.#f,(o,v)=>o.#f=v]"#), "{compact}");
+        assert!(compact.contains("[_init_field,,_init_x,,_init_f,,,_initClass]=_applyDecs(this,"));
+
+        // The initializers are applied where the static members are
+        // initialized, after the static block assigned them.
+        assert!(class_code.contains("static field = _init_field(1);"));
+        assert!(class_code.contains("static #x_accessor_storage = _init_x(2);"));
+        assert!(class_code.contains("static get x() {\n\t\treturn this.#x_accessor_storage;"));
+        assert!(class_code.contains("static #f = _init_f(3);"));
+        assert!(class_code.contains("if (_initClass) _initClass();"));
+        assert!(class_code.find("static {") < class_code.find("static field ="));
+        assert!(!class_code.contains("_initProto"));
+    }
+
+    #[test]
+    fn test_declare_readonly_field_decorator_has_no_descriptor() {
+        let code = r#"
+class C {
+    @dec declare readonly field: number;
+
+    @dec
+    method() {}
+}
+"#;
+
+        let result = transform(
+            "test.ts".to_string(),
+            code.to_string(),
+            r#"{"strip_types": true}"#.to_string(),
+        );
+
+        assert!(result.is_ok());
+        if let Ok(res) = result {
+            let class_code = &res.code[res.code.find("class C").unwrap()..];
+            assert!(!class_code.contains("@dec"));
+            assert!(
+                !class_code.contains("\"field\""),
+                "Ambient field should not get a descriptor"
+            );
+            assert!(class_code.contains("\"method\""));
+            // The ambient field and its modifiers are gone from the JS output.
+            assert!(!class_code.contains("field"), "{}", class_code);
+            assert!(!class_code.contains("declare"), "{}", class_code);
+            assert!(!class_code.contains("readonly"), "{}", class_code);
+            assert!(!class_code.contains(": number"), "{}", class_code);
+            assert_eq!(res.errors.len(), 0);
+        }
+    }
+
+    #[test]
+    fn test_class_with_only_filtered_decorators_is_untouched() {
+        let code = r#"
+class C {
+    @dec declare field: number;
+    method() {}
+}
+"#;
+
+        let res = transform("test.ts".to_string(), code.to_string(), "{}".to_string()).unwrap();
+        assert!(res.errors.is_empty(), "{:?}", res.errors);
+        assert!(!res.code.contains("_applyDecs"));
+        assert!(!res.code.contains("static {"));
+        assert!(!res.code.contains("_initProto"));
+        assert!(!res.code.contains("@dec"));
+        assert!(res.code.starts_with("class C {"));
+        assert_eq!(res.stats.classes_transformed, 0);
+        assert_eq!(res.stats.helpers_emitted, 0);
+    }
+
+    #[test]
+    fn test_optional_field_decorator() {
+        let code = r#"
+class C {
+    @dec field?: number;
+}
+"#;
+
+        let result = transform(
+            "test.ts".to_string(),
+            code.to_string(),
+            r#"{"strip_types": true}"#.to_string(),
+        );
+
+        assert!(result.is_ok());
+        if let Ok(res) = result {
+            let class_code = &res.code[res.code.find("class C").unwrap()..];
+            assert!(!class_code.contains("@dec"));
+            assert!(class_code.contains("\tfield = _init_field(this);"), "{}", class_code);
+            assert!(!class_code.contains('?'), "{}", class_code);
+            let compact: String = class_code.split_whitespace().collect();
+            assert!(
+                compact.contains("[dec,0,\"field\",false]"),
+                "Optional field should get a field descriptor: {}",
+                class_code
+            );
+            assert!(class_code.contains("_initProto(this)"));
+            assert_eq!(res.errors.len(), 0);
+        }
+    }
+
+    #[test]
+    fn test_computed_key_shared_with_decorator_argument() {
+        let code = r#"
+const KEY = "key";
+class C {
+    @dec(KEY)
+    [KEY]() {}
+}
+"#;
+
+        let result = transform("test.js".to_string(), code.to_string(), "{}".to_string());
+
+        assert!(result.is_ok());
+        if let Ok(res) = result {
+            let class_code = &res.code[res.code.find("class C").unwrap()..];
+            assert!(
+                class_code.contains("[(_dec = dec(KEY), _computedKey = _toPropertyKey(KEY))]()"),
+                "Decorator then key should be evaluated once, in order: {}",
+                class_code
+            );
+            assert_eq!(class_code.matches("_toPropertyKey(KEY)").count(), 1);
+            assert_eq!(class_code.matches("dec(KEY)").count(), 1);
+            let compact: String = class_code.split_whitespace().collect();
+            assert!(compact.contains("[_dec,2,_computedKey,false]"));
+            assert!(res.code.contains("let _initProto, _dec, _computedKey;"));
+            assert_eq!(res.errors.len(), 0);
+        }
+    }
+
+    #[test]
+    fn test_computed_key_referencing_private_name() {
+        let code = r#"
+class Outer {
+    #x = "key";
+    make() {
+        return class Inner {
+            #y;
+            @dec [this.#x]() {}
+        };
+    }
+}
+"#;
+        let res = transform("test.js".to_string(), code.to_string(), "{}".to_string()).unwrap();
+        assert!(res.errors.is_empty(), "{:?}", res.errors);
+        let class_code = &res.code[res.code.find("class Inner").unwrap()..];
+        // Still evaluated in the class body, where `#x` and the outer `this`
+        // are in scope.
+        assert!(
+            class_code.contains("[(_dec = dec, _computedKey = _toPropertyKey(this.#x))]() {}"),
+            "{}",
+            class_code
+        );
+        let compact: String = class_code.split_whitespace().collect();
+        assert!(compact.contains("[_dec,2,_computedKey,false]"));
+        assert!(res.code.contains("make() {\n\t\tlet _initProto, _dec, _computedKey;"));
+    }
+
+    #[test]
+    fn test_members_sharing_a_computed_key_expression() {
+        let code = "class C {\n    @dec [k]() {}\n    @dec2 [k] = 1;\n}\n";
+        let res = transform("test.js".to_string(), code.to_string(), "{}".to_string()).unwrap();
+        assert!(res.errors.is_empty(), "{:?}", res.errors);
+        let class_code = &res.code[res.code.find("class C").unwrap()..];
+        // `k` is evaluated once per member, in source order, and each
+        // descriptor names its own result.
+        assert_eq!(class_code.matches("_toPropertyKey(k)").count(), 2, "{}", class_code);
+        assert!(class_code.contains("\t[(_dec = dec, _computedKey = _toPropertyKey(k))]() {}"));
+        assert!(class_code.contains(
+            "\t[(_dec2 = dec2, _computedKey2 = _toPropertyKey(k))] = _init_computedKey(this, 1);"
+        ));
+        let compact: String = class_code.split_whitespace().collect();
+        assert!(compact.contains("[[_dec,2,_computedKey,false],[_dec2,0,_computedKey2,false]]"));
+    }
+
+    #[test]
+    fn test_template_literal_computed_keys() {
+        let code = "class C {\n    @dec [`prefix_${x}`]() {}\n    @dec static [`s_${x}`] = 1;\n}\n";
+        let res = transform("test.js".to_string(), code.to_string(), "{}".to_string()).unwrap();
+        assert!(res.errors.is_empty(), "{:?}", res.errors);
+        let class_code = &res.code[res.code.find("class C").unwrap()..];
+        // The template is evaluated once, in the key, and the descriptor
+        // names its result rather than a placeholder string.
+        assert_eq!(class_code.matches("`prefix_${x}`").count(), 1, "{}", class_code);
+        assert!(class_code
+            .contains("\t[(_dec = dec, _computedKey = _toPropertyKey(`prefix_${x}`))]() {}"));
+        assert!(class_code.contains(
+            "\tstatic [(_dec2 = dec, _computedKey2 = _toPropertyKey(`s_${x}`))] = _init_computedKey(1);"
+        ));
+        assert!(!class_code.contains("\"computed\""), "{}", class_code);
+        let compact: String = class_code.split_whitespace().collect();
+        assert!(compact.contains("[[_dec,2,_computedKey,false],[_dec2,8,_computedKey2,false]]"));
+    }
+
+    #[test]
+    fn test_symbol_and_identifier_computed_keys() {
+        let code = r#"const KEY = "key";
+class C {
+    @dec [Symbol.iterator]() {}
+    @dec [KEY]() {}
+    @dec accessor [Symbol.for("a")] = 1;
+    @dec get [KEY + "g"]() { return 2; }
+}
+"#;
+        let res = transform("test.js".to_string(), code.to_string(), "{}".to_string()).unwrap();
+        assert!(res.errors.is_empty(), "{:?}", res.errors);
+        let class_code = &res.code[res.code.find("class C").unwrap()..];
+        assert!(class_code
+            .contains("\t[(_dec = dec, _computedKey = _toPropertyKey(Symbol.iterator))]() {}"));
+        assert!(class_code.contains("\t[(_dec2 = dec, _computedKey2 = _toPropertyKey(KEY))]() {}"));
+        // The accessor's setter reuses the key its getter evaluated.
+        assert!(class_code.contains(
+            "\tget [(_dec3 = dec, _computedKey3 = _toPropertyKey(Symbol.for(\"a\")))]() {"
+        ));
+        assert!(class_code.contains("\tset [_computedKey3](v) {"));
+        assert!(class_code.contains("\tget [(_dec4 = dec, _computedKey4 = _toPropertyKey(KEY + \"g\"))]() {"));
+        assert!(!class_code.contains("\"computed\""), "{}", class_code);
+        let compact: String = class_code.split_whitespace().collect();
+        assert!(compact.contains(
+            "[[_dec,2,_computedKey,false],[_dec2,2,_computedKey2,false],[_dec3,1,_computedKey3,false],[_dec4,3,_computedKey4,false]]"
+        ));
+    }
+
+    #[test]
+    fn test_private_member_decorators() {
+        let code = r#"
+function traced(value) { return value; }
+
+class C {
+    @traced
+    #privateMethod() {
+        return "private";
+    }
+    
+    @traced
+    #privateField = 42;
+}
+"#;
+
         let result = transform("test.js".to_string(), code.to_string(), "{}".to_string());
+
         assert!(result.is_ok());
         if let Ok(res) = result {
-            assert!(!res.code.contains("@classDecorator"));
-            assert!(!res.code.contains("@methodDecorator"));
-            assert!(!res.code.contains("@fieldDecorator"));
+            assert!(res.code.contains("static {"));
+            assert!(res.code.contains("traced"));
 
-            assert!(res.code.contains("class MyClass"));
-            assert!(res.code.contains("method()"));
-            assert!(res.code.contains("field = 1"));
+            assert!(res.code.contains("privateMethod") || res.code.contains("privateField"));
+            assert!(!res.code.contains("@traced"));
             assert_eq!(res.errors.len(), 0);
         }
     }
 
     #[test]
-    fn test_complex_decorator_scenario() {
+    fn test_private_accessor_decorator() {
         let code = r#"
-            function logged(value, context) {
-                console.log(`Decorating ${context.name}`);
-                return value;
-            }
-            
-            function bound(value, context) {
-                return value;
-            }
-            
-            @logged
-            class Controller {
-                @logged
-                static staticMethod() {
-                    return 'static';
-                }
-                
-                @bound
-                @logged
-                instanceMethod() {
-                    return 'instance';
-                }
-                
-                @logged
-                get value() {
-                    return this._value;
-                }
-                
-                @logged
-                set value(v) {
-                    this._value = v;
-                }
-                
-                @logged
-                accessor data = 42;
-                
-                @logged
-                #privateMethod() {
-                    return 'private';
-                }
-            }
-        "#;
+class C {
+    @dec accessor #x = 1;
+    read() { return this.#x; }
+}
+"#;
+
+        let res = transform("test.js".to_string(), code.to_string(), "{}".to_string()).unwrap();
+        assert!(res.errors.is_empty(), "{:?}", res.errors);
+
+        let class_code = &res.code[res.code.find("class C").unwrap()..];
+        assert!(!class_code.contains("accessor #x"));
+        let compact: String = class_code.split_whitespace().collect();
+        assert!(compact.contains("#x_accessor_storage=_init_x(this,1);"));
+        assert!(compact.contains("get#x(){return_get_x(this);}"));
+        assert!(compact.contains("set#x(v){_set_x(this,v);}"));
+        assert!(compact
+            .contains(r#"[dec,1,"x",(o)=>o.#x_accessor_storage,(o,v)=>o.#x_accessor_storage=v]"#));
+        assert!(compact.contains("[_init_x,_get_x,_set_x,,_initProto]=_applyDecs("));
+        assert!(compact.contains("[],void0,(o)=>#xino).e;"));
+        assert!(compact.contains("returnthis.#x;"));
+        assert!(res
+            .code
+            .contains("let _initProto, _init_x, _get_x, _set_x;"));
+    }
+
+    #[test]
+    fn test_getter_only_and_setter_only_decorators() {
+        let code = r#"
+class C {
+    @dec get value() { return 1; }
+    @dec set label(v) {}
+}
+"#;
+        let res = transform("test.js".to_string(), code.to_string(), "{}".to_string()).unwrap();
+        assert!(res.errors.is_empty(), "{:?}", res.errors);
+        let class_code = &res.code[res.code.find("class C").unwrap()..];
+        let compact: String = class_code.split_whitespace().collect();
+        assert!(compact.contains(r#"[[dec,3,"value",false],[dec,4,"label",false]]"#));
+        assert!(compact.contains("getvalue(){return1;}"));
+        assert!(compact.contains("setlabel(v){}"));
+        assert!(!compact.contains("setvalue"));
+        assert!(!compact.contains("getlabel"));
+    }
+
+    #[test]
+    fn test_private_getter_only_and_setter_only_decorators() {
+        let code = r#"
+class C {
+    #v = 1;
+    @dec get #value() { return this.#v; }
+    @dec set #label(v) { this.#v = v; }
+}
+"#;
+        let res = transform("test.js".to_string(), code.to_string(), "{}".to_string()).unwrap();
+        assert!(res.errors.is_empty(), "{:?}", res.errors);
+        let class_code = &res.code[res.code.find("class C").unwrap()..];
+        let compact: String = class_code.split_whitespace().collect();
+        assert!(compact.contains(r#"[dec,3,"value",function(){returnthis.#v;}]"#));
+        assert!(compact.contains(r#"[dec,4,"label",function(v){this.#v=v;}]"#));
+        assert!(compact.contains("[_call_value,_call_label,_initProto]=_applyDecs("));
+        assert!(compact.contains("get#value(){return_call_value(this);}"));
+        assert!(compact.contains("set#label(v){_call_label(this,v);}"));
+        assert!(!compact.contains("set#value"));
+        assert!(!compact.contains("get#label"));
+        assert!(res.code.contains("let _initProto, _call_value, _call_label;"));
+    }
+
+    #[test]
+    fn test_getter_and_setter_decorators_returning_replacements() {
+        // `_applyDecs` installs the function a getter or setter decorator
+        // returns: on the prototype (or class) for public members, and as
+        // the `_call_*` function private members call.
+        let code = r#"
+const twice = (get) => function () { return get.call(this) * 2; };
+const clamp = (set) => function (v) { set.call(this, Math.min(v, 10)); };
+class C {
+    #p = 1;
+    @twice get v() { return this._v; }
+    @clamp set v(x) { this._v = x; }
+    @twice get #q() { return this.#p; }
+    @clamp set #q(x) { this.#p = x; }
+    @twice static get #u() { return 2; }
+}
+"#;
+        let res = transform("test.js".to_string(), code.to_string(), "{}".to_string()).unwrap();
+        assert!(res.errors.is_empty(), "{:?}", res.errors);
+        let class_code = &res.code[res.code.find("class C").unwrap()..];
+        let compact: String = class_code.split_whitespace().collect();
+        assert!(compact.contains(r#"[twice,3,"v",false],[clamp,4,"v",false]"#), "{compact}");
+        assert!(compact.contains("getv(){returnthis._v;}setv(x){this._v=x;}"));
+        assert!(compact.contains(r#"[twice,3,"q",function(){returnthis.#p;}]"#));
+        assert!(compact.contains(r#"[clamp,4,"q",function(x){this.#p=x;}]"#));
+        assert!(compact.contains(r#"[twice,11,"u",function(){return2;}]"#));
+        assert!(compact.contains("[_call_u,_call_q,_call_q2,_initProto,_initClass]=_applyDecs("));
+        assert!(compact.contains("get#q(){return_call_q(this);}set#q(v){_call_q2(this,v);}"));
+        assert!(compact.contains("staticget#u(){return_call_u(this);}"));
+    }
+
+    #[test]
+    fn test_private_access_weakmap() {
+        let code = r#"
+class C {
+    @dec accessor #x = 1;
+    read() { return this.#x; }
+}
+"#;
+        let options = r#"{"private_access": "weakmap"}"#;
+        let res = transform("test.js".to_string(), code.to_string(), options.to_string()).unwrap();
+        assert!(res.errors.is_empty(), "{:?}", res.errors);
+        assert!(res.code.contains("function _privateStorageGet(storage, obj)"));
+        assert!(res.code.contains("function _privateStorageSet(storage, obj, value)"));
+
+        let class_code = &res.code[res.code.find("class C").unwrap()..];
+        let compact: String = class_code.split_whitespace().collect();
+        assert!(!compact.contains("accessor_storage"));
+        assert!(compact.contains("static{_x_storage=newWeakMap();"));
+        assert!(compact.contains(
+            r#"[dec,1,"x",(o)=>_privateStorageGet(_x_storage,o),(o,v)=>_privateStorageSet(_x_storage,o,v)]"#
+        ));
+        assert!(compact.contains("if(_initProto)_initProto(this);_x_storage.set(this,_init_x(this,1));"));
+        assert!(compact.contains("get#x(){return_get_x(this);}"));
+        assert!(res
+            .code
+            .contains("let _initProto, _init_x, _get_x, _set_x, _x_storage;"));
+
+        let native = transform("test.js".to_string(), code.to_string(), "{}".to_string()).unwrap();
+        assert!(!native.code.contains("_privateStorageGet"));
+        assert!(native.code.contains("#x_accessor_storage"));
+    }
+
+    #[test]
+    fn test_private_accessor_storage_name_is_unique() {
+        let code = r#"
+class C {
+    #x_accessor_storage = 0;
+    @dec static accessor #x;
+}
+"#;
+
+        let res = transform("test.js".to_string(), code.to_string(), "{}".to_string()).unwrap();
+        let compact: String = res.code.split_whitespace().collect();
+        assert!(compact.contains("static#x_accessor_storage2=_init_x();"));
+        assert!(compact.contains("staticget#x(){return_get_x();}"));
+        assert!(compact.contains("staticset#x(v){_set_x(v);}"));
+        assert!(compact.contains(r#"[dec,9,"x",(o)=>o.#x_accessor_storage2,"#));
+    }
+
+    #[test]
+    fn test_public_field_decorator_initializer() {
+        let code = r#"
+class C {
+    @double x = 2;
+    @double static y = 3;
+}
+"#;
+
+        let res = transform("test.js".to_string(), code.to_string(), "{}".to_string()).unwrap();
+        assert!(res.errors.is_empty(), "{:?}", res.errors);
+
+        // The initial values go through the initializers the decorators
+        // return, instead of those being installed on the prototype.
+        let class_code = &res.code[res.code.find("class C").unwrap()..];
+        assert!(class_code.contains("\tx = _init_x(this, 2);"));
+        assert!(class_code.contains("\tstatic y = _init_y(3);"));
+        let compact: String = class_code.split_whitespace().collect();
+        assert!(compact.contains("[_init_y,,_init_x,,_initProto,_initClass]=_applyDecs("));
+        assert!(compact.contains(r#"[double,0,"x",false],[double,8,"y",false]"#));
+        assert!(res.code.contains("let _initProto, _initClass, _init_x, _init_y;"));
+    }
+
+    #[test]
+    fn test_export_default_class_decorator() {
+        let code = r#"
+@noraComponent
+export default class BrowserShareMode {
+    method() {}
+}
+"#;
 
         let result = transform("test.js".to_string(), code.to_string(), "{}".to_string());
 
         assert!(result.is_ok());
         if let Ok(res) = result {
-            assert!(!res.code.contains("@logged"));
-            assert!(!res.code.contains("@bound"));
+            assert!(!res.code.contains("@noraComponent"));
 
-            assert!(res.code.contains("class Controller"));
-            assert!(res.code.contains("static staticMethod()"));
-            assert!(res.code.contains("instanceMethod()"));
-            assert!(res.code.contains("get value()"));
-            assert!(res.code.contains("set value("));
-            assert!(res.code.contains("accessor data"));
-            assert!(res.code.contains("#privateMethod()"));
+            assert!(res.code.contains("export default"));
 
-            assert!(res.code.contains("function logged"));
-            assert!(res.code.contains("function bound"));
+            assert!(!res.code.contains("export default @"));
 
+            assert!(res.code.contains("function _applyDecs"));
+
+            assert!(!res.code.contains("static {"));
+            assert!(res.code.contains("_applyDecs(BrowserShareMode, [], [noraComponent"));
             assert_eq!(res.errors.len(), 0);
         }
     }
 
     #[test]
-    fn test_helper_injection_on_decorator_presence() {
-        let code = r#"
-            function logged(value, { kind, name }) {
-                if (kind === "method") {
-                    return function (...args) {
-                        console.log(`calling ${name}`);
-                        return value.call(this, ...args);
-                    };
-                }
-            }
+    fn test_export_default_class_decorator_with_call() {
+        let code = r###"
+import { noraComponent, NoraComponentBase } from "#features-chrome/utils/base.ts";
 
-            class C {
-                @logged
-                m(arg) {
-                    return arg * 2;
-                }
-            }
-        "#;
+@noraComponent(import.meta.hot)
+export default class BrowserShareMode extends NoraComponentBase {
+    init() {
+        this.logger.info("Hello from Logger!");
+    }
+
+    _metadata() {
+        return {
+            moduleName: "browser-share-mode",
+            dependencies: [],
+            softDependencies: [],
+        };
+    }
+}
+"###;
 
         let result = transform("test.js".to_string(), code.to_string(), "{}".to_string());
 
         assert!(result.is_ok());
         if let Ok(res) = result {
-            assert!(res.code.contains("function _applyDecs"));
-            assert!(res.code.contains("function _toPropertyKey"));
-            assert!(res.code.contains("function _toPrimitive"));
-            assert!(res.code.contains("function _setFunctionName"));
-            assert!(res.code.contains("function _checkInRHS"));
+            assert!(!res.code.contains("@noraComponent"));
 
-            assert!(res.code.contains("static {"));
-            assert!(res.code.contains("_applyDecs(this"));
-            assert!(res.code.contains("[_initProto, _initClass]"));
+            assert!(res.code.contains("export default"));
+            assert!(res.code.contains("class BrowserShareMode"));
 
-            assert!(res.code.contains("class C"));
-            assert!(res.code.contains("function logged"));
-            assert!(!res.code.contains("@logged")); // Decorator syntax removed
+            assert!(!res.code.contains("export default @"));
+
+            assert!(res.code.contains("function _applyDecs"));
 
+            assert!(!res.code.contains("static {"));
+            assert!(res.code.contains("_applyDecs(BrowserShareMode, [], [noraComponent"));
             assert_eq!(res.errors.len(), 0);
         }
     }
+}
+#[cfg(test)]
+mod test_decorator_call {
+    use crate::transform;
 
     #[test]
-    fn test_no_helper_injection_without_decorators() {
+    fn test_decorator_with_call() {
         let code = r#"
-            class C {
-                m(arg) {
-                    return arg * 2;
-                }
-            }
-        "#;
+function noraComponent(hotCtx) {
+    return function(target) {
+        console.log("Decorated with hotCtx:", hotCtx);
+        return target;
+    };
+}
+
+@noraComponent(import.meta.hot)
+class MyClass {
+    method() {}
+}
+"#;
 
         let result = transform("test.js".to_string(), code.to_string(), "{}".to_string());
 
         assert!(result.is_ok());
         if let Ok(res) = result {
-            assert!(!res.code.contains("function _applyDecs"));
-            assert!(!res.code.contains("function _toPropertyKey"));
+            println!("\n=== TRANSFORMED CODE ===\n{}\n=== END ===\n", res.code);
+            assert_eq!(res.errors.len(), 0);
+        }
+    }
+
+    #[test]
+    fn test_decorator_call_with_spread_and_trailing_comma() {
+        let code = r#"
+@dec(...args)
+class C {
+    @dec(...args) m() {}
+    @dec(a, b,) static x = 1;
+}
+"#;
+
+        let res = transform("test.js".to_string(), code.to_string(), "{}".to_string()).unwrap();
+        assert!(res.errors.is_empty(), "{:?}", res.errors);
+        let compact: String = res.code.split_whitespace().collect();
+        assert!(compact.contains(r#"[dec(...args),2,"m",false]"#));
+        assert!(compact.contains(r#"[dec(a,b),8,"x",false]"#));
+        assert!(res.code.contains("[C, _classInit] = _applyDecs(C, [], [dec(...args)]).c;\n_classInit();"));
+    }
+}
+
+#[cfg(test)]
+mod test_problem_statement {
+    use crate::transform;
+
+    #[test]
+    fn test_problem_statement_decorator() {
+        let code = r###"
+import { render } from "@nora/solid-xul";
+import { ShareModeElement } from "./browser-share-mode.tsx";
+import {
+  noraComponent,
+  NoraComponentBase,
+} from "#features-chrome/utils/base.ts";
+
+@noraComponent(import.meta.hot)
+export default class BrowserShareMode extends NoraComponentBase {
+  init() {
+    this.logger.info("Hello from Logger!");
+    render(ShareModeElement, document.querySelector("#menu_ToolsPopup"), {
+      marker: document.querySelector("#menu_openFirefoxView")!,
+      hotCtx: import.meta.hot,
+    });
+  }
+
+  _metadata() {
+    return {
+      moduleName: "browser-share-mode",
+      dependencies: [],
+      softDependencies: [],
+    };
+  }
+}
+"###;
+
+        let result = transform("test.ts".to_string(), code.to_string(), "{}".to_string());
 
-            assert!(res.code.contains("class C"));
+        assert!(result.is_ok());
+        if let Ok(res) = result {
+            println!("\n=== TRANSFORMED CODE ===\n{}\n=== END ===\n", res.code);
 
-            assert_eq!(res.errors.len(), 0);
+            assert!(
+                res.code.contains("noraComponent(import.meta.hot)"),
+                "Expected noraComponent(import.meta.hot) in output"
+            );
+            assert!(
+                !res.code.contains("@noraComponent"),
+                "Decorator syntax should be removed"
+            );
+            assert_eq!(res.errors.len(), 0, "Should have no errors");
         }
     }
+}
+
+#[cfg(test)]
+mod comprehensive_decorator_tests {
+    use crate::transform;
 
     #[test]
-    fn test_method_decorator_detected() {
-        let code = "class C { @dec method() {} }";
+    fn test_various_decorator_call_patterns() {
+        let code = r#"
+
+@simple
+class Class1 {}
+@decorator(arg)
+class Class2 {}
+@decorator(arg1, arg2, arg3)
+class Class3 {}
+@decorator(import.meta.hot)
+class Class4 {}
+@namespace.decorator
+class Class5 {}
+@namespace.decorator(arg)
+class Class6 {}
+class Class7 {
+    @bound(this)
+    method() {}
+}
+class Class8 {
+    @validate("string")
+    field = "";
+}
+"#;
+
         let result = transform("test.js".to_string(), code.to_string(), "{}".to_string());
-        assert!(result.is_ok());
-    }
 
-    #[test]
-    fn test_options_parsing() {
-        let code = "const x = 1;";
-        let result = transform(
-            "test.js".to_string(),
-            code.to_string(),
-            r#"{"source_maps": false}"#.to_string(),
-        );
         assert!(result.is_ok());
         if let Ok(res) = result {
-            assert!(res.map.is_none());
+            assert!(
+                res.code.contains("simple"),
+                "Simple decorator should be preserved"
+            );
+            assert!(
+                res.code.contains("decorator(arg)"),
+                "Single argument call should be preserved"
+            );
+            assert!(
+                res.code.contains("decorator(arg1, arg2, arg3)"),
+                "Multiple arguments call should be preserved"
+            );
+            assert!(
+                res.code.contains("decorator(import.meta.hot)"),
+                "Complex expression call should be preserved"
+            );
+            assert!(
+                res.code.contains("namespace.decorator"),
+                "Member expression should be preserved"
+            );
+            assert!(
+                res.code.contains("namespace.decorator(arg)"),
+                "Member expression call should be preserved"
+            );
+            assert!(
+                res.code.contains("bound(this)"),
+                "Method decorator call should be preserved"
+            );
+            assert!(
+                res.code.contains("validate(\"string\")"),
+                "Field decorator call should be preserved"
+            );
+
+            assert!(!res.code.contains("@simple"), "@ syntax should be removed");
+            assert!(
+                !res.code.contains("@decorator"),
+                "@ syntax should be removed"
+            );
+            assert!(
+                !res.code.contains("@namespace"),
+                "@ syntax should be removed"
+            );
+            assert!(!res.code.contains("@bound"), "@ syntax should be removed");
+            assert!(
+                !res.code.contains("@validate"),
+                "@ syntax should be removed"
+            );
+
+            assert_eq!(res.errors.len(), 0, "Should have no errors");
         }
     }
 }
 
 #[cfg(test)]
-mod debug_tests {
-    use super::*;
+mod test_constructor_injection {
+    use crate::{transform, transform_edits};
+    use oxc_allocator::Allocator;
+    use oxc_parser::Parser;
+    use oxc_semantic::SemanticBuilder;
+    use oxc_span::SourceType;
 
     #[test]
-    #[ignore] // Only run explicitly
-    fn test_print_transformed_output() {
+    #[ignore]
+    fn test_field_decorator_with_constructor() {
         let code = r#"
-function logged(value, { kind, name }) {
-    if (kind === "method") {
-        return function (...args) {
-            console.log(`calling ${name}`);
-            return value.call(this, ...args);
+function validated(value, { kind }) {
+    if (kind === "field") {
+        return function(initialValue) {
+            console.log("Field initialized");
+            return initialValue;
         };
     }
 }
 
 class C {
-    @logged
-    m(arg) {
-        return arg * 2;
-    }
+    @validated
+    field = 1;
 }
 "#;
 
@@ -515,14 +4154,20 @@ class C {
 
         assert!(result.is_ok());
         if let Ok(res) = result {
-            println!("\n=== TRANSFORMED CODE ===\n{}\n=== END ===\n", res.code);
+            println!(
+                "\n=== FIELD DECORATOR OUTPUT ===\n{}\n=== END ===\n",
+                res.code
+            );
+            assert!(res.code.contains("constructor()"));
+            assert!(res.code.contains("_initProto(this)"));
         }
     }
 
     #[test]
-    fn test_field_decorator_transformation() {
+    #[ignore]
+    fn test_field_decorator_with_existing_constructor() {
         let code = r#"
-function validated(value, { kind, name }) {
+function validated(value, { kind }) {
     if (kind === "field") {
         return function(initialValue) {
             return initialValue;
@@ -533,6 +4178,10 @@ function validated(value, { kind, name }) {
 class C {
     @validated
     field = 1;
+    
+    constructor() {
+        console.log("Constructor");
+    }
 }
 "#;
 
@@ -540,363 +4189,532 @@ class C {
 
         assert!(result.is_ok());
         if let Ok(res) = result {
-            assert!(res.code.contains("static {"));
-            assert!(res.code.contains("_applyDecs(this"));
-            assert!(res.code.contains("validated"));
-            assert!(res.code.contains("\"field\""));
-
-            assert!(!res.code.contains("@validated"));
-
-            assert_eq!(res.errors.len(), 0);
+            println!(
+                "\n=== FIELD WITH EXISTING CTOR ===\n{}\n=== END ===\n",
+                res.code
+            );
+            assert!(res.code.contains("_initProto(this)"));
         }
     }
 
     #[test]
-    fn test_accessor_decorator_transformation() {
+    fn test_static_accessor_with_instance_field() {
         let code = r#"
-function tracked(value, { kind }) {
-    if (kind === "accessor") {
-        return value;
-    }
-}
-
 class C {
-    @tracked
-    accessor data = 42;
+    @dec static accessor x = 1;
+    @dec y = 2;
 }
 "#;
 
-        let result = transform("test.js".to_string(), code.to_string(), "{}".to_string());
+        let res = transform("test.js".to_string(), code.to_string(), "{}".to_string()).unwrap();
+        assert!(res.errors.is_empty(), "{:?}", res.errors);
 
-        assert!(result.is_ok());
-        if let Ok(res) = result {
-            assert!(res.code.contains("static {"));
-            assert!(res.code.contains("tracked"));
-            assert!(res.code.contains("\"data\""));
-            assert!(!res.code.contains("@tracked"));
-            assert_eq!(res.errors.len(), 0);
-        }
+        let class_code = &res.code[res.code.find("class C").unwrap()..];
+        let ctor = &class_code[class_code.find("constructor()").unwrap()..];
+        assert!(ctor[..ctor.find('}').unwrap()].contains("_initProto(this)"));
+
+        let compact: String = class_code.split_whitespace().collect();
+        assert!(compact.contains(r#"[dec,9,"x",false]"#));
+        assert!(compact.contains(r#"[dec,0,"y",false]"#));
+        assert!(compact.contains("if(_initClass)_initClass();"));
+        assert!(!class_code.contains("@dec"));
     }
 
     #[test]
-    fn test_getter_setter_decorator_transformation() {
+    fn test_uninitialized_public_accessor() {
         let code = r#"
-function logged(value, { kind }) {
-    return value;
+class C {
+    @dec accessor x;
 }
+"#;
 
-class C {
-    @logged
-    get value() {
-        return this._value;
-    }
-    
-    @logged
-    set value(v) {
-        this._value = v;
+        let res = transform("test.js".to_string(), code.to_string(), "{}".to_string()).unwrap();
+        assert!(res.errors.is_empty(), "{:?}", res.errors);
+
+        let class_code = &res.code[res.code.find("class C").unwrap()..];
+        let compact: String = class_code.split_whitespace().collect();
+        assert!(compact.contains(r#"[dec,1,"x",false]"#));
+        assert!(compact.contains("#x_accessor_storage=_init_x(this);"));
+        assert!(compact.contains("getx(){returnthis.#x_accessor_storage;}"));
+        assert!(compact.contains("setx(v){this.#x_accessor_storage=v;}"));
+        assert!(!class_code.contains("accessor x"));
     }
+
+    #[test]
+    fn test_accessor_only_class() {
+        let code = r#"
+class C {
+    @dec accessor x = 1;
+    @dec accessor #y = 2;
+}
+class D extends Base {
+    @dec accessor z;
 }
 "#;
 
-        let result = transform("test.js".to_string(), code.to_string(), "{}".to_string());
-
-        assert!(result.is_ok());
-        if let Ok(res) = result {
-            assert!(res.code.contains("static {"));
-            assert!(res.code.contains("logged"));
-            assert!(res.code.contains("\"value\""));
-            assert!(!res.code.contains("@logged"));
-            assert_eq!(res.errors.len(), 0);
-        }
+        let res = transform("test.js".to_string(), code.to_string(), "{}".to_string()).unwrap();
+        assert!(res.errors.is_empty(), "{:?}", res.errors);
+        assert!(res.code.contains("let _initProto, _init_x, _init_y, _get_y, _set_y;"));
+        assert!(res.code.contains("let _initProto2, _init_z;"));
+
+        let c = &res.code[res.code.find("class C").unwrap()..res.code.find("class D").unwrap()];
+        let compact: String = c.split_whitespace().collect();
+        assert!(compact.contains("constructor(){if(_initProto)_initProto(this);}"));
+        assert!(compact.contains("[_init_x,,_init_y,_get_y,_set_y,,_initProto]=_applyDecs(this,"));
+        assert!(compact.contains("#x_accessor_storage=_init_x(this,1);"));
+        assert!(compact.contains("#y_accessor_storage=_init_y(this,2);"));
+        assert!(compact.contains("get#y(){return_get_y(this);}"));
+        assert!(compact.contains("set#y(v){_set_y(this,v);}"));
+        assert!(!c.contains("_initClass"));
+
+        let d = &res.code[res.code.find("class D").unwrap()..];
+        let compact: String = d.split_whitespace().collect();
+        assert!(compact
+            .contains("constructor(..._args){super(..._args);if(_initProto2)_initProto2(this);}"));
+        assert!(compact.contains("[_init_z,,_initProto2]=_applyDecs(this,"));
+        assert!(compact.contains("#z_accessor_storage=_init_z(this);"));
     }
 
     #[test]
-    fn test_class_decorator_transformation() {
+    fn test_unused_init_variables_are_not_declared() {
         let code = r#"
-function metadata(data) {
-    return function(value, { kind }) {
-        if (kind === "class") {
-            return value;
-        }
-    };
+@dec
+class OnlyClass {
+    m() {}
 }
+"#;
 
-@metadata({ version: "1.0" })
-class MyClass {
-    method() {}
+        let res = transform("test.js".to_string(), code.to_string(), "{}".to_string()).unwrap();
+        assert!(res.errors.is_empty(), "{:?}", res.errors);
+        assert!(!res.code.contains("_initProto"));
+        assert!(!res.code.contains("_initClass"));
+        let class_code = &res.code[res.code.find("let OnlyClass").unwrap()..];
+        assert!(!class_code.contains("static {"));
+        assert!(class_code.contains("[OnlyClass, _classInit] = _applyDecs(OnlyClass, [], [dec]).c;\n_classInit();"));
+
+        let code = r#"
+class Instance {
+    @dec m() {}
 }
 "#;
+        let res = transform("test.js".to_string(), code.to_string(), "{}".to_string()).unwrap();
+        assert!(res.code.contains("let _initProto;\nclass Instance {"));
+        assert!(!res.code.contains("_initClass"));
+    }
 
-        let result = transform("test.js".to_string(), code.to_string(), "{}".to_string());
+    const THREE_CLASSES: &str = r#"
+import { dec } from "./dec.js";
+class A { @dec m() {} @dec static s() {} }
+class B { @dec m() {} @dec static s() {} }
+class C { @dec m() {} @dec static s() {} }
+"#;
 
-        assert!(result.is_ok());
-        if let Ok(res) = result {
-            assert!(res.code.contains("static {"));
-            assert!(res.code.contains("metadata"));
-            assert!(!res.code.contains("@metadata"));
-            assert_eq!(res.errors.len(), 0);
-        }
+    #[test]
+    fn test_init_variables_are_unique_per_class() {
+        let res = transform("test.js".to_string(), THREE_CLASSES.to_string(), "{}".to_string())
+            .unwrap();
+        assert!(res.errors.is_empty(), "{:?}", res.errors);
+        assert!(res.code.contains("let _initProto, _initClass;\nclass A {"));
+        assert!(res.code.contains("let _initProto2, _initClass2;\nclass B {"));
+        assert!(res.code.contains("let _initProto3, _initClass3;\nclass C {"));
+        assert!(res.code.contains("[_initProto3, _initClass3] = _applyDecs(this"));
+        assert!(res.code.contains("if (_initProto3) _initProto3(this);"));
+        assert!(res.code.contains("if (_initClass3) _initClass3();"));
     }
 
     #[test]
-    fn test_multiple_decorators_on_same_member() {
+    fn test_init_variables_never_redeclared() {
         let code = r#"
-function first(value) { return value; }
-function second(value) { return value; }
-
-class C {
-    @first
-    @second
-    method() {}
-}
+import { dec } from "./dec.js";
+let _initProto = 1;
+const pair = [class { @dec m() {} }, class { @dec m() {} }];
+@dec class A { @dec m() {} static Inner = class { @dec n() {} }; }
+export class B { @dec accessor x = 1; @dec static s() {} }
+export default class { @dec m() {} }
 "#;
+        let res = transform("test.mjs".to_string(), code.to_string(), "{}".to_string()).unwrap();
+        assert!(res.errors.is_empty(), "{:?}", res.errors);
+
+        // Re-checking the output reports any binding declared twice in the
+        // module scope.
+        let allocator = Allocator::default();
+        let parsed = Parser::new(&allocator, &res.code, SourceType::mjs()).parse();
+        assert!(parsed.errors.is_empty(), "{:?}", parsed.errors);
+        let semantic = SemanticBuilder::new()
+            .with_check_syntax_error(true)
+            .build(&parsed.program);
+        assert!(semantic.errors.is_empty(), "{:?}\n{}", semantic.errors, res.code);
+        assert_eq!(res.code.matches("let _initProto,").count(), 0, "{}", res.code);
+        assert_eq!(res.code.matches("let _initProto;").count(), 0, "{}", res.code);
+    }
 
-        let result = transform("test.js".to_string(), code.to_string(), "{}".to_string());
+    #[test]
+    fn test_init_variables_in_try_block() {
+        let code = r#"
+try {
+    @dec class C { @dec m() {} }
+} catch {}
+const make = () => class { @dec m() {} };
+"#;
+        let res = transform("test.js".to_string(), code.to_string(), "{}".to_string()).unwrap();
+        assert!(res.errors.is_empty(), "{:?}", res.errors);
+        assert!(
+            res.code
+                .contains("try {\n\tlet _initProto, _classInit;\n\tlet C = class C {"),
+            "{}",
+            res.code
+        );
+        assert!(!res.code.contains("\nlet _initProto;"));
+        // A concise arrow body has no room for the declaration.
+        assert!(res
+            .code
+            .contains("let _initProto2;\nconst make = () => class {"));
+    }
 
-        assert!(result.is_ok());
-        if let Ok(res) = result {
-            assert!(res.code.contains("first"));
-            assert!(res.code.contains("second"));
-            assert!(res.code.contains("static {"));
-            assert!(!res.code.contains("@first"));
-            assert!(!res.code.contains("@second"));
-            assert_eq!(res.errors.len(), 0);
+    #[test]
+    fn test_collapse_init_vars() {
+        let res = transform(
+            "test.js".to_string(),
+            THREE_CLASSES.to_string(),
+            r#"{"collapse_init_vars": true}"#.to_string(),
+        )
+        .unwrap();
+        assert!(res.errors.is_empty(), "{:?}", res.errors);
+        assert_eq!(res.code.matches("let _init").count(), 1, "{}", res.code);
+        assert!(res.code.contains(
+            "import { dec } from \"./dec.js\";\n\
+             let _initProto, _initClass, _initProto2, _initClass2, _initProto3, _initClass3;\n\
+             class A {"
+        ));
+
+        let edits = transform_edits(
+            "test.js".to_string(),
+            THREE_CLASSES.to_string(),
+            r#"{"collapse_init_vars": true}"#.to_string(),
+        )
+        .unwrap();
+        let mut patched = THREE_CLASSES.to_string();
+        for edit in edits.iter().rev() {
+            patched.replace_range(edit.start as usize..edit.end as usize, &edit.text);
         }
+        assert_eq!(patched.matches("let _init").count(), 1, "{}", patched);
+        let lines = |code: &str| {
+            code.lines().filter(|line| !line.is_empty()).collect::<Vec<_>>().join("\n")
+        };
+        assert_eq!(lines(&patched), lines(&res.code));
     }
 
     #[test]
-    fn test_static_member_decorators() {
+    fn test_decoration_site_after_class() {
         let code = r#"
-function logged(value) { return value; }
-
 class C {
-    @logged
-    static staticMethod() {
-        return 42;
-    }
-    
-    @logged
-    static staticField = 100;
+    @dec m() {}
+    @dec static s() {}
 }
 "#;
-
-        let result = transform("test.js".to_string(), code.to_string(), "{}".to_string());
-
-        assert!(result.is_ok());
-        if let Ok(res) = result {
-            assert!(res.code.contains("static {"));
-            assert!(res.code.contains("logged"));
-            assert!(!res.code.contains("@logged"));
-            assert_eq!(res.errors.len(), 0);
-        }
+        let options = r#"{"decoration_site":"after-class"}"#;
+        let res = transform("test.js".to_string(), code.to_string(), options.to_string()).unwrap();
+        assert!(res.errors.is_empty(), "{:?}", res.errors);
+        let class_code = &res.code[res.code.find("class C {").unwrap()..];
+        assert!(!class_code.contains("static {"));
+        let class_end = class_code.find("\n}\n").unwrap();
+        let after = &class_code[class_end..];
+        assert!(after.contains("[_initProto, _initClass] = _applyDecs(C, ["));
+        assert!(after.contains("if (_initClass) _initClass();"));
+
+        // Static fields are initialized while the class is defined, so they
+        // keep the static block.
+        let code = r#"
+class D {
+    @dec static x = 1;
+}
+"#;
+        let res = transform("test.js".to_string(), code.to_string(), options.to_string()).unwrap();
+        assert!(res.code.contains("static {"));
     }
 
     #[test]
-    fn test_private_member_decorators() {
+    fn test_static_only_members_skip_constructor() {
         let code = r#"
-function traced(value) { return value; }
-
 class C {
-    @traced
-    #privateMethod() {
-        return "private";
-    }
-    
-    @traced
-    #privateField = 42;
+    @dec static accessor x = 1;
+    @dec static m() {}
 }
 "#;
 
-        let result = transform("test.js".to_string(), code.to_string(), "{}".to_string());
-
-        assert!(result.is_ok());
-        if let Ok(res) = result {
-            assert!(res.code.contains("static {"));
-            assert!(res.code.contains("traced"));
-
-            assert!(res.code.contains("privateMethod") || res.code.contains("privateField"));
-            assert!(!res.code.contains("@traced"));
-            assert_eq!(res.errors.len(), 0);
-        }
+        let res = transform("test.js".to_string(), code.to_string(), "{}".to_string()).unwrap();
+        let class_code = &res.code[res.code.find("class C").unwrap()..];
+        assert!(!class_code.contains("constructor"));
+        assert!(!class_code.contains("_initProto(this)"));
     }
 
     #[test]
-    fn test_export_default_class_decorator() {
+    fn test_static_and_instance_methods_share_apply_decs() {
         let code = r#"
-@noraComponent
-export default class BrowserShareMode {
-    method() {}
+class C {
+    @dec static m() {}
+    @dec instanceMethod() {}
 }
 "#;
 
-        let result = transform("test.js".to_string(), code.to_string(), "{}".to_string());
-
-        assert!(result.is_ok());
-        if let Ok(res) = result {
-            assert!(!res.code.contains("@noraComponent"));
-
-            assert!(res.code.contains("export default"));
-
-            assert!(!res.code.contains("export default @"));
-
-            assert!(res.code.contains("function _applyDecs"));
-
-            assert!(res.code.contains("static {"));
-            assert!(res.code.contains("noraComponent"));
-            assert_eq!(res.errors.len(), 0);
-        }
+        let res = transform("test.js".to_string(), code.to_string(), "{}".to_string()).unwrap();
+        let class_code = &res.code[res.code.find("class C").unwrap()..];
+        let compact: String = class_code.split_whitespace().collect();
+        // Static methods carry the static bit (8) on top of the method kind (2).
+        assert!(compact.contains(r#"[dec,10,"m",false]"#));
+        assert!(compact.contains(r#"[dec,2,"instanceMethod",false]"#));
+        assert_eq!(class_code.matches("_applyDecs(").count(), 1);
+        assert_eq!(class_code.matches("_initProto(this)").count(), 1);
+        assert!(compact.contains("staticm(){}"));
+        assert!(compact.contains("instanceMethod(){}"));
     }
+}
+
+#[cfg(test)]
+mod test_super_handling {
+    use crate::transform;
 
     #[test]
-    fn test_export_default_class_decorator_with_call() {
-        let code = r###"
-import { noraComponent, NoraComponentBase } from "#features-chrome/utils/base.ts";
+    #[ignore]
+    fn test_field_decorator_with_super() {
+        let code = r#"
+function validated(value, { kind }) {
+    if (kind === "field") {
+        return function(initialValue) {
+            return initialValue;
+        };
+    }
+}
 
-@noraComponent(import.meta.hot)
-export default class BrowserShareMode extends NoraComponentBase {
-    init() {
-        this.logger.info("Hello from Logger!");
+class Base {
+    constructor(x) {
+        this.x = x;
     }
+}
 
-    _metadata() {
-        return {
-            moduleName: "browser-share-mode",
-            dependencies: [],
-            softDependencies: [],
-        };
+class C extends Base {
+    @validated
+    field = 1;
+    
+    constructor() {
+        super(42);
+        console.log("After super");
     }
 }
-"###;
+"#;
 
         let result = transform("test.js".to_string(), code.to_string(), "{}".to_string());
 
         assert!(result.is_ok());
         if let Ok(res) = result {
-            assert!(!res.code.contains("@noraComponent"));
-
-            assert!(res.code.contains("export default"));
-            assert!(res.code.contains("class BrowserShareMode"));
-
-            assert!(!res.code.contains("export default @"));
-
-            assert!(res.code.contains("function _applyDecs"));
+            println!("\n=== FIELD WITH SUPER ===\n{}\n=== END ===\n", res.code);
+            assert!(res.code.contains("super(42)"));
+            assert!(res.code.contains("_initProto(this)"));
 
-            assert!(res.code.contains("static {"));
-            assert!(res.code.contains("noraComponent"));
-            assert_eq!(res.errors.len(), 0);
+            let super_pos = res.code.find("super(42);").unwrap();
+            let init_pos = res.code.find("_initProto(this)").unwrap();
+            assert!(init_pos > super_pos, "_initProto should be after super()");
         }
     }
+
+    #[test]
+    fn test_constructors_returning_an_object() {
+        let code = r#"
+class A {
+    @field x = 1;
+    @method m() {}
+    constructor() { return other; }
+}
+class B extends Base {
+    @method m() {}
+    constructor() { return other; }
+}
+class C extends Base {
+    @method m() {}
+    constructor(f) {
+        if (f) { const g = () => super(); g(); return other; }
+    }
+}
+class D extends Base {
+    @method m() {}
+}
+"#;
+        let res = transform("test.js".to_string(), code.to_string(), "{}".to_string()).unwrap();
+        assert!(res.errors.is_empty(), "{:?}", res.errors);
+        let class_code = |name: &str| {
+            let start = res.code.find(&format!("class {name} ")).unwrap();
+            let end = res.code[start..].find("\n}\n").unwrap();
+            &res.code[start..start + end]
+        };
+        // The instance is initialized before the constructor body returns
+        // another object, and field initializers still run on it.
+        assert!(class_code("A").contains(
+            "\tconstructor() {\n\t\tif (_initProto) _initProto(this);\n\t\treturn other;\n\t}"
+        ));
+        assert!(class_code("A").contains("x = _init_x(this, 1);"));
+        // Without `super()` there is no instance, and `this` must not be read.
+        assert!(class_code("B").contains("\tconstructor() {\n\t\treturn other;\n\t}"));
+        // A nested `super()` is followed by the initialization.
+        assert!(class_code("C").contains(
+            "const g = () => (super(), _initProto3 && _initProto3(this), this);"
+        ));
+        // A generated constructor passes its arguments on.
+        assert!(class_code("D").contains("\tconstructor(..._args) {\n\t\tsuper(..._args);"));
+    }
 }
+
 #[cfg(test)]
-mod test_decorator_call {
+mod test_output_debug {
     use crate::transform;
 
     #[test]
-    fn test_decorator_with_call() {
+    #[ignore]
+    fn show_export_default_output() {
         let code = r#"
-function noraComponent(hotCtx) {
-    return function(target) {
-        console.log("Decorated with hotCtx:", hotCtx);
-        return target;
-    };
-}
-
-@noraComponent(import.meta.hot)
-class MyClass {
+@logged
+export default class MyClass {
     method() {}
 }
 "#;
 
         let result = transform("test.js".to_string(), code.to_string(), "{}".to_string());
 
-        assert!(result.is_ok());
         if let Ok(res) = result {
-            println!("\n=== TRANSFORMED CODE ===\n{}\n=== END ===\n", res.code);
-            assert_eq!(res.errors.len(), 0);
+            println!("\n=== GENERATED CODE ===");
+            println!("{}", res.code);
+            println!("=== END ===\n");
         }
     }
 }
 
 #[cfg(test)]
-mod test_problem_statement {
+mod test_export_variations {
     use crate::transform;
 
     #[test]
-    fn test_problem_statement_decorator() {
-        let code = r###"
-import { render } from "@nora/solid-xul";
-import { ShareModeElement } from "./browser-share-mode.tsx";
-import {
-  noraComponent,
-  NoraComponentBase,
-} from "#features-chrome/utils/base.ts";
-
-@noraComponent(import.meta.hot)
-export default class BrowserShareMode extends NoraComponentBase {
-  init() {
-    this.logger.info("Hello from Logger!");
-    render(ShareModeElement, document.querySelector("#menu_ToolsPopup"), {
-      marker: document.querySelector("#menu_openFirefoxView")!,
-      hotCtx: import.meta.hot,
-    });
-  }
-
-  _metadata() {
-    return {
-      moduleName: "browser-share-mode",
-      dependencies: [],
-      softDependencies: [],
-    };
-  }
+    #[ignore]
+    fn test_export_class_output() {
+        let code = r#"
+@logged
+export class MyClass {
+    method() {}
 }
-"###;
+"#;
 
-        let result = transform("test.ts".to_string(), code.to_string(), "{}".to_string());
+        let result = transform("test.js".to_string(), code.to_string(), "{}".to_string());
 
-        assert!(result.is_ok());
         if let Ok(res) = result {
-            println!("\n=== TRANSFORMED CODE ===\n{}\n=== END ===\n", res.code);
-
-            assert!(
-                res.code.contains("noraComponent(import.meta.hot)"),
-                "Expected noraComponent(import.meta.hot) in output"
-            );
-            assert!(
-                !res.code.contains("@noraComponent"),
-                "Decorator syntax should be removed"
-            );
-            assert_eq!(res.errors.len(), 0, "Should have no errors");
+            println!("\n=== EXPORT CLASS OUTPUT ===");
+            println!("{}", res.code);
+            println!("=== END ===\n");
         }
     }
 }
 
 #[cfg(test)]
-mod comprehensive_decorator_tests {
-    use crate::transform;
+mod test_export_fix {
+    use crate::{transform, transform_edits};
 
     #[test]
-    fn test_various_decorator_call_patterns() {
+    fn test_class_decorators_ignore_the_name_elsewhere() {
         let code = r#"
+const source = "class Foo {";
+// class Foo { ... }
+class FooHelper {}
+@dec class Foo {
+    m() { return "}"; }
+}
+"#;
+        let res = transform("test.js".to_string(), code.to_string(), "{}".to_string()).unwrap();
+        assert!(res.errors.is_empty(), "{:?}", res.errors);
+        assert!(res.code.contains("const source = \"class Foo {\";"), "{}", res.code);
+        assert!(res.code.contains("// class Foo { ... }"));
+        assert!(res.code.contains("\nclass FooHelper {}"));
+        assert!(res.code.contains(
+            "let Foo = class Foo {\n\tm() {\n\t\treturn \"}\";\n\t}\n};\n[Foo, _classInit] = _applyDecs(Foo, [], [dec]).c;\n_classInit();"
+        ));
+    }
 
-@simple
-class Class1 {}
-@decorator(arg)
-class Class2 {}
-@decorator(arg1, arg2, arg3)
-class Class3 {}
-@decorator(import.meta.hot)
-class Class4 {}
-@namespace.decorator
-class Class5 {}
-@namespace.decorator(arg)
-class Class6 {}
-class Class7 {
-    @bound(this)
-    method() {}
+    #[test]
+    fn test_class_decoration_precedes_following_statements() {
+        let code = "@register class C { @m x() {} }\nregistry.use(C);\nexport @register class D {} registry.use(D);\n";
+        for options in ["{}", r#"{"decoration_site": "after-class"}"#] {
+            let res = transform("test.js".to_string(), code.to_string(), options.to_string())
+                .unwrap();
+            assert!(res.errors.is_empty(), "{:?}", res.errors);
+            assert!(
+                res.code.contains(
+                    "\n};\n[C, _classInit] = _applyDecs(C, [], [register]).c;\n_classInit();\nregistry.use(C);\n"
+                ),
+                "{options}: {}",
+                res.code
+            );
+            assert!(res.code.contains(
+                "let D = class D {};\n[D, _classInit2] = _applyDecs(D, [], [register]).c;\n_classInit2();\nexport { D };\nregistry.use(D);\n"
+            ));
+        }
+    }
+
+    #[test]
+    fn test_decorated_class_beside_re_exports() {
+        let code = r#"export * from "x";
+export * as ns from "y";
+export { a } from "z";
+@dec class C { @m x() {} }
+export { C as D };
+"#;
+        let res = transform("test.js".to_string(), code.to_string(), "{}".to_string()).unwrap();
+        assert!(res.errors.is_empty(), "{:?}", res.errors);
+        // Helpers come first, the re-exports are kept as written, and the
+        // init variables are declared right before the class.
+        assert!(res.code.starts_with("/**"), "{}", res.code);
+        assert!(res.code.contains(
+            "\nexport * from \"x\";\nexport * as ns from \"y\";\nexport { a } from \"z\";\nlet _initProto, _classInit;\nlet C = class C {"
+        ));
+        assert!(res.code.ends_with("[C, _classInit] = _applyDecs(C, [], [dec]).c;\n_classInit();\nexport { C as D };\n"));
+
+        let edits =
+            transform_edits("test.js".to_string(), code.to_string(), "{}".to_string()).unwrap();
+        assert_eq!(edits.len(), 2, "{:?}", edits);
+        assert_eq!((edits[0].start, edits[0].end), (0, 0));
+        assert_eq!(edits[1].start as usize, code.find("@dec").unwrap());
+    }
+
+    #[test]
+    fn test_class_decorators_with_braces_in_strings_and_comments() {
+        let code = r#"@dec class Foo {
+    m() {
+        const s = "class X {";
+        const t = `${ {a:1}.a } }`; // }
+        /* } */ return s + t + '}' + /}/.source;
+    }
 }
-class Class8 {
-    @validate("string")
-    field = "";
+after();
+"#;
+        let res = transform("test.js".to_string(), code.to_string(), "{}".to_string()).unwrap();
+        assert!(res.errors.is_empty(), "{:?}", res.errors);
+        // The class ends at its real closing brace, so the rebinding follows
+        // the whole body and the code after it is untouched.
+        let class_code = &res.code[res.code.find("let Foo = class Foo {").unwrap()..];
+        assert!(class_code.contains("const s = \"class X {\";"), "{}", class_code);
+        assert!(class_code.contains("const t = `${{ a: 1 }.a} }`;"), "{}", class_code);
+        assert!(class_code.contains(
+            "return s + t + \"}\" + /}/.source;\n\t}\n};\n[Foo, _classInit] = _applyDecs(Foo, [], [dec]).c;\n_classInit();\nafter();"
+        ));
+
+        let edits =
+            transform_edits("test.js".to_string(), code.to_string(), "{}".to_string()).unwrap();
+        let class_edit = edits.last().unwrap();
+        assert_eq!(class_edit.start, 0);
+        assert_eq!(&code[class_edit.end as usize..], "\nafter();\n");
+    }
+
+    #[test]
+    fn test_export_default_class_no_invalid_syntax() {
+        let code = r#"
+@logged
+export default class MyClass {
+    method() {}
 }
 "#;
 
@@ -905,78 +4723,162 @@ class Class8 {
         assert!(result.is_ok());
         if let Ok(res) = result {
             assert!(
-                res.code.contains("simple"),
-                "Simple decorator should be preserved"
-            );
-            assert!(
-                res.code.contains("decorator(arg)"),
-                "Single argument call should be preserved"
+                !res.code.contains("export default var"),
+                "Bug: Found 'export default var' which is invalid syntax"
             );
             assert!(
-                res.code.contains("decorator(arg1, arg2, arg3)"),
-                "Multiple arguments call should be preserved"
+                !res.code.contains("export default let"),
+                "Bug: Found 'export default let' which is invalid syntax"
             );
+
             assert!(
-                res.code.contains("decorator(import.meta.hot)"),
-                "Complex expression call should be preserved"
+                res.code.contains("let MyClass = class MyClass"),
+                "Should use 'let' for variable declaration"
             );
+
             assert!(
-                res.code.contains("namespace.decorator"),
-                "Member expression should be preserved"
+                res.code.contains("export default MyClass"),
+                "Should have export default identifier (not class declaration)"
             );
+
+            let let_pos = res
+                .code
+                .find("let MyClass")
+                .expect("Should find let declaration");
+            let export_pos = res
+                .code
+                .find("export default")
+                .expect("Should find export default");
             assert!(
-                res.code.contains("namespace.decorator(arg)"),
-                "Member expression call should be preserved"
+                let_pos < export_pos,
+                "Variable declaration should come before export statement"
             );
+        }
+    }
+
+    #[test]
+    fn test_export_named_class_no_invalid_syntax() {
+        let code = r#"
+@logged
+export class MyClass {
+    method() {}
+}
+"#;
+
+        let result = transform("test.js".to_string(), code.to_string(), "{}".to_string());
+
+        assert!(result.is_ok());
+        if let Ok(res) = result {
             assert!(
-                res.code.contains("bound(this)"),
-                "Method decorator call should be preserved"
+                !res.code.contains("export var"),
+                "Bug: Found 'export var' which is invalid syntax"
             );
             assert!(
-                res.code.contains("validate(\"string\")"),
-                "Field decorator call should be preserved"
+                !res.code.contains("export let"),
+                "Bug: Found 'export let' - variable should come before export"
             );
 
-            assert!(!res.code.contains("@simple"), "@ syntax should be removed");
             assert!(
-                !res.code.contains("@decorator"),
-                "@ syntax should be removed"
+                res.code.contains("let MyClass = class MyClass"),
+                "Should use 'let' for variable declaration"
             );
+
             assert!(
-                !res.code.contains("@namespace"),
-                "@ syntax should be removed"
+                res.code.contains("export { MyClass }"),
+                "Should have export {{ MyClass }} for named export"
             );
-            assert!(!res.code.contains("@bound"), "@ syntax should be removed");
+
+            let let_pos = res
+                .code
+                .find("let MyClass")
+                .expect("Should find let declaration");
+            let export_pos = res.code.find("export {").expect("Should find export");
             assert!(
-                !res.code.contains("@validate"),
-                "@ syntax should be removed"
+                let_pos < export_pos,
+                "Variable declaration should come before export statement"
             );
-
-            assert_eq!(res.errors.len(), 0, "Should have no errors");
         }
     }
+
+    #[test]
+    fn test_regular_class_uses_let() {
+        let code = r#"
+@logged
+class MyClass {
+    method() {}
 }
+"#;
 
-#[cfg(test)]
-mod test_constructor_injection {
-    use crate::transform;
+        let result = transform("test.js".to_string(), code.to_string(), "{}".to_string());
+
+        assert!(result.is_ok());
+        if let Ok(res) = result {
+            assert!(
+                res.code.contains("let MyClass = class MyClass"),
+                "Should use 'let' for ESNext compatibility"
+            );
+            assert!(
+                !res.code.contains("var MyClass"),
+                "Should not use 'var' - use 'let' for ESNext"
+            );
+        }
+    }
 
+    // Like the class declaration it replaces, the `let` binding is in its
+    // temporal dead zone until the class is evaluated: the early `new C()`
+    // still throws, while the call inside `make` (run later) still works.
     #[test]
-    #[ignore]
-    fn test_field_decorator_with_constructor() {
+    fn test_decorated_class_keeps_tdz() {
         let code = r#"
-function validated(value, { kind }) {
-    if (kind === "field") {
-        return function(initialValue) {
-            console.log("Field initialized");
-            return initialValue;
-        };
+function make() { return new C(); }
+new C();
+@dec class C {}
+"#;
+
+        let res = transform("test.js".to_string(), code.to_string(), "{}".to_string()).unwrap();
+        let early_use = res.code.find("\nnew C();").unwrap();
+        let binding = res.code.find("let C = class C").unwrap();
+        assert!(early_use < binding);
+        assert!(res.code.find("return new C();").unwrap() < binding);
+        assert!(!res.code.contains("var C"));
     }
+
+    #[test]
+    fn test_default_export_static_block_self_reference() {
+        let code = r#"
+export default @dec class C {
+    static instance;
+    static { C.instance = new C(); }
 }
+"#;
 
-class C {
-    @validated
-    field = 1;
+        let res = transform("test.js".to_string(), code.to_string(), "{}".to_string()).unwrap();
+        // The class expression keeps its name, so `C` inside the body is
+        // bound by the class itself while the outer `let C` is still in TDZ.
+        let class_start = res.code.find("let C = class C {").unwrap();
+        let self_reference = res.code.find("C.instance = new C();").unwrap();
+        let class_end = res.code.find("[C, _classInit] = _applyDecs(C, [], [dec]).c;\n_classInit();").unwrap();
+        assert!(class_start < self_reference && self_reference < class_end);
+        assert!(res.code[class_end..].contains("export default C;"));
+    }
+
+    #[test]
+    fn test_leading_comment_stays_above_decorated_class() {
+        let code = "const a = 1;\n// Widget docs\n@dec\nclass Widget {}\n";
+
+        let res = transform("test.js".to_string(), code.to_string(), "{}".to_string()).unwrap();
+        assert!(res
+            .code
+            .contains("const a = 1;\nlet _classInit;\n// Widget docs\nlet Widget = class Widget {"));
+        assert_eq!(res.code.matches("// Widget docs").count(), 1);
+    }
+
+    #[test]
+    fn test_helpers_use_const_let_not_var() {
+        let code = r#"
+@logged
+class MyClass {
+    method() {}
 }
 "#;
 
@@ -984,295 +4886,1170 @@ class C {
 
         assert!(result.is_ok());
         if let Ok(res) = result {
-            println!(
-                "\n=== FIELD DECORATOR OUTPUT ===\n{}\n=== END ===\n",
-                res.code
+            assert!(
+                res.code.contains("function _applyDecs"),
+                "Should have helper functions"
+            );
+
+            let const_count = res.code.matches(" const ").count();
+            let let_count = res.code.matches(" let ").count();
+            let var_count = res.code.matches(" var ").count();
+
+            assert!(
+                const_count + let_count > 0,
+                "Should use const/let in helpers"
+            );
+            assert_eq!(
+                var_count, 0,
+                "Should not use 'var' - all should be converted to const/let for ESNext"
             );
-            assert!(res.code.contains("constructor()"));
-            assert!(res.code.contains("_initProto(this)"));
         }
     }
 
     #[test]
-    #[ignore]
-    fn test_field_decorator_with_existing_constructor() {
-        let code = r#"
-function validated(value, { kind }) {
-    if (kind === "field") {
-        return function(initialValue) {
-            return initialValue;
+    fn test_decorator_placement_around_export() {
+        let output = |code: &str| {
+            let res = transform("test.js".to_string(), code.to_string(), "{}".to_string()).unwrap();
+            assert!(res.errors.is_empty(), "{:?}", res.errors);
+            res.code
         };
+
+        let named = output("@dec export class A { @dec m() {} }\n");
+        assert_eq!(named, output("export @dec class A { @dec m() {} }\n"));
+        assert!(named.contains("[A, _classInit] = _applyDecs(A, [], [dec]).c;\n_classInit();\nexport { A };"));
+
+        let default = output("export default @dec class A { @dec m() {} }\n");
+        assert_eq!(default, output("@dec export default class A { @dec m() {} }\n"));
+        assert!(default.contains("[A, _classInit] = _applyDecs(A, [], [dec]).c;\n_classInit();\nexport default A;"));
+
+        let anonymous = output("export default @dec class { @dec m() {} }\n");
+        assert_eq!(anonymous, output("@dec export default class { @dec m() {} }\n"));
+        assert!(anonymous.contains("let _default = _setFunctionName(class {"));
+        assert!(anonymous.contains(
+            "}, \"default\");\n[_default, _classInit] = _applyDecs(_default, [], [dec]).c;\n_classInit();\nexport default _default;"
+        ));
+        assert!(anonymous.contains("function _setFunctionName"));
+    }
+
+    #[test]
+    fn test_decorators_between_export_and_default() {
+        let res = transform(
+            "test.js".to_string(),
+            "export @dec({ a: 1 }) default class {}\n".to_string(),
+            "{}".to_string(),
+        )
+        .unwrap();
+        assert!(
+            res.errors[0].starts_with(
+                "Decorators must follow `export default`, not precede `default` (at offset 0): \
+                 write `export default @dec({ a: 1 }) class` or `@dec({ a: 1 }) export default class`"
+            ),
+            "{:?}",
+            res.errors
+        );
+
+        let res = transform(
+            "test.js".to_string(),
+            "export @dec const x = 1;\n".to_string(),
+            "{}".to_string(),
+        )
+        .unwrap();
+        assert!(res.errors[0].contains("can only decorate a class declaration"));
+        assert!(res.errors[0].contains("`@dec` is followed by `const`"));
+
+        let err = transform_edits(
+            "test.js".to_string(),
+            "export @dec default class {}".to_string(),
+            "{}".to_string(),
+        )
+        .unwrap_err();
+        assert!(err.starts_with("Decorators must follow `export default`"), "{err}");
+
+        let valid = transform(
+            "test.js".to_string(),
+            "export @dec class A {}\nexport default @dec class {}\n".to_string(),
+            "{}".to_string(),
+        )
+        .unwrap();
+        assert!(valid.errors.is_empty(), "{:?}", valid.errors);
     }
 }
 
-class C {
-    @validated
-    field = 1;
-    
-    constructor() {
-        console.log("Constructor");
+#[cfg(test)]
+mod test_unicode_names {
+    use crate::transform;
+
+    #[test]
+    fn test_unicode_class_and_member_names() {
+        let code = r#"
+const ü = "日本";
+@dec class Café {
+    @dec café() {}
+    @dec "日本語" = 1;
+    @dec [ü]() {}
+}
+"#;
+
+        let res = transform("test.js".to_string(), code.to_string(), "{}".to_string()).unwrap();
+        assert!(res.errors.is_empty(), "{:?}", res.errors);
+        assert!(res.code.contains("let Café = class Café {"));
+        assert!(res
+            .code
+            .contains("[Café, _classInit] = _applyDecs(Café, [], [dec]).c;\n_classInit();"));
+
+        let class_code = &res.code[res.code.find("let Café").unwrap()..];
+        let compact: String = class_code.split_whitespace().collect();
+        assert!(compact.contains(r#"[dec,2,"café",false]"#));
+        assert!(compact.contains(r#"[dec,0,"日本語",false]"#));
+        assert!(compact.contains(r#""日本語"=_init____(this,1);"#));
+        assert!(compact.contains("[(_dec=dec,_computedKey=_toPropertyKey(ü))](){}"));
+        assert!(!class_code.contains('@'));
+    }
+
+    #[test]
+    fn test_class_name_prefix_of_unicode_name() {
+        let code = r#"
+class Café {}
+@dec class Caf {}
+"#;
+
+        let res = transform("test.js".to_string(), code.to_string(), "{}".to_string()).unwrap();
+        assert!(res.code.contains("\nclass Café {}"));
+        assert!(res.code.contains("let Caf = class Caf {"));
+        assert!(res.code.contains("[Caf, _classInit] = _applyDecs(Caf, [], [dec]).c;\n_classInit();"));
+        assert!(!res.code.contains("let Café"));
     }
 }
+
+#[cfg(test)]
+mod test_class_expressions {
+    use crate::transform;
+
+    #[test]
+    fn test_decorated_class_expression_in_exported_const() {
+        let code = r#"
+export const Widget = @dec class {
+    render() {}
+};
+"#;
+        let res = transform("test.js".to_string(), code.to_string(), "{}".to_string()).unwrap();
+        assert!(res.errors.is_empty(), "{:?}", res.errors);
+        assert!(res.code.contains(
+            "let _Widget, _classInit;\nexport const Widget = ([_Widget, _classInit] = _applyDecs(_setFunctionName(class {"
+        ));
+        assert!(res
+            .code
+            .contains("}, \"Widget\"), [], [dec]).c, _classInit(), _Widget);"));
+        assert!(!res.code.contains("Widget = _applyDecs(Widget"));
+        assert!(!res.code.contains("let Widget"));
+    }
+
+    #[test]
+    fn test_decorated_class_expressions_as_arguments_and_const() {
+        let code = "const C = @dec class {};\nregister(@dec class Foo {});\nregister(@dec class {\n    @method m() {}\n});\n";
+        let res = transform("test.js".to_string(), code.to_string(), "{}".to_string()).unwrap();
+        assert!(res.errors.is_empty(), "{:?}", res.errors);
+        let user_code = &res.code[res.code.find("const C =").unwrap()..];
+        assert!(!user_code.contains('@'), "{}", user_code);
+        assert!(res.code.contains(
+            "const C = ([_C, _classInit] = _applyDecs(_setFunctionName(class {}, \"C\"), [], [dec]).c, _classInit(), _C);"
+        ));
+        assert!(res.code.contains(
+            "register(([_Foo, _classInit2] = _applyDecs(class Foo {}, [], [dec]).c, _classInit2(), _Foo));"
+        ));
+        // An anonymous argument keeps its empty name and needs no binding.
+        assert!(
+            res.code
+                .contains("register(([_class, _classInit3] = _applyDecs(class {\n"),
+            "{}",
+            res.code
+        );
+        assert!(
+            res.code
+                .contains("\tm() {}\n}, [], [dec]).c, _classInit3(), _class));"),
+            "{}",
+            res.code
+        );
+        assert!(res.code.contains("[_initProto] = _applyDecs(this, [["));
+        assert_eq!(res.stats.class_decorators, 3);
+    }
+
+    #[test]
+    fn test_decorated_class_assigned_to_binding() {
+        let code = "let C;\nC = @dec class {\n    @method m() {}\n};\nD ||= @dec class {};\nE += @dec class {};\n";
+        let res = transform("test.js".to_string(), code.to_string(), "{}".to_string()).unwrap();
+        assert!(res.errors.is_empty(), "{:?}", res.errors);
+        assert!(
+            res.code
+                .contains("\nC = ([_C, _classInit] = _applyDecs(_setFunctionName(class {"),
+            "{}",
+            res.code
+        );
+        assert!(res.code.contains("}, \"C\"), [], [dec]).c, _classInit(), _C);"));
+        assert!(res.code.contains("[_initProto] = _applyDecs(this, [["));
+        assert!(res.code.contains(
+            "D ||= ([_D, _classInit2] = _applyDecs(_setFunctionName(class {}, \"D\"), [], [dec]).c, _classInit2(), _D);"
+        ));
+        // Compound assignments do not name the class.
+        assert!(res.code.contains(
+            "E += ([_class, _classInit3] = _applyDecs(class {}, [], [dec]).c, _classInit3(), _class);"
+        ));
+    }
+
+    #[test]
+    fn test_decorated_class_assigned_to_member() {
+        let code = r#"
+function dec(value) { return value; }
+const obj = {};
+obj.prop = @dec class {
+    method() {}
+};
+globalThis.Bar = @dec class Bar {};
 "#;
 
         let result = transform("test.js".to_string(), code.to_string(), "{}".to_string());
 
         assert!(result.is_ok());
         if let Ok(res) = result {
-            println!(
-                "\n=== FIELD WITH EXISTING CTOR ===\n{}\n=== END ===\n",
+            assert!(
+                !res.code.contains("@dec"),
+                "Decorator syntax should be removed"
+            );
+            assert!(
+                res.code
+                    .contains("obj.prop = ([_class, _classInit] = _applyDecs(class {"),
+                "Assignment target should receive the decorated class: {}",
                 res.code
             );
-            assert!(res.code.contains("_initProto(this)"));
+            assert!(res.code.contains("}, [], [dec]).c, _classInit(), _class);"));
+            assert!(res.code.contains(
+                "globalThis.Bar = ([_Bar, _classInit2] = _applyDecs(class Bar {}, [], [dec]).c, _classInit2(), _Bar);"
+            ));
+            assert!(res.code.contains("function _applyDecs"));
+            assert_eq!(res.errors.len(), 0);
         }
     }
-}
-
-#[cfg(test)]
-mod test_super_handling {
-    use crate::transform;
 
     #[test]
-    #[ignore]
-    fn test_field_decorator_with_super() {
+    fn test_decorated_classes_in_conditional_branches() {
         let code = r#"
-function validated(value, { kind }) {
-    if (kind === "field") {
-        return function(initialValue) {
-            return initialValue;
-        };
+const X = cond ? @dec class A { @m a() {} } : @dec class B { @m b() {} };
+"#;
+
+        let res = transform("test.js".to_string(), code.to_string(), "{}".to_string()).unwrap();
+        assert!(res.errors.is_empty(), "{:?}", res.errors);
+        assert!(!res.code.contains("@dec"));
+        assert!(!res.code.contains("@m"));
+
+        let statement = &res.code[res.code.find("const X = ").unwrap()..];
+        assert!(statement.starts_with("const X = cond ? ([_A, _classInit] = _applyDecs(class A {"));
+        assert!(statement
+            .contains("}, [], [dec]).c, _classInit(), _A) : ([_B, _classInit2] = _applyDecs(class B {"));
+        assert!(statement.contains("}, [], [dec]).c, _classInit2(), _B);"));
+        let compact: String = statement.split_whitespace().collect();
+        assert!(compact.contains(r#"[m,2,"a",false]"#));
+        assert!(compact.contains(r#"[m,2,"b",false]"#));
     }
 }
 
-class Base {
-    constructor(x) {
-        this.x = x;
+#[cfg(test)]
+mod test_transform_edits {
+    use crate::{transform, transform_edits};
+    use oxc_allocator::Allocator;
+    use oxc_codegen::Codegen;
+    use oxc_parser::Parser;
+    use oxc_span::SourceType;
+
+    fn normalize(code: &str) -> String {
+        let allocator = Allocator::default();
+        let parsed = Parser::new(&allocator, code, SourceType::mjs()).parse();
+        assert!(
+            parsed.errors.is_empty(),
+            "Should parse: {:?}",
+            parsed.errors
+        );
+        Codegen::new().build(&parsed.program).code
     }
+
+    #[test]
+    fn test_edits_for_single_decorated_method_are_localized() {
+        let code = r#"import { logged } from "./logged.js";
+
+const untouched   =   { a: 1 };
+
+class C {
+    @logged
+    method() {}
 }
 
-class C extends Base {
-    @validated
-    field = 1;
-    
-    constructor() {
-        super(42);
-        console.log("After super");
+export function helper() { return untouched; }
+"#;
+
+        let edits = transform_edits("test.js".to_string(), code.to_string(), "{}".to_string())
+            .expect("Edits should be produced");
+
+        assert_eq!(
+            edits.len(),
+            2,
+            "Expected a helper insertion and one class edit"
+        );
+        assert_eq!((edits[0].start, edits[0].end), (0, 0));
+        assert!(edits[0].text.contains("function _applyDecs"));
+
+        let class_start = code.find("class C").unwrap() as u32;
+        let class_end = code.find("\n\nexport function").unwrap() as u32;
+        assert_eq!((edits[1].start, edits[1].end), (class_start, class_end));
+        assert!(edits[1].text.starts_with("let _initProto;"));
+
+        let mut patched = code.to_string();
+        for edit in edits.iter().rev() {
+            patched.replace_range(edit.start as usize..edit.end as usize, &edit.text);
+        }
+        assert!(patched.contains("const untouched   =   { a: 1 };"));
+
+        let full = transform("test.js".to_string(), code.to_string(), "{}".to_string()).unwrap();
+        assert_eq!(normalize(&patched), normalize(&full.code));
     }
-}
+
+    #[test]
+    fn test_class_decorators_before_lines_starting_with_brackets() {
+        // Each of these lines would continue the previous statement if the
+        // rewritten class did not end in an explicit `;`.
+        let code = r#"import { dec, log } from "./dec.js";
+@dec class A {}
+(function () { log("iife"); })();
+@dec class B { @dec m() {} }
+[1, 2].forEach(log);
+export default @dec class {}
+`template`.length;
 "#;
+        let full = transform("test.js".to_string(), code.to_string(), "{}".to_string()).unwrap();
+        assert!(full.errors.is_empty(), "{:?}", full.errors);
+        assert!(full.code.contains("_classInit();\n(function() {"));
+        assert!(full.code.contains("_classInit2();\n[1, 2].forEach(log);"));
+        assert!(full.code.contains(";\n`template`.length;"), "{}", full.code);
+
+        let edits = transform_edits("test.js".to_string(), code.to_string(), "{}".to_string())
+            .expect("Edits should be produced");
+        let mut patched = code.to_string();
+        for edit in edits.iter().rev() {
+            assert!(edit.start == edit.end || edit.text.trim_end().ends_with(';'));
+            patched.replace_range(edit.start as usize..edit.end as usize, &edit.text);
+        }
+        assert_eq!(normalize(&patched), normalize(&full.code));
+    }
 
-        let result = transform("test.js".to_string(), code.to_string(), "{}".to_string());
+    #[test]
+    fn test_edits_for_class_decorators_match_transform() {
+        let code = r#"const before = 1;
 
-        assert!(result.is_ok());
-        if let Ok(res) = result {
-            println!("\n=== FIELD WITH SUPER ===\n{}\n=== END ===\n", res.code);
-            assert!(res.code.contains("super(42)"));
-            assert!(res.code.contains("_initProto(this)"));
+@dec class A {}
 
-            let super_pos = res.code.find("super(42);").unwrap();
-            let init_pos = res.code.find("_initProto(this)").unwrap();
-            assert!(init_pos > super_pos, "_initProto should be after super()");
+export @dec class B {}
+
+export default @dec class {}
+"#;
+        let edits = transform_edits("test.js".to_string(), code.to_string(), "{}".to_string())
+            .unwrap();
+        // The helpers, then one edit per decorated class.
+        assert_eq!(edits.len(), 4, "{:?}", edits);
+        assert!(edits[3]
+            .text
+            .ends_with("[_default, _classInit3] = _applyDecs(_default, [], [dec]).c;\n_classInit3();\nexport default _default;"));
+
+        let mut patched = code.to_string();
+        for edit in edits.iter().rev() {
+            patched.replace_range(edit.start as usize..edit.end as usize, &edit.text);
+        }
+        let full = transform("test.js".to_string(), code.to_string(), "{}".to_string()).unwrap();
+        assert_eq!(normalize(&patched), normalize(&full.code));
+    }
+
+    #[test]
+    fn test_decorated_class_without_trailing_newline() {
+        for code in [
+            "@dec class C { @dec m() {} }",
+            "const a = 1;\nexport default @dec class { @dec m() {} }",
+            "#!/usr/bin/env node\n@dec\nclass C {}",
+        ] {
+            let full =
+                transform("test.js".to_string(), code.to_string(), "{}".to_string()).unwrap();
+            assert!(full.errors.is_empty(), "{:?}", full.errors);
+            assert!(
+                full.code.ends_with("_classInit();\n")
+                    || full.code.ends_with("export default _default;\n")
+            );
+            let map = oxc_sourcemap::SourceMap::from_json_string(full.map.as_deref().unwrap())
+                .unwrap();
+            let last_line = code.lines().count() as u32 - 1;
+            assert!(map.get_source_view_tokens().all(|t| t.get_src_line() <= last_line));
+
+            // The class edit runs to the end of the file.
+            let edits =
+                transform_edits("test.js".to_string(), code.to_string(), "{}".to_string())
+                    .unwrap();
+            assert_eq!(edits.last().unwrap().end as usize, code.len());
+            let mut patched = code.to_string();
+            for edit in edits.iter().rev() {
+                patched.replace_range(edit.start as usize..edit.end as usize, &edit.text);
+            }
+            assert_eq!(normalize(&patched), normalize(&full.code));
+
+            let legacy = transform(
+                "test.js".to_string(),
+                code.to_string(),
+                r#"{"legacy_decorators": true}"#.to_string(),
+            )
+            .unwrap();
+            assert!(legacy.errors.is_empty(), "{:?}", legacy.errors);
+            assert!(!legacy.code.contains("@dec"), "{}", legacy.code);
+            normalize(&legacy.code);
         }
     }
+
+    #[test]
+    fn test_edits_follow_every_option() {
+        let js = r#"#!/usr/bin/env node
+"use client";
+const before = 1;
+class A { @dec m() {} @dec static s = 1; }
+@dec class B {}
+after(A[Symbol.metadata], B);
+"#;
+        let ts = r#"import type { T } from "./t.js";
+interface I { x: T }
+class A { @dec m(a: T): void {} }
+type U = I;
+const untouched = 1;
+"#;
+        let cases = [
+            ("test.js", js, r#"{"legacy_decorators": true}"#),
+            ("test.js", js, r#"{"auto_import": {"dec": "./dec.js"}}"#),
+            ("test.js", js, r#"{"decoration_site": "after-class"}"#),
+            ("test.js", js, r#"{"collapse_init_vars": true}"#),
+            ("test.js", js, r#"{"banner": "/* banner */", "polyfill_symbol_metadata": true}"#),
+            ("test.cjs", js, r#"{"force_strict": true, "newline": "crlf"}"#),
+            ("test.ts", ts, r#"{"strip_types": true}"#),
+        ];
+        for (filename, code, options) in cases {
+            let full =
+                transform(filename.to_string(), code.to_string(), options.to_string()).unwrap();
+            assert!(full.errors.is_empty(), "{}: {:?}", options, full.errors);
+            let edits =
+                transform_edits(filename.to_string(), code.to_string(), options.to_string())
+                    .unwrap();
+            let mut patched = code.to_string();
+            for edit in edits.iter().rev() {
+                patched.replace_range(edit.start as usize..edit.end as usize, &edit.text);
+            }
+            // The hashbang, banner, directives and helpers are in place.
+            let head = |code: &str| code.lines().take(4).collect::<Vec<_>>().join("\n");
+            assert_eq!(head(&patched), head(&full.code), "{}", options);
+            assert_eq!(normalize(&patched), normalize(&full.code), "{}", options);
+        }
+    }
+
+    #[test]
+    fn test_no_edits_without_decorators() {
+        let edits = transform_edits(
+            "test.js".to_string(),
+            "class C { m() {} }".to_string(),
+            "{}".to_string(),
+        )
+        .unwrap();
+        assert!(edits.is_empty());
+    }
 }
 
 #[cfg(test)]
-mod test_output_debug {
-    use crate::transform;
+mod test_descriptor_hook {
+    use crate::{transform_with_descriptor_hook, DecoratorKind, ExtraDescriptorFields};
+    use serde_json::json;
+    use std::cell::RefCell;
 
     #[test]
-    #[ignore]
-    fn show_export_default_output() {
+    fn test_hook_appends_descriptor_fields() {
         let code = r#"
-@logged
-export default class MyClass {
-    method() {}
+class C {
+    @route("/users") list() {}
+    @inject service;
 }
 "#;
+        let seen = RefCell::new(Vec::new());
+        let hook = |metadata: &crate::DecoratorMetadata| {
+            seen.borrow_mut().push((
+                metadata.name.clone(),
+                metadata.kind,
+                metadata.decorators.clone(),
+            ));
+            (metadata.kind == DecoratorKind::Method).then(|| ExtraDescriptorFields {
+                fields: vec![json!({ "framework": "router" }), json!(1)],
+            })
+        };
 
-        let result = transform("test.js".to_string(), code.to_string(), "{}".to_string());
-
-        if let Ok(res) = result {
-            println!("\n=== GENERATED CODE ===");
-            println!("{}", res.code);
-            println!("=== END ===\n");
-        }
+        let res = transform_with_descriptor_hook(
+            "test.js".to_string(),
+            code.to_string(),
+            "{}".to_string(),
+            &hook,
+        )
+        .unwrap();
+
+        let class_code = &res.code[res.code.find("class C").unwrap()..];
+        let compact: String = class_code.split_whitespace().collect();
+        assert!(compact.contains(r#"[route("/users"),2,"list",false,{"framework":"router"},1]"#));
+        assert!(compact.contains(r#"[inject,0,"service",false]"#));
+        assert_eq!(
+            seen.into_inner(),
+            vec![
+                (
+                    Some("list".to_string()),
+                    DecoratorKind::Method,
+                    vec!["route(\"/users\")".to_string()]
+                ),
+                (
+                    Some("service".to_string()),
+                    DecoratorKind::Field,
+                    vec!["inject".to_string()]
+                ),
+            ]
+        );
     }
 }
 
 #[cfg(test)]
-mod test_export_variations {
-    use crate::transform;
+mod test_legacy_decorators {
+    use crate::{transform, transform_dual};
 
     #[test]
-    #[ignore]
-    fn test_export_class_output() {
+    fn test_legacy_member_and_class_decorators() {
         let code = r#"
-@logged
-export class MyClass {
-    method() {}
+@sealed
+export class C {
+    @log x = 1;
+    @log m() {}
+    @log static s() {}
 }
 "#;
+        let res = transform(
+            "test.ts".to_string(),
+            code.to_string(),
+            r#"{"legacy_decorators": true}"#.to_string(),
+        )
+        .unwrap();
+        assert!(res.errors.is_empty(), "errors: {:?}", res.errors);
+        assert!(res.code.contains("var __decorate"));
+        assert!(!res.code.contains("_applyDecs"));
+        assert!(res.code.contains("let C = class C {"));
+        assert!(res
+            .code
+            .contains("__decorate([log], C.prototype, \"x\", void 0);"));
+        assert!(res
+            .code
+            .contains("__decorate([log], C.prototype, \"m\", null);"));
+        assert!(res.code.contains("__decorate([log], C, \"s\", null);"));
+        assert!(res
+            .code
+            .contains("C = __decorate([sealed], C);\nexport { C };"));
+        let class_code = &res.code[res.code.find("let C = class C").unwrap()..];
+        assert!(!class_code.contains('@'));
+        assert_eq!(res.stats.member_decorators, 3);
+        assert_eq!(res.stats.class_decorators, 1);
+        assert_eq!(res.stats.helpers_emitted, 1);
+    }
 
-        let result = transform("test.js".to_string(), code.to_string(), "{}".to_string());
+    #[test]
+    fn test_legacy_parameter_decorators() {
+        let code = r#"
+class Service {
+    constructor(@inject dep) {}
+    @log m(a, @inject b) {}
+}
+"#;
+        let res = transform(
+            "test.ts".to_string(),
+            code.to_string(),
+            r#"{"legacy_decorators": true}"#.to_string(),
+        )
+        .unwrap();
+        assert!(res.errors.is_empty(), "errors: {:?}", res.errors);
+        assert!(res.code.contains("var __param"));
+        assert!(res.code.contains("let Service = class Service {"));
+        assert!(res.code.contains("constructor(dep) {}"));
+        assert!(res.code.contains(
+            "__decorate([log, __param(1, inject)], Service.prototype, \"m\", null);"
+        ));
+        assert!(res
+            .code
+            .contains("Service = __decorate([__param(0, inject)], Service);"));
+
+        let res = transform(
+            "test.ts".to_string(),
+            "class C { @log m() {} }".to_string(),
+            r#"{"legacy_decorators": true}"#.to_string(),
+        )
+        .unwrap();
+        assert!(!res.code.contains("__param"));
+    }
 
-        if let Ok(res) = result {
-            println!("\n=== EXPORT CLASS OUTPUT ===");
-            println!("{}", res.code);
-            println!("=== END ===\n");
-        }
+    #[test]
+    fn test_legacy_rejects_class_expressions() {
+        let code = "const C = @dec class {};\n";
+        let res = transform(
+            "test.ts".to_string(),
+            code.to_string(),
+            r#"{"legacy_decorators": true}"#.to_string(),
+        )
+        .unwrap();
+        assert_eq!(res.errors.len(), 1);
+        assert!(res.errors[0].contains("only supported on class declarations"));
+    }
+
+    #[test]
+    fn test_transform_dual() {
+        let code = "class C {\n  @dec m() {}\n}\n";
+        let (stage3, legacy) =
+            transform_dual("test.ts".to_string(), code.to_string(), "{}".to_string()).unwrap();
+        assert!(stage3.code.contains("_applyDecs(this"));
+        assert!(!stage3.code.contains("__decorate"));
+        assert!(legacy
+            .code
+            .contains("__decorate([dec], C.prototype, \"m\", null);"));
+        assert!(!legacy.code.contains("_applyDecs"));
+        assert_ne!(stage3.code, legacy.code);
     }
 }
 
 #[cfg(test)]
-mod test_export_fix {
-    use crate::transform;
+mod test_external_helpers {
+    use super::*;
+
+    fn imported_helpers(code: &str) -> Vec<String> {
+        let line = code
+            .lines()
+            .find(|line| line.ends_with("from \"./decorator-helpers.js\";"))
+            .expect("helper import");
+        let names = &line[line.find('{').unwrap() + 1..line.find('}').unwrap()];
+        names.split(',').map(|name| name.trim().to_string()).collect()
+    }
 
     #[test]
-    fn test_export_default_class_no_invalid_syntax() {
-        let code = r#"
-@logged
-export default class MyClass {
-    method() {}
-}
-"#;
+    fn test_shared_helper_module_exports_imported_helpers() {
+        let options = r#"{"external_helpers": "./decorator-helpers.js"}"#;
+        let plain = transform(
+            "a.js".to_string(),
+            "class A { @dec m() {} }".to_string(),
+            options.to_string(),
+        )
+        .unwrap();
+        let computed = transform(
+            "b.js".to_string(),
+            "class B { @dec [key]() {} }".to_string(),
+            options.to_string(),
+        )
+        .unwrap();
+        assert!(plain.errors.is_empty(), "errors: {:?}", plain.errors);
+        assert!(!plain.code.contains("function _applyDecs"));
+        assert_eq!(plain.stats.helpers_emitted, 0);
+        assert_eq!(imported_helpers(&plain.code), ["_applyDecs"]);
+        assert_eq!(
+            imported_helpers(&computed.code),
+            ["_applyDecs", "_toPropertyKey"]
+        );
 
-        let result = transform("test.js".to_string(), code.to_string(), "{}".to_string());
+        let module = helper_module(options.to_string()).unwrap();
+        assert!(module.contains("function _applyDecs"));
+        let exports = module.lines().last().unwrap();
+        assert_eq!(exports, "export { _applyDecs, _toPropertyKey, _setFunctionName };");
 
-        assert!(result.is_ok());
-        if let Ok(res) = result {
-            assert!(
-                !res.code.contains("export default var"),
-                "Bug: Found 'export default var' which is invalid syntax"
-            );
-            assert!(
-                !res.code.contains("export default let"),
-                "Bug: Found 'export default let' which is invalid syntax"
-            );
+        let legacy = helper_module(r#"{"legacy_decorators": true}"#.to_string()).unwrap();
+        assert!(legacy.ends_with("export { __decorate, __param };\n"));
+    }
 
-            assert!(
-                res.code.contains("let _initProto, _initClass;"),
-                "Should use 'let' for variable declaration"
-            );
+    #[test]
+    fn test_transform_many_emits_helpers_once() {
+        let files = vec![
+            ("src/a.js".to_string(), "export const a = 1;\n".to_string()),
+            ("src/b.js".to_string(), "class B { @dec m() {} }\n".to_string()),
+            ("src/lib/c.js".to_string(), "@dec class C { @dec #x = 1; }\n".to_string()),
+        ];
+        let results = transform_many(files.clone(), "{}".to_string()).unwrap();
+        assert_eq!(results.len(), 4);
+        assert!(results.iter().all(|result| result.errors.is_empty()));
+        let copies: Vec<usize> = results
+            .iter()
+            .map(|result| result.code.matches("function _applyDecs(").count())
+            .collect();
+        assert_eq!(copies, [0, 0, 0, 1]);
+        assert_eq!(results[0].code, "export const a = 1;\n");
+        assert!(results[1]
+            .code
+            .starts_with("import { _applyDecs } from \"../decorator-helpers.js\";\n"));
+        assert!(results[2]
+            .code
+            .starts_with("import { _applyDecs } from \"../../decorator-helpers.js\";\n"));
+        assert_eq!(results[3].code, helper_module("{}".to_string()).unwrap());
+        assert_eq!(results[3].stats.helpers_emitted, 5);
+
+        // A configured helper module is imported as named.
+        let external = r#"{"external_helpers": "./h.js"}"#;
+        let imported = transform_many(files, external.to_string()).unwrap();
+        assert!(imported[1].code.starts_with("import { _applyDecs } from \"./h.js\";\n"));
+        assert!(imported[2].code.starts_with("import { _applyDecs } from \"./h.js\";\n"));
+        assert_eq!(imported[3].code, results[3].code);
+    }
 
-            assert!(
-                res.code.contains("export default MyClass"),
-                "Should have export default identifier (not class declaration)"
-            );
+    #[test]
+    fn test_transform_many_scripts_inline_helpers() {
+        let files = vec![
+            ("a.cjs".to_string(), "@dec class A {}\n".to_string()),
+            ("b.js".to_string(), "@dec class B {}\n".to_string()),
+        ];
+        let results = transform_many(files, "{}".to_string()).unwrap();
+        assert!(results[0].code.contains("function _applyDecs("));
+        assert!(!results[0].code.contains("import"));
+        assert!(results[1]
+            .code
+            .starts_with("import { _applyDecs } from \"./decorator-helpers.js\";"));
+    }
 
-            let let_pos = res
-                .code
-                .find("let _initProto")
-                .expect("Should find let declaration");
-            let export_pos = res
-                .code
-                .find("export default")
-                .expect("Should find export default");
-            assert!(
-                let_pos < export_pos,
-                "Variable declaration should come before export statement"
-            );
+    // Runs a batch whose modules import each other with node, when it is
+    // installed: the helpers module must not add edges to the cycle.
+    #[test]
+    fn test_transform_many_cyclic_batch_runs() {
+        let files = vec![
+            (
+                "a.js".to_string(),
+                "import { b } from \"./b.js\";\n\
+                 export function dec(target) { target.tagged = true; }\n\
+                 @dec class A {}\n\
+                 console.log(A.tagged, b());\n"
+                    .to_string(),
+            ),
+            (
+                "b.js".to_string(),
+                "import { dec } from \"./a.js\";\n\
+                 @dec class B {}\n\
+                 export const b = () => B.tagged;\n"
+                    .to_string(),
+            ),
+        ];
+        for options in ["{}", r#"{"legacy_decorators": true}"#] {
+            let results = transform_many(files.clone(), options.to_string()).unwrap();
+            let dir = std::env::temp_dir().join(format!("transform-many-{}", std::process::id()));
+            std::fs::create_dir_all(&dir).unwrap();
+            std::fs::write(dir.join("package.json"), r#"{"type": "module"}"#).unwrap();
+            std::fs::write(dir.join("a.js"), &results[0].code).unwrap();
+            std::fs::write(dir.join("b.js"), &results[1].code).unwrap();
+            std::fs::write(dir.join(BATCH_HELPERS_MODULE), &results[2].code).unwrap();
+            let output = std::process::Command::new("node").arg(dir.join("a.js")).output();
+            std::fs::remove_dir_all(&dir).unwrap();
+            let Ok(output) = output else {
+                eprintln!("node is not installed, skipping");
+                return;
+            };
+            let stderr = String::from_utf8_lossy(&output.stderr);
+            assert!(output.status.success(), "{}: {}", options, stderr);
+            assert_eq!(String::from_utf8_lossy(&output.stdout), "true true\n");
         }
     }
 
     #[test]
-    fn test_export_named_class_no_invalid_syntax() {
-        let code = r#"
-@logged
-export class MyClass {
-    method() {}
-}
-"#;
+    fn test_relative_specifier() {
+        assert_eq!(relative_specifier("a.js", "b.js"), "./b.js");
+        assert_eq!(relative_specifier("src/a.js", "src/b.js"), "./b.js");
+        assert_eq!(relative_specifier("src/lib/c.js", "src/b.js"), "../b.js");
+        assert_eq!(relative_specifier("src/c.js", "src/lib/b.js"), "./lib/b.js");
+        assert_eq!(relative_specifier("./x/a.ts", "y/b.ts"), "../y/b.ts");
+        assert_eq!(relative_specifier("x\\a.js", "x\\b.js"), "./b.js");
+    }
 
-        let result = transform("test.js".to_string(), code.to_string(), "{}".to_string());
+    #[test]
+    fn test_transform_many_reports_errors_per_file() {
+        let files = vec![
+            ("ok.js".to_string(), "class A { @dec m() {} }".to_string()),
+            ("bad.js".to_string(), "class B { @dec m() {".to_string()),
+        ];
+        let results = transform_many(files.clone(), "{}".to_string()).unwrap();
+        assert_eq!(results.len(), 3);
+        assert!(results[0].errors.is_empty());
+        assert_eq!(results[1].errors.len(), 1);
+
+        let fail_fast = r#"{"error_mode": "fail-fast"}"#.to_string();
+        let err = transform_many(files, fail_fast).unwrap_err();
+        assert!(err.starts_with("bad.js: "), "{}", err);
+        assert!(transform_many(vec![], "not json".to_string()).is_err());
+    }
 
-        assert!(result.is_ok());
-        if let Ok(res) = result {
-            assert!(
-                !res.code.contains("export var"),
-                "Bug: Found 'export var' which is invalid syntax"
-            );
-            assert!(
-                !res.code.contains("export let"),
-                "Bug: Found 'export let' - variable should come before export"
-            );
+    #[test]
+    fn test_helper_hash() {
+        let hash = |options: &str| helper_hash(options.to_string()).unwrap();
+        let default = hash("{}");
+        assert_eq!(default.len(), 64);
+        assert!(default.bytes().all(|b| b.is_ascii_digit() || (b'a'..=b'f').contains(&b)));
+        // Options that leave the helper set alone leave the hash alone.
+        assert_eq!(default, hash("{}"));
+        assert_eq!(default, hash(r#"{"hoist_descriptors": true}"#));
+        assert_eq!(default, helper_module_hash(&helper_module("{}".to_string()).unwrap()));
+
+        let weakmap = hash(r#"{"private_access": "weakmap"}"#);
+        let legacy = hash(r#"{"legacy_decorators": true}"#);
+        assert_ne!(default, weakmap);
+        assert_ne!(default, legacy);
+        assert_ne!(weakmap, legacy);
+        assert_eq!(
+            helper_module_hash(""),
+            "e3b0c44298fc1c149afbf4c8996fb92427ae41e4649b934ca495991b7852b855"
+        );
+        assert!(helper_hash(r#"{"helper_version": "1999-01"}"#.to_string()).is_err());
+    }
 
-            assert!(
-                res.code.contains("let _initProto, _initClass;"),
-                "Should use 'let' for variable declaration"
-            );
+    #[test]
+    fn test_helpers_renamed_around_user_bindings() {
+        let source = "function _applyDecs() {}\nconst _toPropertyKey = 1;\nclass A { @dec m() {} @dec [k]() {} }\n";
+        let run = |options: &str| {
+            transform("a.js".to_string(), source.to_string(), options.to_string()).unwrap()
+        };
+        let inline = run("{}");
+        assert!(inline.errors.is_empty(), "{:?}", inline.errors);
+        assert!(inline.code.contains("function _applyDecs2("), "{}", inline.code);
+        assert!(inline.code.contains("function _toPropertyKey2(value) {"));
+        assert!(inline.code.contains(": _toPropertyKey2(memberName),"));
+        assert!(inline.code.contains("\nfunction _applyDecs() {}\nconst _toPropertyKey = 1;\n"));
+        assert!(inline.code.contains("= _applyDecs2(this, [["), "{}", inline.code);
+        assert!(inline.code.contains("_computedKey = _toPropertyKey2(k)"));
+        // Helpers the module does not use keep their names.
+        assert!(inline.code.contains("function _setFunctionName(fn, name, prefix) {"));
+        assert!(!inline.code.contains("_setFunctionName2"));
+
+        let imported = run(r#"{"external_helpers": "./h.js"}"#);
+        assert!(imported.code.starts_with(
+            "import { _applyDecs as _applyDecs2, _toPropertyKey as _toPropertyKey2 } from \"./h.js\";\n"
+        ));
+
+        // The host provides shared helpers under their own names.
+        let shared = run(r#"{"shared_helpers": true}"#);
+        assert!(shared.code.contains("= _applyDecs(this, [["), "{}", shared.code);
+
+        let taken = "const _applyDecs = 1, _applyDecs2 = 2;\nclass A { @dec m() {} }\n";
+        let res = transform("a.js".to_string(), taken.to_string(), "{}".to_string()).unwrap();
+        assert!(res.code.contains("= _applyDecs3(this, [["), "{}", res.code);
+    }
 
-            assert!(
-                res.code.contains("export { MyClass }"),
-                "Should have export {{ MyClass }} for named export"
-            );
+    #[test]
+    fn test_internal_and_legacy_helpers_renamed() {
+        let redeclared = |code: &str| {
+            let allocator = Allocator::default();
+            let parsed = Parser::new(&allocator, code, SourceType::mjs()).parse();
+            SemanticBuilder::new()
+                .with_check_syntax_error(true)
+                .build(&parsed.program)
+                .errors
+        };
 
-            let let_pos = res
-                .code
-                .find("let _initProto")
-                .expect("Should find let declaration");
-            let export_pos = res.code.find("export {").expect("Should find export");
-            assert!(
-                let_pos < export_pos,
-                "Variable declaration should come before export statement"
-            );
-        }
+        let source = "function _toPrimitive() {}\nlet _checkInRHS;\nclass A { @dec [k]() {} }\n";
+        let res = transform("a.js".to_string(), source.to_string(), "{}".to_string()).unwrap();
+        assert!(res.code.contains("function _toPrimitive2(value, hint) {"), "{}", res.code);
+        assert!(res.code.contains("function _checkInRHS2(value) {"));
+        assert!(res.code.contains("\nfunction _toPrimitive() {}\nlet _checkInRHS;\n"));
+        assert!(redeclared(&res.code).is_empty(), "{:?}", redeclared(&res.code));
+
+        let source = "function __decorate() {}\nconst __param = 1;\n\
+                      @dec class A { constructor(@inject a) {} @dec m() {} }\n";
+        let run = |options: &str| {
+            transform("a.ts".to_string(), source.to_string(), options.to_string()).unwrap()
+        };
+        let legacy = run(r#"{"legacy_decorators": true}"#);
+        assert!(legacy.errors.is_empty(), "{:?}", legacy.errors);
+        assert!(legacy
+            .code
+            .contains("var __decorate2 = (this && this.__decorate) || function"));
+        assert!(legacy.code.contains("var __param2 = (this && this.__param) || function"));
+        assert!(legacy.code.contains("__decorate2([dec], A.prototype, \"m\", null);"));
+        assert!(legacy.code.contains("A = __decorate2([dec, __param2(0, inject)], A);"));
+        assert!(redeclared(&legacy.code).is_empty(), "{:?}", redeclared(&legacy.code));
+
+        let imported = run(r#"{"legacy_decorators": true, "external_helpers": "./h.js"}"#);
+        assert!(imported.code.starts_with(
+            "import { __decorate as __decorate2, __param as __param2 } from \"./h.js\";\n"
+        ));
     }
 
     #[test]
-    fn test_regular_class_uses_let() {
-        let code = r#"
-@logged
-class MyClass {
-    method() {}
-}
-"#;
+    fn test_helpers_import_mode() {
+        let source = "class A { @dec m() {} }";
+        let run = |options: &str| {
+            transform("a.js".to_string(), source.to_string(), options.to_string())
+        };
+        let imported = run(r#"{"helpers":{"Import":{"module":"@myorg/decorator-runtime"}}}"#)
+            .unwrap();
+        assert!(imported.errors.is_empty(), "{:?}", imported.errors);
+        assert!(imported
+            .code
+            .starts_with("import { _applyDecs } from \"@myorg/decorator-runtime\";\n"));
+        assert!(!imported.code.contains("function _applyDecs"));
+        assert_eq!(imported.stats.helpers_emitted, 0);
+
+        let external = run(r#"{"external_helpers": "@myorg/decorator-runtime"}"#).unwrap();
+        assert_eq!(imported.code, external.code);
+        let inline = run(r#"{"helpers": "Inline"}"#).unwrap();
+        assert!(inline.code.contains("function _applyDecs"));
+
+        let same = r#"{"helpers":{"Import":{"module":"./h.js"}},"external_helpers":"./h.js"}"#;
+        assert!(run(same).is_ok());
+        let conflict = r#"{"helpers":{"Import":{"module":"./h.js"}},"external_helpers":"./g.js"}"#;
+        assert!(run(conflict).unwrap_err().contains("different modules"));
+        let shared = r#"{"helpers":{"Import":{"module":"./h.js"}},"shared_helpers":true}"#;
+        assert!(run(shared).is_err());
+    }
 
-        let result = transform("test.js".to_string(), code.to_string(), "{}".to_string());
+    #[test]
+    fn test_external_helpers_rejects_scripts() {
+        let err = transform(
+            "a.cjs".to_string(),
+            "class A { @dec m() {} }".to_string(),
+            r#"{"external_helpers": "./decorator-helpers.js"}"#.to_string(),
+        )
+        .unwrap_err();
+        assert!(err.contains("ES module"), "{err}");
+    }
+    #[test]
+    fn test_dedupe_helpers_marker() {
+        let options = r#"{"dedupe_helpers_marker": true}"#;
+        let run = |filename: &str, code: &str, options: &str| {
+            transform(filename.to_string(), code.to_string(), options.to_string()).unwrap()
+        };
+        let a = run("a.js", "class A { @dec m() {} }", options);
+        let b = run("b.js", "class B { @dec m() {} }", options);
+        assert!(a.code.starts_with("/* @decorator-helpers start "), "{}", a.code);
+        assert!(a.code.contains("\n/* @decorator-helpers end */\nlet _initProto;"));
+        assert_eq!(a.stats.helpers_emitted, 5);
+
+        let bundle = dedupe_helpers(format!("{}{}", a.code, b.code));
+        assert_eq!(bundle.matches("function _applyDecs").count(), 1);
+        assert!(bundle.contains("class A {"));
+        assert!(bundle.contains("class B {"));
+
+        let plain = run("a.js", "class A { @dec m() {} }", "{}");
+        assert!(!plain.code.contains("@decorator-helpers"));
+    }
 
-        assert!(result.is_ok());
-        if let Ok(res) = result {
-            assert!(
-                res.code.contains("let _initProto, _initClass;"),
-                "Should use 'let' for ESNext compatibility"
-            );
-            assert!(
-                !res.code.contains("var _initProto"),
-                "Should not use 'var' - use 'let' for ESNext"
-            );
-        }
+    #[test]
+    fn test_context_metadata_is_attached_to_the_class() {
+        let code = "const foo = (value, context) => { context.metadata.foo = 1; };\n@foo class A { @foo m() {} }\nclass B extends A { @foo static n = 1; }\n";
+        let res = transform("test.js".to_string(), code.to_string(), "{}".to_string()).unwrap();
+        assert!(res.errors.is_empty(), "{:?}", res.errors);
+        assert!(res.code.contains("context.metadata.foo = 1;"));
+        // The helpers give every decorator context one metadata object per
+        // class, inheriting from the parent's, and define it on the class.
+        assert!(res
+            .code
+            .contains("const metadataSymbol = Symbol.metadata || Symbol.for(\"Symbol.metadata\");"));
+        assert!(res.code.contains("metadata: metadataValue,"));
+        assert!(res.code.contains("return defineProperty(constructor, metadataSymbol, {"));
+        assert!(res.code.contains("[A, _classInit] = _applyDecs(A, [], [foo]).c;\n_classInit();"), "{}", res.code);
+        assert!(res.code.contains("[_init_n, , , _initClass] = _applyDecs(this, [["), "{}", res.code);
     }
 
     #[test]
-    fn test_helpers_use_const_let_not_var() {
-        let code = r#"
-@logged
-class MyClass {
-    method() {}
-}
+    fn test_context_metadata_at_runtime() {
+        let code = r#"const foo = (value, context) => { context.metadata[context.name] = 1; };
+@foo class A { @foo m() {} }
+class B extends A { @foo static n = 1; }
+const meta = Symbol.metadata || Symbol.for("Symbol.metadata");
+console.log(JSON.stringify([A[meta], B[meta], Object.getPrototypeOf(B[meta]) === A[meta]]));
 "#;
+        // "2023-11" shares one object between the member and class decorators
+        // of `A`, defines one on `B` with only a member decorator, and chains
+        // it to the parent's; the default "2023-05" keeps its original contract.
+        for (options, expected) in [
+            ("{}", r#"[{"A":1},{"A":1},false]"#),
+            (r#"{"helper_version": "2023-11"}"#, r#"[{"m":1,"A":1},{"n":1},true]"#),
+        ] {
+            let res = transform("test.js".to_string(), code.to_string(), options.to_string())
+                .unwrap();
+            assert!(res.errors.is_empty(), "{:?}", res.errors);
+            let Ok(output) = std::process::Command::new("node").arg("-e").arg(&res.code).output()
+            else {
+                eprintln!("node is not installed, skipping");
+                return;
+            };
+            let stderr = String::from_utf8_lossy(&output.stderr);
+            assert!(output.status.success(), "{}: {}", options, stderr);
+            assert_eq!(String::from_utf8_lossy(&output.stdout).trim_end(), expected, "{}", options);
+        }
+    }
 
-        let result = transform("test.js".to_string(), code.to_string(), "{}".to_string());
+    #[test]
+    fn test_polyfill_symbol_metadata() {
+        let polyfill = "Symbol.metadata ??= Symbol(\"Symbol.metadata\");\n";
+        let options = r#"{"polyfill_symbol_metadata": true}"#;
+        let run = |source: &str, options: &str| {
+            transform("a.js".to_string(), source.to_string(), options.to_string()).unwrap()
+        };
 
-        assert!(result.is_ok());
-        if let Ok(res) = result {
-            assert!(
-                res.code.contains("function _applyDecs"),
-                "Should have helper functions"
-            );
+        let tag = "const tag = (_, context) => { context.metadata.tagged = true; };\n";
+        let decorated = run(&format!("{tag}class A {{ @tag m() {{}} }}"), options);
+        assert!(decorated.code.starts_with(polyfill), "{}", decorated.code);
+        assert_eq!(decorated.code.matches("Symbol.metadata ??=").count(), 1);
+        let class_only = run(&format!("{tag}@tag class A {{}}"), options);
+        assert!(class_only.code.starts_with(polyfill), "{}", class_only.code);
+        let reader = run("@dec class A {}\nconsole.log(A[Symbol.metadata]);", options);
+        assert!(reader.code.starts_with(polyfill), "{}", reader.code);
+
+        // The decorators never touch metadata, so nothing reads it.
+        let untouched = run("const dec = (value) => value;\nclass A { @dec m() {} }", options);
+        assert!(!untouched.code.contains("Symbol.metadata ??="), "{}", untouched.code);
+        // Nothing is decorated, so no class gets metadata.
+        let plain = run("class A { m() { return Symbol.metadata; } }", options);
+        assert!(!plain.code.contains("Symbol.metadata ??="), "{}", plain.code);
+        let default = run(&format!("{tag}class A {{ @tag m() {{}} }}"), "{}");
+        assert!(!default.code.contains("Symbol.metadata ??="));
+
+        let edits = transform_edits(
+            "a.js".to_string(),
+            format!("{tag}class A {{ @tag m() {{}} }}"),
+            options.to_string(),
+        )
+        .unwrap();
+        assert!(edits[0].text.starts_with(polyfill), "{:?}", edits[0]);
+    }
 
-            let const_count = res.code.matches(" const ").count();
-            let let_count = res.code.matches(" let ").count();
-            let var_count = res.code.matches(" var ").count();
+    #[test]
+    fn test_spec_compliant_evaluation_order() {
+        let source = "@a class C { @b m() {} @c [k]() {} @d #p() {} }";
+        let result = transform(
+            "a.js".to_string(),
+            source.to_string(),
+            r#"{"spec_compliant": true, "decoration_site": "after-class"}"#.to_string(),
+        )
+        .unwrap();
+        let code = &result.code;
+        assert!(result.errors.is_empty(), "{:?}", result.errors);
+        // Nothing in the module touches metadata, so it is not polyfilled.
+        assert!(!code.contains("Symbol.metadata ??="), "{}", code);
+
+        // Class decorators, then member decorators and keys in source order.
+        let class_decs = code.find("_classDecs = [a];").expect(code);
+        assert!(class_decs < code.find("let C = class C").unwrap(), "{}", code);
+        assert!(
+            code.contains("[(_dec = b, _dec2 = c, _computedKey = _toPropertyKey(k))]()"),
+            "{}",
+            code
+        );
+        // A single `_applyDecs` call in the static block, despite
+        // `decoration_site`, and the decorated class rebinds `C`.
+        assert_eq!(code.matches("_applyDecs(").count(), 2, "{}", code);
+        assert!(code.contains("static {"), "{}", code);
+        assert!(code.contains("c: [_C, _classInit]} = _applyDecs(this, ["), "{}", code);
+        assert!(code.contains("], _classDecs, void 0, (o) => #p in o));"), "{}", code);
+        assert!(code.contains("C = _C;\n_classInit();"), "{}", code);
+        // `@d` follows the last computed key, so it stays in the static block.
+        assert!(code.contains("d,\n"), "{}", code);
+    }
 
-            assert!(
-                const_count + let_count > 0,
-                "Should use const/let in helpers"
-            );
-            assert_eq!(
-                var_count, 0,
-                "Should not use 'var' - all should be converted to const/let for ESNext"
-            );
-        }
+    #[test]
+    fn test_include_classes() {
+        let source = "@dec class A { @m x() {} }\n@dec class B { @m x() {} }\nconst C = @dec class {};\n";
+        let result = transform(
+            "a.js".to_string(),
+            source.to_string(),
+            r#"{"include_classes": ["B", "C"]}"#.to_string(),
+        )
+        .unwrap();
+        assert!(result.errors.is_empty(), "{:?}", result.errors);
+        let code = &result.code;
+        // `A` is left as written, decorators included.
+        assert!(code.contains("@dec class A {\n\t@m x() {}\n}\n"), "{}", code);
+        assert!(code.contains("let B = class B {"), "{}", code);
+        assert!(code.contains("[B, _classInit] = _applyDecs(B, [], [dec]).c;\n_classInit();"));
+        // An anonymous class expression goes by its binding.
+        assert!(code.contains(
+            "const C = ([_C, _classInit2] = _applyDecs(_setFunctionName(class {}, \"C\"), [], [dec]).c, _classInit2(), _C);"
+        ));
+        assert_eq!(result.stats.classes_transformed, 2);
+
+        let none = transform(
+            "a.js".to_string(),
+            source.to_string(),
+            r#"{"include_classes": []}"#.to_string(),
+        )
+        .unwrap();
+        assert!(!none.code.contains("_applyDecs"), "{}", none.code);
+    }
+
+    #[test]
+    fn test_verbose_output() {
+        let source = "class A { @dec m() {} @dec static s() {} }\n@dec class B { @dec m() {} }\n";
+        let run = |options: &str| {
+            transform("a.js".to_string(), source.to_string(), options.to_string()).unwrap()
+        };
+
+        let compact = run("{}");
+        assert!(!compact.code.contains("_descriptors"));
+        assert!(compact.code.contains("[_initProto, _initClass] = _applyDecs(this, [["));
+
+        let verbose = run(r#"{"verbose_output": true}"#);
+        assert!(verbose.errors.is_empty(), "{:?}", verbose.errors);
+        let code = &verbose.code;
+        let class_a = &code[code.find("class A {").unwrap()..code.find("let B").unwrap()];
+        assert!(class_a.contains("static {\n\t\tconst _descriptors = [["), "{}", class_a);
+        assert!(class_a.contains(
+            "\"s\",\n\t\t\tfalse\n\t\t]];\n\t\tconst _result = _applyDecs(this, _descriptors, []);\n\t\t[_initProto, _initClass] = _result.e;\n\t\tif (_initClass) _initClass();"
+        ));
+        // Each class gets its own names.
+        assert!(code.contains(
+            "const _result2 = _applyDecs(this, _descriptors2, []);\n\t\t[_initProto2] = _result2.e;"
+        ));
+
+        // Hoisted descriptors are already named.
+        let hoisted = run(r#"{"verbose_output": true, "hoist_descriptors": true}"#);
+        assert!(hoisted.code.contains("const _result = _applyDecs(this, _descriptors, []);"));
+        assert_eq!(hoisted.code.matches("const _descriptors =").count(), 1);
+
+        let spec = run(r#"{"verbose_output": true, "spec_compliant": true}"#);
+        assert!(
+            spec.code.contains(
+                "const _result2 = _applyDecs(this, _descriptors2, _classDecs);\n\t\t({e: [_initProto2], c: [_B, _classInit]} = _result2);"
+            ),
+            "{}",
+            spec.code
+        );
+    }
+
+    #[test]
+    fn test_helper_strategies() {
+        let source = "class A { @dec m() {} }";
+        let run = |options: &str| {
+            transform("a.js".to_string(), source.to_string(), options.to_string()).unwrap()
+        };
+
+        let inline = run("{}");
+        assert!(inline.code.starts_with("/**"), "{}", inline.code);
+        assert!(inline.code.find("function _applyDecs") < inline.code.find("class A"));
+        assert_eq!(inline.stats.helpers_emitted, 5);
+
+        let runtime = run(r#"{"external_helpers": "./decorator-helpers.js"}"#);
+        assert!(runtime
+            .code
+            .starts_with("import { _applyDecs } from \"./decorator-helpers.js\";"));
+        assert!(!runtime.code.contains("function _applyDecs"));
+        assert_eq!(runtime.stats.helpers_emitted, 0);
+
+        let shared = run(r#"{"shared_helpers": true}"#);
+        assert!(shared.code.starts_with("let _initProto;"), "{}", shared.code);
+        assert!(shared.code.contains("_applyDecs(this"));
+        assert!(!shared.code.contains("function _applyDecs"));
+        assert!(!shared.code.contains("import"));
+        assert_eq!(shared.stats.helpers_emitted, 0);
+
+        let err = transform(
+            "a.js".to_string(),
+            source.to_string(),
+            r#"{"external_helpers": "./h.js", "shared_helpers": true}"#.to_string(),
+        )
+        .unwrap_err();
+        assert!(err.contains("cannot be combined"), "{err}");
     }
+
 }
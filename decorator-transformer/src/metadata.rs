@@ -0,0 +1,144 @@
+use oxc_ast::ast::*;
+use oxc_ast_visit::{walk, Visit};
+use oxc_span::{GetSpan, Span};
+use serde::Serialize;
+
+use crate::DecoratorKind;
+
+/// The `metadata` sidecar: every decorated class in source order, nested and
+/// expression classes included.
+#[derive(Serialize, Debug)]
+pub struct ModuleMetadata {
+    pub classes: Vec<ClassMetadata>,
+}
+
+#[derive(Serialize, Debug)]
+pub struct ClassMetadata {
+    /// `None` for anonymous class expressions.
+    pub name: Option<String>,
+    pub span: SpanMetadata,
+    pub decorators: Vec<String>,
+    pub decorator_sources: Vec<String>,
+    pub members: Vec<MemberMetadata>,
+}
+
+#[derive(Serialize, Debug)]
+pub struct MemberMetadata {
+    /// The member name, without `#` for private members; `None` for
+    /// computed keys that are not literals.
+    pub name: Option<String>,
+    pub kind: DecoratorKind,
+    #[serde(rename = "static")]
+    pub is_static: bool,
+    #[serde(rename = "private")]
+    pub is_private: bool,
+    pub span: SpanMetadata,
+    /// Source text of each decorator expression.
+    pub decorators: Vec<String>,
+    /// Each decorator exactly as written, `@` and formatting included.
+    pub decorator_sources: Vec<String>,
+}
+
+/// UTF-8 byte offsets into the original source.
+#[derive(Serialize, Debug)]
+pub struct SpanMetadata {
+    pub start: u32,
+    pub end: u32,
+}
+
+impl From<Span> for SpanMetadata {
+    fn from(span: Span) -> Self {
+        Self {
+            start: span.start,
+            end: span.end,
+        }
+    }
+}
+
+struct MetadataCollector<'s> {
+    source_text: &'s str,
+    classes: Vec<ClassMetadata>,
+}
+
+impl MetadataCollector<'_> {
+    fn decorator_sources(&self, decorators: &[Decorator<'_>]) -> Vec<String> {
+        decorators
+            .iter()
+            .map(|dec| dec.expression.span().source_text(self.source_text).to_string())
+            .collect()
+    }
+
+    fn verbatim_decorators(&self, decorators: &[Decorator<'_>]) -> Vec<String> {
+        decorators
+            .iter()
+            .map(|dec| dec.span.source_text(self.source_text).to_string())
+            .collect()
+    }
+
+    fn member(&self, element: &ClassElement<'_>) -> Option<MemberMetadata> {
+        let (decorators, kind, is_static, key, span) = match element {
+            ClassElement::MethodDefinition(m) if !m.decorators.is_empty() => {
+                let kind = match m.kind {
+                    MethodDefinitionKind::Get => DecoratorKind::Getter,
+                    MethodDefinitionKind::Set => DecoratorKind::Setter,
+                    _ => DecoratorKind::Method,
+                };
+                (&m.decorators, kind, m.r#static, &m.key, m.span)
+            }
+            ClassElement::PropertyDefinition(p) if !p.decorators.is_empty() => {
+                (&p.decorators, DecoratorKind::Field, p.r#static, &p.key, p.span)
+            }
+            ClassElement::AccessorProperty(a) if !a.decorators.is_empty() => {
+                (&a.decorators, DecoratorKind::Accessor, a.r#static, &a.key, a.span)
+            }
+            _ => return None,
+        };
+        let name = match key {
+            PropertyKey::PrivateIdentifier(id) => Some(id.name.to_string()),
+            key => key.static_name().map(|name| name.to_string()),
+        };
+        Some(MemberMetadata {
+            name,
+            kind,
+            is_static,
+            is_private: matches!(key, PropertyKey::PrivateIdentifier(_)),
+            span: span.into(),
+            decorators: self.decorator_sources(decorators),
+            decorator_sources: self.verbatim_decorators(decorators),
+        })
+    }
+}
+
+impl<'a> Visit<'a> for MetadataCollector<'_> {
+    fn visit_class(&mut self, class: &Class<'a>) {
+        let members: Vec<_> = class
+            .body
+            .body
+            .iter()
+            .filter_map(|element| self.member(element))
+            .collect();
+        if !class.decorators.is_empty() || !members.is_empty() {
+            self.classes.push(ClassMetadata {
+                name: class.id.as_ref().map(|id| id.name.to_string()),
+                span: class.span.into(),
+                decorators: self.decorator_sources(&class.decorators),
+                decorator_sources: self.verbatim_decorators(&class.decorators),
+                members,
+            });
+        }
+        walk::walk_class(self, class);
+    }
+}
+
+/// Collects the decorated classes and members of `program`, for the
+/// `emit_metadata` option. Must run before lowering removes the decorators.
+pub fn collect_metadata(program: &Program<'_>) -> ModuleMetadata {
+    let mut collector = MetadataCollector {
+        source_text: program.source_text,
+        classes: Vec::new(),
+    };
+    collector.visit_program(program);
+    ModuleMetadata {
+        classes: collector.classes,
+    }
+}
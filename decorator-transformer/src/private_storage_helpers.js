@@ -0,0 +1,30 @@
+/**
+ * Read a decorated private accessor's value from its WeakMap storage,
+ * rejecting objects that do not have the member like `#x` access does.
+ *
+ * @param {WeakMap} storage - Values keyed by instance (or class)
+ * @param {Object} obj - Object to read from
+ * @returns {*} The stored value
+ */
+function _privateStorageGet(storage, obj) {
+  if (!storage.has(obj)) {
+    throw new TypeError("Cannot read private member from an object whose class did not declare it");
+  }
+  return storage.get(obj);
+}
+
+/**
+ * Write a decorated private accessor's value to its WeakMap storage.
+ *
+ * @param {WeakMap} storage - Values keyed by instance (or class)
+ * @param {Object} obj - Object to write to
+ * @param {*} value - Value to store
+ * @returns {*} The value
+ */
+function _privateStorageSet(storage, obj, value) {
+  if (!storage.has(obj)) {
+    throw new TypeError("Cannot write private member to an object whose class did not declare it");
+  }
+  storage.set(obj, value);
+  return value;
+}
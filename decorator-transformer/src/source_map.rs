@@ -0,0 +1,101 @@
+use std::sync::Arc;
+
+use oxc_sourcemap::{SourceMap, Token};
+use oxc_span::Span;
+
+/// Moves every mapping on or after `from_line` down by `lines`, for whole
+/// lines inserted ahead of the generated code (helpers, banner, strict
+/// directive).
+pub fn shift_lines(map: &SourceMap, from_line: u32, lines: u32) -> SourceMap {
+    if lines == 0 {
+        return map.clone();
+    }
+    rebuild(map, map.get_names().cloned().collect(), |token| {
+        let dst_line = match token.get_dst_line() {
+            line if line >= from_line => line + lines,
+            line => line,
+        };
+        Token::new(
+            dst_line,
+            token.get_dst_col(),
+            token.get_src_line(),
+            token.get_src_col(),
+            token.get_source_id(),
+            token.get_name_id(),
+        )
+    })
+}
+
+/// Attaches `name` to the mappings that start at each span in
+/// `source_text`, so debuggers show the original identifier for bindings
+/// the transform re-emits (such as `C` in `let C = class C {}`).
+pub fn add_names(map: &SourceMap, source_text: &str, names: &[(Span, String)]) -> SourceMap {
+    let mut all_names: Vec<Arc<str>> = map.get_names().cloned().collect();
+    let positions: Vec<((u32, u32), u32)> = names
+        .iter()
+        .map(|(span, name)| {
+            let id = match all_names.iter().position(|n| **n == **name) {
+                Some(id) => id,
+                None => {
+                    all_names.push(Arc::from(name.as_str()));
+                    all_names.len() - 1
+                }
+            };
+            (line_col(source_text, span.start), id as u32)
+        })
+        .collect();
+    rebuild(map, all_names, |token| {
+        let src = (token.get_src_line(), token.get_src_col());
+        let name_id = positions
+            .iter()
+            .find(|(position, _)| *position == src)
+            .map(|(_, id)| *id)
+            .or(token.get_name_id());
+        Token::new(
+            token.get_dst_line(),
+            token.get_dst_col(),
+            src.0,
+            src.1,
+            token.get_source_id(),
+            name_id,
+        )
+    })
+}
+
+fn rebuild(
+    map: &SourceMap,
+    names: Vec<Arc<str>>,
+    mut adjust: impl FnMut(Token) -> Token,
+) -> SourceMap {
+    let tokens: Vec<Token> = map.get_tokens().map(&mut adjust).collect();
+    SourceMap::new(
+        map.get_file().cloned(),
+        names,
+        map.get_source_root().map(str::to_string),
+        map.get_sources().cloned().collect(),
+        map.get_source_contents().map(|c| c.cloned()).collect(),
+        tokens.into_boxed_slice(),
+        None,
+    )
+}
+
+// Line and UTF-16 column of a byte offset, counting line terminators the way
+// the codegen's source map builder does.
+fn line_col(source_text: &str, offset: u32) -> (u32, u32) {
+    let before = &source_text[..offset as usize];
+    let mut line = 0;
+    let mut line_start = 0;
+    let mut chars = before.char_indices().peekable();
+    while let Some((i, c)) = chars.next() {
+        match c {
+            '\r' if chars.peek().is_some_and(|&(_, next)| next == '\n') => {}
+            '\n' | '\r' | '\u{2028}' | '\u{2029}' => {
+                line += 1;
+                line_start = i + c.len_utf8();
+            }
+            _ => {}
+        }
+    }
+    let column = before[line_start..].encode_utf16().count();
+    (line, column as u32)
+}
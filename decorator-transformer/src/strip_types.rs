@@ -0,0 +1,395 @@
+use oxc_allocator::{Allocator, Box as ArenaBox, TakeIn, Vec as ArenaVec};
+use oxc_ast::{ast::*, AstBuilder};
+use oxc_ast_visit::{walk_mut, VisitMut};
+use oxc_semantic::ScopeFlags;
+use oxc_span::{GetSpan, Span, SPAN};
+
+use crate::Located;
+
+/// Removes TypeScript-only syntax from `program`, returning the original
+/// span of everything removed in source order.
+///
+/// Only syntax with no runtime meaning is stripped: annotations, type
+/// parameters and arguments, `as`/`satisfies`/`!`/`<T>` wrappers, TS class
+/// modifiers and members, and type-only declarations, imports and exports.
+/// Parameter properties are lowered to `this.a = a;` assignments at the
+/// start of the constructor (after `super()`), each spanning its parameter.
+/// Imports whose specifiers are all type-only are removed. Enums, namespaces
+/// with values, `import =` and `export =` have a runtime meaning this cannot
+/// produce, and are reported as errors instead.
+pub fn strip_types<'a>(
+    program: &mut Program<'a>,
+    allocator: &'a Allocator,
+) -> Result<Vec<Span>, Vec<Located>> {
+    let mut stripper = TypeStripper {
+        allocator,
+        stripped: Vec::new(),
+        errors: Vec::new(),
+    };
+    stripper.visit_program(program);
+    if !stripper.errors.is_empty() {
+        stripper.errors.sort_by_key(|(_, offset)| *offset);
+        return Err(stripper.errors);
+    }
+    stripper.stripped.sort_by_key(|span| (span.start, span.end));
+    Ok(stripper.stripped)
+}
+
+struct TypeStripper<'a> {
+    allocator: &'a Allocator,
+    stripped: Vec<Span>,
+    errors: Vec<Located>,
+}
+
+impl<'a> TypeStripper<'a> {
+    fn strip<T: GetSpan>(&mut self, node: Option<ArenaBox<'a, T>>) {
+        if let Some(node) = node {
+            self.stripped.push(node.span());
+        }
+    }
+
+    fn is_type_only_statement(statement: &Statement<'a>) -> bool {
+        match statement {
+            // As TypeScript does, an import is elided when every specifier
+            // it had is type-only, not kept as a side-effect import.
+            Statement::ImportDeclaration(import) => {
+                import.import_kind.is_type()
+                    || import.specifiers.as_ref().is_some_and(|specifiers| {
+                        !specifiers.is_empty()
+                            && specifiers.iter().all(|specifier| {
+                                matches!(specifier, ImportDeclarationSpecifier::ImportSpecifier(s)
+                                    if s.import_kind.is_type())
+                            })
+                    })
+            }
+            Statement::ExportNamedDeclaration(export) => {
+                export.export_kind.is_type()
+                    || export
+                        .declaration
+                        .as_ref()
+                        .is_some_and(Self::is_type_only_declaration)
+            }
+            Statement::ExportDefaultDeclaration(export) => matches!(
+                export.declaration,
+                ExportDefaultDeclarationKind::TSInterfaceDeclaration(_)
+            ),
+            Statement::TSNamespaceExportDeclaration(_) => true,
+            _ => statement
+                .as_declaration()
+                .is_some_and(Self::is_type_only_declaration),
+        }
+    }
+
+    fn is_type_only_declaration(declaration: &Declaration<'a>) -> bool {
+        match declaration {
+            Declaration::TSTypeAliasDeclaration(_) | Declaration::TSInterfaceDeclaration(_) => true,
+            Declaration::VariableDeclaration(decl) => decl.declare,
+            Declaration::FunctionDeclaration(func) => func.declare || func.body.is_none(),
+            Declaration::ClassDeclaration(class) => class.declare,
+            Declaration::TSEnumDeclaration(decl) => decl.declare,
+            Declaration::TSModuleDeclaration(decl) => {
+                decl.declare || !Self::is_instantiated_namespace(decl)
+            }
+            Declaration::TSImportEqualsDeclaration(decl) => decl.import_kind.is_type(),
+        }
+    }
+
+    // Whether the namespace declares any value, as TypeScript decides whether
+    // to emit it.
+    fn is_instantiated_namespace(decl: &TSModuleDeclaration<'a>) -> bool {
+        match &decl.body {
+            Some(TSModuleDeclarationBody::TSModuleBlock(block)) => block
+                .body
+                .iter()
+                .any(|statement| !Self::is_type_only_statement(statement)),
+            Some(TSModuleDeclarationBody::TSModuleDeclaration(inner)) => {
+                Self::is_instantiated_namespace(inner)
+            }
+            None => false,
+        }
+    }
+
+    // The construct with a runtime meaning in a statement that is not
+    // type-only, which stripping cannot lower.
+    fn unsupported_statement(statement: &Statement<'a>) -> Option<&'static str> {
+        let declaration = match statement {
+            Statement::TSExportAssignment(_) => return Some("`export =` assignments"),
+            Statement::ExportNamedDeclaration(export) => export.declaration.as_ref()?,
+            _ => statement.as_declaration()?,
+        };
+        match declaration {
+            Declaration::TSEnumDeclaration(_) => Some("enums"),
+            Declaration::TSModuleDeclaration(_) => Some("namespaces with values"),
+            Declaration::TSImportEqualsDeclaration(_) => Some("`import =` declarations"),
+            _ => None,
+        }
+    }
+
+    // Lowers the parameter properties of `constructor` to assignments after
+    // its `super()` call, or at its start in a base class. Each assignment
+    // keeps the span of its parameter, which the decorator transform uses to
+    // run its initializers after them.
+    fn lower_parameter_properties(&mut self, constructor: &mut Function<'a>, derived: bool) {
+        let mut properties = Vec::new();
+        for param in constructor.params.items.iter_mut() {
+            if param.accessibility.is_none() && !param.readonly && !param.r#override {
+                continue;
+            }
+            param.accessibility = None;
+            param.readonly = false;
+            param.r#override = false;
+            if let Some(name) = param.pattern.get_identifier_name() {
+                properties.push((name, param.span));
+            }
+        }
+        let Some(body) = &mut constructor.body else {
+            return;
+        };
+        if properties.is_empty() {
+            return;
+        }
+        let index = if derived {
+            let super_call = body.statements.iter().position(|statement| {
+                matches!(statement, Statement::ExpressionStatement(s)
+                    if s.expression.is_super_call_expression())
+            });
+            let Some(super_call) = super_call else {
+                self.errors.push((
+                    "Parameter properties of a derived class need a `super()` call at the top \
+                     level of its constructor"
+                        .to_string(),
+                    constructor.span.start,
+                ));
+                return;
+            };
+            super_call + 1
+        } else {
+            0
+        };
+        let ast = AstBuilder::new(self.allocator);
+        let assignments = properties.into_iter().map(|(name, span)| {
+            let target = ast.member_expression_static(
+                SPAN,
+                ast.expression_this(SPAN),
+                ast.identifier_name(SPAN, name),
+                false,
+            );
+            ast.statement_expression(
+                span,
+                ast.expression_assignment(
+                    SPAN,
+                    AssignmentOperator::Assign,
+                    AssignmentTarget::from(SimpleAssignmentTarget::from(target)),
+                    ast.expression_identifier(SPAN, name),
+                ),
+            )
+        });
+        body.statements.splice(index..index, assignments);
+    }
+
+    fn is_type_only_class_element(element: &ClassElement<'a>) -> bool {
+        match element {
+            ClassElement::TSIndexSignature(_) => true,
+            ClassElement::MethodDefinition(method) => {
+                method.r#type.is_abstract() || method.value.body.is_none()
+            }
+            ClassElement::PropertyDefinition(prop) => prop.r#type.is_abstract() || prop.declare,
+            ClassElement::AccessorProperty(accessor) => accessor.r#type.is_abstract(),
+            ClassElement::StaticBlock(_) => false,
+        }
+    }
+
+    fn unwrap_type_expression(&mut self, expr: &mut Expression<'a>) {
+        loop {
+            let inner = match expr {
+                Expression::TSAsExpression(e) => {
+                    self.stripped
+                        .push(Span::new(e.expression.span().end, e.span.end));
+                    e.expression.take_in(self.allocator)
+                }
+                Expression::TSSatisfiesExpression(e) => {
+                    self.stripped
+                        .push(Span::new(e.expression.span().end, e.span.end));
+                    e.expression.take_in(self.allocator)
+                }
+                Expression::TSNonNullExpression(e) => {
+                    self.stripped
+                        .push(Span::new(e.expression.span().end, e.span.end));
+                    e.expression.take_in(self.allocator)
+                }
+                Expression::TSTypeAssertion(e) => {
+                    self.stripped
+                        .push(Span::new(e.span.start, e.expression.span().start));
+                    e.expression.take_in(self.allocator)
+                }
+                Expression::TSInstantiationExpression(e) => {
+                    self.stripped.push(e.type_arguments.span);
+                    e.expression.take_in(self.allocator)
+                }
+                _ => return,
+            };
+            *expr = inner;
+        }
+    }
+}
+
+impl<'a> VisitMut<'a> for TypeStripper<'a> {
+    fn visit_statements(&mut self, it: &mut ArenaVec<'a, Statement<'a>>) {
+        let mut stripped = Vec::new();
+        it.retain(|statement| {
+            let type_only = Self::is_type_only_statement(statement);
+            if type_only {
+                stripped.push(statement.span());
+            }
+            !type_only
+        });
+        self.stripped.extend(stripped);
+        for statement in it.iter() {
+            if let Some(construct) = Self::unsupported_statement(statement) {
+                self.errors.push((
+                    format!("strip_types cannot remove {construct}, which have a runtime meaning"),
+                    statement.span().start,
+                ));
+            }
+        }
+        walk_mut::walk_statements(self, it);
+    }
+
+    fn visit_expression(&mut self, it: &mut Expression<'a>) {
+        self.unwrap_type_expression(it);
+        walk_mut::walk_expression(self, it);
+    }
+
+    fn visit_import_declaration(&mut self, it: &mut ImportDeclaration<'a>) {
+        if let Some(specifiers) = &mut it.specifiers {
+            specifiers.retain(|specifier| match specifier {
+                ImportDeclarationSpecifier::ImportSpecifier(s) if s.import_kind.is_type() => {
+                    self.stripped.push(s.span);
+                    false
+                }
+                _ => true,
+            });
+        }
+        walk_mut::walk_import_declaration(self, it);
+    }
+
+    fn visit_export_named_declaration(&mut self, it: &mut ExportNamedDeclaration<'a>) {
+        it.specifiers.retain(|specifier| {
+            if specifier.export_kind.is_type() {
+                self.stripped.push(specifier.span);
+            }
+            !specifier.export_kind.is_type()
+        });
+        walk_mut::walk_export_named_declaration(self, it);
+    }
+
+    fn visit_variable_declarator(&mut self, it: &mut VariableDeclarator<'a>) {
+        it.definite = false;
+        walk_mut::walk_variable_declarator(self, it);
+    }
+
+    fn visit_binding_pattern(&mut self, it: &mut BindingPattern<'a>) {
+        let annotation = it.type_annotation.take();
+        self.strip(annotation);
+        it.optional = false;
+        walk_mut::walk_binding_pattern(self, it);
+    }
+
+    fn visit_function(&mut self, it: &mut Function<'a>, flags: ScopeFlags) {
+        let type_parameters = it.type_parameters.take();
+        self.strip(type_parameters);
+        let this_param = it.this_param.take();
+        self.strip(this_param);
+        let return_type = it.return_type.take();
+        self.strip(return_type);
+        walk_mut::walk_function(self, it, flags);
+    }
+
+    fn visit_arrow_function_expression(&mut self, it: &mut ArrowFunctionExpression<'a>) {
+        let type_parameters = it.type_parameters.take();
+        self.strip(type_parameters);
+        let return_type = it.return_type.take();
+        self.strip(return_type);
+        walk_mut::walk_arrow_function_expression(self, it);
+    }
+
+    fn visit_class(&mut self, it: &mut Class<'a>) {
+        let type_parameters = it.type_parameters.take();
+        self.strip(type_parameters);
+        let super_type_arguments = it.super_type_arguments.take();
+        self.strip(super_type_arguments);
+        for implements in it.implements.drain(..) {
+            self.stripped.push(implements.span);
+        }
+        it.r#abstract = false;
+        let mut stripped = Vec::new();
+        it.body.body.retain(|element| {
+            let type_only = Self::is_type_only_class_element(element);
+            if type_only {
+                stripped.push(element.span());
+            }
+            !type_only
+        });
+        self.stripped.extend(stripped);
+        let derived = it.super_class.is_some();
+        let constructor = it.body.body.iter_mut().find_map(|element| match element {
+            ClassElement::MethodDefinition(m) if m.kind.is_constructor() => Some(m),
+            _ => None,
+        });
+        if let Some(constructor) = constructor {
+            self.lower_parameter_properties(&mut constructor.value, derived);
+        }
+        walk_mut::walk_class(self, it);
+    }
+
+    fn visit_method_definition(&mut self, it: &mut MethodDefinition<'a>) {
+        it.accessibility = None;
+        it.optional = false;
+        it.r#override = false;
+        walk_mut::walk_method_definition(self, it);
+    }
+
+    fn visit_property_definition(&mut self, it: &mut PropertyDefinition<'a>) {
+        let annotation = it.type_annotation.take();
+        self.strip(annotation);
+        it.accessibility = None;
+        it.optional = false;
+        it.definite = false;
+        it.readonly = false;
+        it.r#override = false;
+        walk_mut::walk_property_definition(self, it);
+    }
+
+    fn visit_accessor_property(&mut self, it: &mut AccessorProperty<'a>) {
+        let annotation = it.type_annotation.take();
+        self.strip(annotation);
+        it.accessibility = None;
+        it.definite = false;
+        it.r#override = false;
+        walk_mut::walk_accessor_property(self, it);
+    }
+
+    fn visit_call_expression(&mut self, it: &mut CallExpression<'a>) {
+        let type_arguments = it.type_arguments.take();
+        self.strip(type_arguments);
+        walk_mut::walk_call_expression(self, it);
+    }
+
+    fn visit_new_expression(&mut self, it: &mut NewExpression<'a>) {
+        let type_arguments = it.type_arguments.take();
+        self.strip(type_arguments);
+        walk_mut::walk_new_expression(self, it);
+    }
+
+    fn visit_tagged_template_expression(&mut self, it: &mut TaggedTemplateExpression<'a>) {
+        let type_arguments = it.type_arguments.take();
+        self.strip(type_arguments);
+        walk_mut::walk_tagged_template_expression(self, it);
+    }
+
+    fn visit_jsx_opening_element(&mut self, it: &mut JSXOpeningElement<'a>) {
+        let type_arguments = it.type_arguments.take();
+        self.strip(type_arguments);
+        walk_mut::walk_jsx_opening_element(self, it);
+    }
+}
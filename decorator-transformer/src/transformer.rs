@@ -1,12 +1,18 @@
-use oxc_allocator::Allocator;
-use oxc_ast::{ast::*, NONE};
+use oxc_allocator::{Allocator, CloneIn, TakeIn, Vec as ArenaVec};
+use oxc_ast::{ast::*, AstBuilder, NONE};
+use oxc_ast_visit::{walk, walk_mut, Visit, VisitMut};
 use oxc_codegen::Codegen;
-use oxc_semantic::ScopeFlags;
-use oxc_span::SPAN;
-use oxc_traverse::{Traverse, TraverseCtx};
+use oxc_semantic::{ScopeFlags, Scoping, SemanticBuilder, SymbolId};
+use oxc_span::{Atom, GetSpan, Span, SPAN};
+use oxc_traverse::{Ancestor, Traverse, TraverseCtx};
+use serde::Serialize;
 use std::cell::RefCell;
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+use crate::codegen::{EXPORTED_HELPERS, INTERNAL_HELPERS, PRIVATE_STORAGE_HELPERS};
+use crate::{DecorationSite, Located, MemberInstallation, PrivateAccess, TransformOptions, TransformStats};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
 #[repr(u8)]
 pub enum DecoratorKind {
     Field = 0,
@@ -16,75 +22,484 @@ pub enum DecoratorKind {
     Setter = 4,
 }
 
+/// A decorated class member, as seen by a [`DescriptorHook`].
+#[derive(Debug, Clone)]
+pub struct DecoratorMetadata {
+    /// The member name, without `#` for private members; `None` for
+    /// computed keys.
+    pub name: Option<String>,
+    pub kind: DecoratorKind,
+    pub is_static: bool,
+    pub is_private: bool,
+    /// Source text of each decorator expression, in source order (the
+    /// temporaries holding them for computed keys).
+    pub decorators: Vec<String>,
+}
+
+/// Values appended, in order, to a member's descriptor array.
+#[derive(Debug, Clone, Default)]
+pub struct ExtraDescriptorFields {
+    pub fields: Vec<serde_json::Value>,
+}
+
+/// Called for every decorated member; returning fields appends them to the
+/// member's descriptor after the ones `_applyDecs` reads.
+pub type DescriptorHook<'h> = dyn Fn(&DecoratorMetadata) -> Option<ExtraDescriptorFields> + 'h;
+
 pub struct DecoratorTransformer<'a> {
-    pub errors: Vec<String>,
+    pub errors: Vec<Located>,
+    pub warnings: Vec<Located>,
+    pub stats: TransformStats,
     in_decorated_class: RefCell<bool>,
     helpers_injected: RefCell<bool>,
+    uses_to_property_key: RefCell<bool>,
+    uses_set_function_name: RefCell<bool>,
+    uses_private_storage: RefCell<bool>,
     classes_with_class_decorators: RefCell<Vec<ClassDecoratorInfo<'a>>>,
-    _allocator: &'a Allocator,
+    hoisted_descriptors: RefCell<Vec<HoistedDescriptors<'a>>>,
+    class_temps: RefCell<Vec<ClassTemps<'a>>>,
+    after_class_statements: RefCell<Vec<AfterClassStatements<'a>>>,
+    // `_initProto`/`_initClass` of the class being transformed, unique per
+    // class so several classes can share a scope.
+    init_names: RefCell<(Atom<'a>, Atom<'a>)>,
+    // With `spec_compliant`, the temporaries holding the class decorators
+    // and receiving the decorated class and its initializer when the static
+    // block applies them.
+    class_decorator_temps: RefCell<Option<(Atom<'a>, Atom<'a>, Atom<'a>)>>,
+    // Helpers renamed because the module already uses their name.
+    helper_renames: Vec<(&'static str, Atom<'a>)>,
+    options: TransformOptions,
+    descriptor_hook: Option<&'a DescriptorHook<'a>>,
+    allocator: &'a Allocator,
 }
 
 pub struct ClassDecoratorInfo<'a> {
+    /// The binding the decorated class is assigned to.
     pub class_name: String,
+    /// `None` for `export default class {}`, which has no name of its own.
+    pub name_span: Option<Span>,
+    pub class_span: Span,
     pub decorators: Vec<Expression<'a>>,
+    /// The temporary receiving the class initializer, which runs the
+    /// `addInitializer` callbacks of the class decorators. `None` for a
+    /// named class with only marker decorators, which is not rebound.
+    pub class_init: Option<Atom<'a>>,
+    /// With `spec_compliant`, the temporaries of `class_decorator_temps`:
+    /// the class is rebound to the decorated class its static block stored.
+    pub applied_in_class: Option<(Atom<'a>, Atom<'a>)>,
+}
+
+// How a class declaration with class decorators was exported, which its
+// rebinding has to preserve.
+enum ClassExport {
+    None,
+    Named,
+    Default,
+}
+
+pub struct HoistedDescriptors<'a> {
+    pub name: String,
+    pub descriptors: Expression<'a>,
+    pub class_span: Span,
+    source: String,
+}
+
+pub struct ClassTemps<'a> {
+    pub class_span: Span,
+    pub names: Vec<Atom<'a>>,
+}
+
+// Decoration statements placed after the class statement for
+// `decoration_site: "after-class"`.
+pub struct AfterClassStatements<'a> {
+    pub class_span: Span,
+    pub statements: ArenaVec<'a, Statement<'a>>,
+}
+
+pub struct ComputedMember<'a> {
+    decorators: Vec<Atom<'a>>,
+    key: Atom<'a>,
+}
+
+// A decorated member and the temporaries that receive its entries from
+// `_applyDecs(...).e`.
+pub struct MemberInit<'a> {
+    kind: DecoratorKind,
+    is_static: bool,
+    private_name: Option<Atom<'a>>,
+    // Private field holding the value, or for private accessors with
+    // `private_access: "weakmap"` the WeakMap temporary. For private members
+    // it is read and written by the access closures in the descriptor;
+    // public accessors reach it through their desugared getter and setter.
+    storage: Option<Atom<'a>>,
+    init: Option<Atom<'a>>,
+    get: Option<Atom<'a>>,
+    set: Option<Atom<'a>>,
+    // The decorated function of a private method, getter or setter, which
+    // the member calls in place of its original body.
+    call: Option<Atom<'a>>,
+}
+
+impl<'a> MemberInit<'a> {
+    fn is_private(&self) -> bool {
+        self.private_name.is_some()
+    }
+
+    fn private_storage(&self) -> Option<Atom<'a>> {
+        self.storage.filter(|_| self.is_private())
+    }
 }
 
 pub struct TransformerState;
 
+// Whether `stmt` is a `this.a = a;` assignment `strip_types` lowered from a
+// parameter property of `params`, which keeps the span of its parameter.
+fn is_parameter_property(stmt: &Statement, params: &FormalParameters) -> bool {
+    matches!(stmt, Statement::ExpressionStatement(expr_stmt)
+        if expr_stmt.expression.is_assignment()
+            && params.items.iter().any(|param| param.span == expr_stmt.span))
+}
+
+struct DecoratorFinder {
+    found: bool,
+}
+
+impl<'a> Visit<'a> for DecoratorFinder {
+    fn visit_decorator(&mut self, _decorator: &Decorator<'a>) {
+        self.found = true;
+    }
+}
+
+struct SuperFinder {
+    found: bool,
+}
+
+impl<'a> Visit<'a> for SuperFinder {
+    fn visit_super(&mut self, _it: &Super) {
+        self.found = true;
+    }
+}
+
+// Follows each `super(...)` call of a derived constructor, outside nested
+// functions and classes, with `inits` and `this`: `(super(...), ..., this)`.
+struct SuperCallWrapper<'a, 'i> {
+    ast: AstBuilder<'a>,
+    inits: &'i [Expression<'a>],
+}
+
+impl<'a> VisitMut<'a> for SuperCallWrapper<'a, '_> {
+    fn visit_expression(&mut self, it: &mut Expression<'a>) {
+        walk_mut::walk_expression(self, it);
+        if matches!(it, Expression::CallExpression(call) if call.callee.is_super()) {
+            let mut sequence = self.ast.vec1(it.take_in(self.ast));
+            sequence.extend(self.inits.iter().map(|init| init.clone_in(self.ast.allocator)));
+            sequence.push(self.ast.expression_this(SPAN));
+            *it = self.ast.expression_sequence(SPAN, sequence);
+        }
+    }
+
+    fn visit_function(&mut self, _it: &mut Function<'a>, _flags: ScopeFlags) {}
+
+    fn visit_class(&mut self, _it: &mut Class<'a>) {}
+}
+
+struct SymbolReferenceFinder<'s> {
+    scoping: &'s Scoping,
+    symbol: SymbolId,
+    found: bool,
+}
+
+impl<'a> Visit<'a> for SymbolReferenceFinder<'_> {
+    fn visit_identifier_reference(&mut self, it: &IdentifierReference<'a>) {
+        let symbol = it
+            .reference_id
+            .get()
+            .and_then(|id| self.scoping.get_reference(id).symbol_id());
+        if symbol == Some(self.symbol) {
+            self.found = true;
+        }
+    }
+}
+
+// Collects decorators whose leading identifier (`log` in `@log`,
+// `@log.debug` or `@log("x")`) has no binding in scope.
+struct UnresolvedDecoratorFinder<'s> {
+    scoping: &'s Scoping,
+    // Leading identifier and offset of each unresolved decorator.
+    unresolved: Vec<(String, u32)>,
+}
+
+impl<'a> Visit<'a> for UnresolvedDecoratorFinder<'_> {
+    fn visit_decorator(&mut self, it: &Decorator<'a>) {
+        let mut expr = &it.expression;
+        let id = loop {
+            match expr {
+                Expression::Identifier(id) => break id,
+                Expression::CallExpression(call) => expr = &call.callee,
+                Expression::StaticMemberExpression(member) => expr = &member.object,
+                Expression::ComputedMemberExpression(member) => expr = &member.object,
+                Expression::ParenthesizedExpression(paren) => expr = &paren.expression,
+                _ => return walk::walk_decorator(self, it),
+            }
+        };
+        let resolved = id
+            .reference_id
+            .get()
+            .is_some_and(|reference| self.scoping.get_reference(reference).symbol_id().is_some());
+        if !resolved {
+            self.unresolved.push((id.name.to_string(), it.span.start));
+        }
+        walk::walk_decorator(self, it);
+    }
+}
+
+fn find_unresolved_decorators(program: &Program<'_>) -> Vec<(String, u32)> {
+    let semantic = SemanticBuilder::new().build(program);
+    let scoping = semantic.semantic.into_scoping();
+    let mut finder = UnresolvedDecoratorFinder {
+        scoping: &scoping,
+        unresolved: Vec::new(),
+    };
+    finder.visit_program(program);
+    finder.unresolved
+}
+
+/// Reports decorators that do not resolve to a declaration or import, for
+/// `check_decorator_bindings`.
+pub(crate) fn unresolved_decorators(program: &Program<'_>) -> Vec<Located> {
+    find_unresolved_decorators(program)
+        .into_iter()
+        .map(|(name, offset)| {
+            let message =
+                format!("Decorator \"{name}\" at offset {offset} is not defined or imported");
+            (message, offset)
+        })
+        .collect()
+}
+
+struct CallDecoratorFinder<'s> {
+    source_text: &'s str,
+    warnings: Vec<Located>,
+}
+
+impl<'a> Visit<'a> for CallDecoratorFinder<'_> {
+    fn visit_decorator(&mut self, it: &Decorator<'a>) {
+        if matches!(
+            it.expression.without_parentheses(),
+            Expression::CallExpression(_)
+        ) {
+            let message = format!(
+                "Decorator \"{}\" at offset {} is a call and may have side effects; it is evaluated when the class is defined rather than before, in source order, as native decorators are",
+                it.expression.span().source_text(self.source_text),
+                it.span.start
+            );
+            self.warnings.push((message, it.span.start));
+        }
+        walk::walk_decorator(self, it);
+    }
+}
+
+/// Warns about decorators whose expression is a call, whose side effects
+/// could observe the lowered evaluation order, for `warn_decorator_order`.
+pub(crate) fn call_decorator_warnings(program: &Program<'_>) -> Vec<Located> {
+    let mut finder = CallDecoratorFinder {
+        source_text: program.source_text,
+        warnings: Vec::new(),
+    };
+    finder.visit_program(program);
+    finder.warnings
+}
+
+/// Names of the unresolved decorators, once each in order of first use, for
+/// `auto_import`.
+pub(crate) fn unresolved_decorator_names(program: &Program<'_>) -> Vec<String> {
+    let mut names: Vec<String> = Vec::new();
+    for (name, _) in find_unresolved_decorators(program) {
+        if !names.contains(&name) {
+            names.push(name);
+        }
+    }
+    names
+}
+
+// The part of a member's key usable in a temporary's name, e.g. `x` for
+// `#x` and `my_key` for `"my-key"`. Identifier keys are kept as written,
+// Unicode included; other keys are reduced to ASCII, since not every
+// alphanumeric character may appear in an identifier.
+fn key_name_hint(key: &PropertyKey) -> String {
+    let name = match key {
+        PropertyKey::StaticIdentifier(id) => return id.name.to_string(),
+        PropertyKey::PrivateIdentifier(id) => return id.name.to_string(),
+        PropertyKey::StringLiteral(lit) => lit.value.to_string(),
+        PropertyKey::NumericLiteral(lit) => lit.value.to_string(),
+        _ => return "computedKey".to_string(),
+    };
+    name.chars()
+        .map(|c| if c.is_ascii_alphanumeric() || c == '$' { c } else { '_' })
+        .collect()
+}
+
+fn is_computed_key(key: &PropertyKey) -> bool {
+    !matches!(
+        key,
+        PropertyKey::StaticIdentifier(_)
+            | PropertyKey::PrivateIdentifier(_)
+            | PropertyKey::StringLiteral(_)
+            | PropertyKey::NumericLiteral(_)
+    )
+}
+
+// A class with class decorators is re-emitted as `let C = class C {}`, and
+// class expressions cannot be abstract, so drop the modifier along with the
+// abstract members that have no runtime form.
+pub(crate) fn strip_abstract(class: &mut Class<'_>) {
+    if !class.r#abstract {
+        return;
+    }
+    class.r#abstract = false;
+    class.body.body.retain(|element| match element {
+        ClassElement::MethodDefinition(m) => !m.r#type.is_abstract(),
+        ClassElement::PropertyDefinition(p) => !p.r#type.is_abstract(),
+        ClassElement::AccessorProperty(a) => !a.r#type.is_abstract(),
+        _ => true,
+    });
+}
+
+/// `(helper, name)` for each of `helpers` whose name `scoping` already binds
+/// or references anywhere in the module, `name` being the first free
+/// `<helper>2`, `<helper>3`, ...
+pub fn deconflicted_helper_names(
+    scoping: &Scoping,
+    helpers: &[&'static str],
+) -> Vec<(&'static str, String)> {
+    let taken = |name: &str| {
+        scoping.symbol_names().any(|symbol| symbol == name)
+            || scoping.root_unresolved_references().contains_key(name)
+    };
+    helpers
+        .iter()
+        .filter(|helper| taken(helper))
+        .map(|helper| {
+            let renamed = (2..)
+                .map(|n| format!("{helper}{n}"))
+                .find(|name| !taken(name))
+                .unwrap();
+            (*helper, renamed)
+        })
+        .collect()
+}
+
 impl<'a> DecoratorTransformer<'a> {
-    pub fn new(allocator: &'a Allocator) -> Self {
+    pub fn new(allocator: &'a Allocator, options: TransformOptions) -> Self {
         Self {
             errors: Vec::new(),
+            warnings: Vec::new(),
+            stats: TransformStats::default(),
             in_decorated_class: RefCell::new(false),
+            init_names: RefCell::new((Atom::from("_initProto"), Atom::from("_initClass"))),
+            class_decorator_temps: RefCell::new(None),
+            helper_renames: Vec::new(),
             helpers_injected: RefCell::new(false),
+            uses_to_property_key: RefCell::new(false),
+            uses_set_function_name: RefCell::new(false),
+            uses_private_storage: RefCell::new(false),
             classes_with_class_decorators: RefCell::new(Vec::new()),
-            _allocator: allocator,
+            hoisted_descriptors: RefCell::new(Vec::new()),
+            class_temps: RefCell::new(Vec::new()),
+            after_class_statements: RefCell::new(Vec::new()),
+            options,
+            descriptor_hook: None,
+            allocator,
         }
     }
 
-    pub fn get_class_decorator_strings(&self) -> Vec<(String, Vec<String>)> {
-        self.classes_with_class_decorators
-            .borrow()
+    pub fn with_descriptor_hook(mut self, hook: &'a DescriptorHook<'a>) -> Self {
+        self.descriptor_hook = Some(hook);
+        self
+    }
+
+    /// Renames every top-level helper function the module already binds or
+    /// references the name of, as [`deconflicted_helper_names`] does.
+    pub fn deconflict_helpers(&mut self, scoping: &Scoping) {
+        let helpers = [EXPORTED_HELPERS, INTERNAL_HELPERS, PRIVATE_STORAGE_HELPERS].concat();
+        for (helper, renamed) in deconflicted_helper_names(scoping, &helpers) {
+            self.helper_renames
+                .push((helper, Atom::from(self.allocator.alloc_str(&renamed))));
+        }
+    }
+
+    /// `(helper, local name)` for each helper `deconflict_helpers` renamed.
+    pub fn helper_renames(&self) -> Vec<(&'static str, &str)> {
+        self.helper_renames
             .iter()
-            .map(|info| {
-                let decorator_strings = info
-                    .decorators
-                    .iter()
-                    .map(|expr| {
-                        let mut codegen = Codegen::new();
-                        codegen.print_expression(expr);
-                        codegen.into_source_text()
-                    })
-                    .collect();
-                (info.class_name.clone(), decorator_strings)
-            })
+            .map(|(helper, name)| (*helper, name.as_str()))
             .collect()
     }
 
-    pub fn check_for_decorators(&self, program: &Program<'a>) -> bool {
-        program
-            .body
+    // The name generated code calls `helper` by.
+    fn helper(&self, helper: &'static str) -> Atom<'a> {
+        self.helper_renames
             .iter()
-            .any(|stmt| self.statement_has_decorators(stmt))
+            .find(|(name, _)| *name == helper)
+            .map_or(Atom::from(helper), |(_, renamed)| *renamed)
     }
 
-    fn statement_has_decorators(&self, stmt: &Statement<'a>) -> bool {
-        match stmt {
-            Statement::ClassDeclaration(class) => self.has_decorators(class),
-            Statement::ExportDefaultDeclaration(export) => {
-                matches!(&export.declaration, ExportDefaultDeclarationKind::ClassDeclaration(class) if self.has_decorators(class))
-            }
-            Statement::ExportNamedDeclaration(export) => {
-                matches!(&export.declaration, Some(Declaration::ClassDeclaration(class)) if self.has_decorators(class))
-            }
-            _ => false,
-        }
+    pub fn take_class_temps(&self) -> Vec<ClassTemps<'a>> {
+        self.class_temps.take()
+    }
+
+    pub fn take_hoisted_descriptors(&self) -> Vec<HoistedDescriptors<'a>> {
+        self.hoisted_descriptors.take()
+    }
+
+    pub fn take_after_class_statements(&self) -> Vec<AfterClassStatements<'a>> {
+        self.after_class_statements.take()
+    }
+
+    /// Original spans of the names of classes rebound by the class-decorator
+    /// rewrite, for naming their source map mappings.
+    pub fn get_class_name_spans(&self) -> Vec<(Span, String)> {
+        self.classes_with_class_decorators
+            .borrow()
+            .iter()
+            .filter_map(|info| Some((info.name_span?, info.class_name.clone())))
+            .collect()
+    }
+
+    pub fn check_for_decorators(&self, program: &Program<'a>) -> bool {
+        let mut finder = DecoratorFinder { found: false };
+        finder.visit_program(program);
+        finder.found
     }
 
     pub fn needs_helpers(&self) -> bool {
         *self.helpers_injected.borrow()
     }
 
+    /// Helpers the transformed code calls directly, in export order.
+    /// Whether the output calls `_privateStorageGet`/`_privateStorageSet`,
+    /// which are not part of the bundled helpers.
+    pub fn uses_private_storage(&self) -> bool {
+        *self.uses_private_storage.borrow()
+    }
+
+    pub fn used_helpers(&self) -> Vec<&'static str> {
+        let mut used = Vec::new();
+        if self.needs_helpers() {
+            used.push("_applyDecs");
+        }
+        if *self.uses_to_property_key.borrow() {
+            used.push("_toPropertyKey");
+        }
+        if *self.uses_set_function_name.borrow() {
+            used.push("_setFunctionName");
+        }
+        if self.uses_private_storage() {
+            used.extend(PRIVATE_STORAGE_HELPERS);
+        }
+        used
+    }
+
     fn has_decorators(&self, class: &Class<'a>) -> bool {
         !class.decorators.is_empty()
             || class.body.body.iter().any(|element| match element {
@@ -95,6 +510,89 @@ impl<'a> DecoratorTransformer<'a> {
             })
     }
 
+    // The class binding to decorate after the class statement with
+    // `decoration_site: "after-class"`. Only top-level named declarations
+    // qualify, and only when no decoration needs the class scope: private
+    // members (access closures and brand checks), static fields and
+    // accessors (initialized before a later call could run) and class
+    // decorators (which may replace the class) keep the static block.
+    fn after_class_target(
+        &self,
+        class: &Class<'a>,
+        has_class_decorators: bool,
+        member_inits: &[Option<MemberInit<'a>>],
+        ctx: &TraverseCtx<'a, TransformerState>,
+    ) -> Option<Atom<'a>> {
+        if self.options.decoration_site != DecorationSite::AfterClass
+            || !class.is_declaration()
+            || has_class_decorators
+            || ctx.current_scope_id() != ctx.scoping().root_scope_id()
+        {
+            return None;
+        }
+        let needs_class_scope = member_inits.iter().flatten().any(|m| {
+            m.is_private()
+                || (m.is_static && matches!(m.kind, DecoratorKind::Field | DecoratorKind::Accessor))
+        });
+        if needs_class_scope {
+            return None;
+        }
+        class.id.as_ref().map(|id| id.name)
+    }
+
+    // With `lazy_decorators`, whether decoration can wait for the first
+    // construction: nothing but `_initProto` reads the results of
+    // `_applyDecs` when only instance methods, getters and setters are
+    // decorated.
+    fn decorates_lazily(
+        &self,
+        has_class_decorators: bool,
+        member_inits: &[Option<MemberInit<'a>>],
+    ) -> bool {
+        self.options.lazy_decorators
+            && !has_class_decorators
+            && member_inits.iter().flatten().all(|m| {
+                !m.is_static
+                    && matches!(
+                        m.kind,
+                        DecoratorKind::Method | DecoratorKind::Getter | DecoratorKind::Setter
+                    )
+            })
+    }
+
+    // Whether any instance member and any static member is decorated; only
+    // then does `_applyDecs` return a proto or static initializer
+    // (`_initProto`/`_initClass`).
+    fn decorated_member_sides(class: &Class<'a>) -> (bool, bool) {
+        let mut sides = (false, false);
+        for element in &class.body.body {
+            let decorated = match element {
+                ClassElement::MethodDefinition(m) => !m.decorators.is_empty(),
+                ClassElement::PropertyDefinition(p) => !p.decorators.is_empty(),
+                ClassElement::AccessorProperty(a) => !a.decorators.is_empty(),
+                _ => false,
+            };
+            if decorated && element.r#static() {
+                sides.1 = true;
+            } else if decorated {
+                sides.0 = true;
+            }
+        }
+        sides
+    }
+
+    // `declare` fields have no runtime presence, so their decorators have
+    // nothing to apply to and must not produce a descriptor.
+    fn strip_ambient_field_decorators(&self, class: &mut Class<'a>) {
+        for element in &mut class.body.body {
+            if let ClassElement::PropertyDefinition(p) = element {
+                if p.declare {
+                    p.decorators.clear();
+                }
+            }
+        }
+    }
+
     fn clone_expression(
         &self,
         expr: &Expression<'a>,
@@ -103,7 +601,7 @@ impl<'a> DecoratorTransformer<'a> {
         match expr {
             Expression::Identifier(ident) => Expression::Identifier(
                 ctx.ast
-                    .alloc(ctx.ast.identifier_reference(SPAN, ident.name)),
+                    .alloc(ctx.ast.identifier_reference(ident.span, ident.name)),
             ),
             Expression::CallExpression(call) => {
                 let callee = self.clone_expression(&call.callee, ctx);
@@ -113,7 +611,7 @@ impl<'a> DecoratorTransformer<'a> {
                         Argument::SpreadElement(spread) => {
                             let spread_arg = self.clone_expression(&spread.argument, ctx);
                             Argument::SpreadElement(
-                                ctx.ast.alloc(ctx.ast.spread_element(SPAN, spread_arg)),
+                                ctx.ast.alloc(ctx.ast.spread_element(spread.span, spread_arg)),
                             )
                         }
                         _ => match arg.as_expression() {
@@ -126,14 +624,14 @@ impl<'a> DecoratorTransformer<'a> {
                     arguments.push(cloned_arg);
                 }
                 ctx.ast
-                    .expression_call(SPAN, callee, NONE, arguments, false)
+                    .expression_call(call.span, callee, NONE, arguments, false)
             }
             Expression::StaticMemberExpression(member) => {
                 let object = self.clone_expression(&member.object, ctx);
-                let property = ctx.ast.identifier_name(SPAN, member.property.name);
+                let property = ctx.ast.identifier_name(member.property.span, member.property.name);
                 Expression::from(
                     ctx.ast
-                        .member_expression_static(SPAN, object, property, false),
+                        .member_expression_static(member.span, object, property, false),
                 )
             }
             Expression::ComputedMemberExpression(member) => {
@@ -141,17 +639,28 @@ impl<'a> DecoratorTransformer<'a> {
                 let property = self.clone_expression(&member.expression, ctx);
                 Expression::from(
                     ctx.ast
-                        .member_expression_computed(SPAN, object, property, false),
+                        .member_expression_computed(member.span, object, property, false),
                 )
             }
             Expression::PrivateFieldExpression(private) => {
                 let object = self.clone_expression(&private.object, ctx);
-                let field = ctx.ast.private_identifier(SPAN, private.field.name);
+                let field = ctx.ast.private_identifier(private.field.span, private.field.name);
                 Expression::from(
                     ctx.ast
-                        .member_expression_private_field_expression(SPAN, object, field, false),
+                        .member_expression_private_field_expression(private.span, object, field, false),
                 )
             }
+            Expression::ParenthesizedExpression(paren) => {
+                let expression = self.clone_expression(&paren.expression, ctx);
+                ctx.ast.expression_parenthesized(paren.span, expression)
+            }
+            Expression::SequenceExpression(sequence) => {
+                let mut expressions = ctx.ast.vec();
+                for expression in &sequence.expressions {
+                    expressions.push(self.clone_expression(expression, ctx));
+                }
+                ctx.ast.expression_sequence(sequence.span, expressions)
+            }
             _ => {
                 let mut codegen = Codegen::new();
                 codegen.print_expression(expr);
@@ -169,232 +678,2147 @@ impl<'a> DecoratorTransformer<'a> {
         }
     }
 
-    fn extract_property_key_string(
+    fn extract_property_key_string(
+        &self,
+        key: &PropertyKey<'a>,
+        ctx: &TraverseCtx<'a, TransformerState>,
+    ) -> &'a str {
+        match key {
+            PropertyKey::StaticIdentifier(id) => id.name.as_str(),
+            PropertyKey::PrivateIdentifier(id) => id.name.as_str(),
+            PropertyKey::StringLiteral(lit) => lit.value.as_str(),
+            PropertyKey::NumericLiteral(lit) => {
+                let s = lit.value.to_string();
+                ctx.ast.allocator.alloc_str(&s)
+            }
+            _ => "computed",
+        }
+    }
+
+    fn transform_class_with_decorators(
+        &mut self,
+        class: &mut Class<'a>,
+        ctx: &mut TraverseCtx<'a, TransformerState>,
+    ) -> bool {
+        self.strip_ambient_field_decorators(class);
+        if !self.has_decorators(class) {
+            return false;
+        }
+        let binding_name = match class.id {
+            None if class.is_expression() => Self::class_binding_name(ctx),
+            _ => None,
+        };
+        if !self.is_included(class, binding_name) {
+            return false;
+        }
+        if let Some(error) = self.check_self_referencing_keys(class, ctx) {
+            self.errors.push(error);
+            return false;
+        }
+
+        *self.in_decorated_class.borrow_mut() = true;
+        *self.helpers_injected.borrow_mut() = true;
+        let (has_instance, has_static) = Self::decorated_member_sides(class);
+        let init_proto = has_instance.then(|| ctx.generate_uid_name("initProto"));
+        let init_class = has_static.then(|| ctx.generate_uid_name("initClass"));
+        *self.init_names.borrow_mut() = (
+            init_proto.unwrap_or(Atom::from("_initProto")),
+            init_class.unwrap_or(Atom::from("_initClass")),
+        );
+        self.record_class_temps(class.span, init_proto.into_iter().chain(init_class));
+        self.stats.classes_transformed += 1;
+        self.stats.member_decorators += class
+            .body
+            .body
+            .iter()
+            .map(|element| match element {
+                ClassElement::MethodDefinition(m) => m.decorators.len(),
+                ClassElement::PropertyDefinition(p) => p.decorators.len(),
+                ClassElement::AccessorProperty(a) => a.decorators.len(),
+                _ => 0,
+            })
+            .sum::<usize>() as u32;
+        let class_decorators = self.collect_class_decorators(class, ctx);
+        self.stats.class_decorators += class_decorators.len() as u32;
+        let has_class_decorators = !class_decorators.is_empty();
+        // Markers leave a named class as written, running its initializer in
+        // place; any other class is rebound to the decorated class and its
+        // initializer kept to run once it is.
+        let class_init = (has_class_decorators
+            && (class.id.is_none() || !self.is_marker_only(&class_decorators)))
+        .then(|| ctx.generate_uid_name("classInit"));
+        // `spec_compliant` applies the class decorators of a named class in
+        // its static block, with the member decorators, rather than after it.
+        let applied_in_class = (self.options.spec_compliant && class.id.is_some())
+            .then_some(class_init)
+            .flatten()
+            .map(|class_init| {
+                let decorated = ctx.generate_uid_name(class.id.as_ref().unwrap().name.as_str());
+                (ctx.generate_uid_name("classDecs"), decorated, class_init)
+            });
+        *self.class_decorator_temps.borrow_mut() = applied_in_class;
+        if let Some((class_decs, decorated, _)) = applied_in_class {
+            self.record_class_temps(class.span, [class_decs, decorated]);
+        }
+        self.record_class_temps(class.span, class_init);
+
+        if !class_decorators.is_empty() {
+            // `export default class {}` is bound to a temporary and named
+            // "default" by `_setFunctionName`.
+            let class_name = match &class.id {
+                Some(id) => id.name.to_string(),
+                None => {
+                    *self.uses_set_function_name.borrow_mut() = true;
+                    ctx.generate_uid_name("default").to_string()
+                }
+            };
+            self.classes_with_class_decorators
+                .borrow_mut()
+                .push(ClassDecoratorInfo {
+                    class_name,
+                    name_span: class.id.as_ref().map(|id| id.span),
+                    class_span: class.span,
+                    decorators: class_decorators,
+                    class_init,
+                    applied_in_class: applied_in_class
+                        .map(|(class_decs, decorated, _)| (class_decs, decorated)),
+                });
+            strip_abstract(class);
+        }
+
+        let computed_members = self.hoist_computed_keys(class, ctx);
+        let member_inits = self.plan_member_inits(class, ctx);
+        // Class decorators alone are applied outside the class, leaving the
+        // static block nothing to do, unless `spec_compliant`.
+        let mut static_block = None;
+        if has_instance || has_static || applied_in_class.is_some() {
+            match self.after_class_target(class, has_class_decorators, &member_inits, ctx) {
+                Some(name) => {
+                    let target = self.identifier(name, ctx);
+                    let statements = self.build_decoration_statements(
+                        class,
+                        target,
+                        &computed_members,
+                        &member_inits,
+                        false,
+                        ctx,
+                    );
+                    self.after_class_statements
+                        .borrow_mut()
+                        .push(AfterClassStatements {
+                            class_span: class.span,
+                            statements,
+                        });
+                }
+                None => {
+                    let lazy = self.decorates_lazily(has_class_decorators, &member_inits);
+                    if self.options.lazy_decorators && !lazy {
+                        let message = format!(
+                            "Class at offset {} is decorated when it is defined: lazy_decorators only defers classes whose only decorated members are instance methods, getters and setters",
+                            class.span.start
+                        );
+                        self.warnings.push((message, class.span.start));
+                    }
+                    static_block = Some(self.create_decorator_static_block_from_class(
+                        class,
+                        &computed_members,
+                        &member_inits,
+                        lazy,
+                        ctx,
+                    ));
+                }
+            }
+        }
+
+        let instance_inits = self.apply_member_inits(class, &computed_members, member_inits, ctx);
+        // First in the body, so the initializer temporaries are assigned
+        // before any static field initializer runs.
+        if let Some(static_block) = static_block {
+            class.body.body.insert(0, static_block);
+        }
+
+        if has_instance {
+            self.ensure_constructor_with_init(class, instance_inits, ctx);
+        }
+
+        let defined = self.members_defined_by_property(class);
+        class
+            .body
+            .body
+            .retain(|element| !defined.contains(&element.span()));
+        if self.options.node_compat {
+            self.replace_static_blocks(class, ctx);
+        }
+
+        class.decorators.clear();
+        for element in &mut class.body.body {
+            match element {
+                ClassElement::MethodDefinition(m) => m.decorators.clear(),
+                ClassElement::PropertyDefinition(p) => p.decorators.clear(),
+                ClassElement::AccessorProperty(a) => a.decorators.clear(),
+                _ => {}
+            }
+        }
+
+        true
+    }
+
+    // The binding an anonymous class expression is declared or assigned to.
+    fn class_binding_name(ctx: &TraverseCtx<'a, TransformerState>) -> Option<Atom<'a>> {
+        match ctx.parent() {
+            Ancestor::VariableDeclaratorInit(declarator) => declarator
+                .id()
+                .get_binding_identifier()
+                .map(|id| id.name),
+            Ancestor::AssignmentExpressionRight(assignment)
+                if !assignment.operator().is_arithmetic()
+                    && !assignment.operator().is_bitwise() =>
+            {
+                match assignment.left() {
+                    AssignmentTarget::AssignmentTargetIdentifier(id) => Some(id.name),
+                    _ => None,
+                }
+            }
+            _ => None,
+        }
+    }
+
+    // Whether `include_classes` lets the class be transformed: it is named,
+    // by its own name or else the binding it initializes, in the list.
+    fn is_included(&self, class: &Class<'a>, binding_name: Option<Atom<'a>>) -> bool {
+        let Some(include_classes) = &self.options.include_classes else {
+            return true;
+        };
+        class
+            .id
+            .as_ref()
+            .map(|id| id.name)
+            .or(binding_name)
+            .is_some_and(|name| include_classes.iter().any(|included| included == name.as_str()))
+    }
+
+    // Class expressions have no binding to reassign, so class decorators are
+    // applied inline, running the class initializer before the decorated
+    // class is used: `([_C, _classInit] = _applyDecs(class { ... }, [], [dec]).c,
+    // _classInit(), _C)`.
+    fn transform_class_expression_decorators(
+        &mut self,
+        expr: &mut Expression<'a>,
+        ctx: &mut TraverseCtx<'a, TransformerState>,
+    ) {
+        let Expression::ClassExpression(class) = expr else {
+            return;
+        };
+        if class.decorators.is_empty() {
+            return;
+        }
+        // `const Widget = @dec class {}` and `Widget = @dec class {}` name the
+        // class `Widget`, which the call wrapping it would lose; set it before
+        // the decorators run.
+        let binding_name = match class.id {
+            Some(_) => None,
+            None => Self::class_binding_name(ctx),
+        };
+        if !self.is_included(class, binding_name) {
+            return;
+        }
+        let decorators: Vec<_> = class
+            .decorators
+            .iter_mut()
+            .map(|dec| dec.expression.take_in(ctx.ast))
+            .collect();
+        class.decorators.clear();
+        if !self.transform_class_with_decorators(class, ctx) {
+            self.stats.classes_transformed += 1;
+        }
+        self.stats.class_decorators += decorators.len() as u32;
+        *self.helpers_injected.borrow_mut() = true;
+        let decorated = ctx.generate_uid_name(
+            class
+                .id
+                .as_ref()
+                .map(|id| id.name)
+                .or(binding_name)
+                .map_or("class", |name| name.as_str()),
+        );
+        let class_init = ctx.generate_uid_name("classInit");
+        self.record_class_temps(class.span, [decorated, class_init]);
+
+        let mut class_expr = expr.take_in(ctx.ast);
+        if let Some(name) = binding_name {
+            *self.uses_set_function_name.borrow_mut() = true;
+            let mut arguments = ctx.ast.vec();
+            arguments.push(Argument::from(class_expr));
+            arguments.push(Argument::from(
+                ctx.ast.expression_string_literal(SPAN, name, None),
+            ));
+            class_expr = ctx.ast.expression_call(
+                SPAN,
+                self.identifier(self.helper("_setFunctionName"), ctx),
+                NONE,
+                arguments,
+                false,
+            );
+        }
+        // `([_C, _classInit] = _applyDecs(class {}, [], [dec]).c, _classInit(), _C)`
+        let call = self.build_class_decorators_call(class_expr, decorators, ctx);
+        let mut expressions = ctx.ast.vec();
+        expressions.push(ctx.ast.expression_assignment(
+            SPAN,
+            AssignmentOperator::Assign,
+            self.array_assignment_target(&[Some(decorated), Some(class_init)], ctx),
+            call,
+        ));
+        expressions.push(self.build_call(class_init, ctx));
+        expressions.push(self.identifier(decorated, ctx));
+        *expr = ctx.ast.expression_sequence(SPAN, expressions);
+    }
+
+    // `_applyDecs(class_expr, [], [decorators]).c`, which applies the class
+    // decorators when read.
+    fn build_class_decorators_call(
+        &self,
+        class_expr: Expression<'a>,
+        decorators: Vec<Expression<'a>>,
+        ctx: &TraverseCtx<'a, TransformerState>,
+    ) -> Expression<'a> {
+        let mut arguments = ctx.ast.vec();
+        arguments.push(Argument::from(class_expr));
+        arguments.push(Argument::from(ctx.ast.expression_array(SPAN, ctx.ast.vec())));
+        let class_decs = decorators.into_iter().map(ArrayExpressionElement::from);
+        arguments.push(Argument::from(
+            ctx.ast.expression_array(SPAN, ctx.ast.vec_from_iter(class_decs)),
+        ));
+        let apply_decs_call = ctx.ast.expression_call(
+            SPAN,
+            self.identifier(self.helper("_applyDecs"), ctx),
+            NONE,
+            arguments,
+            false,
+        );
+        let c_property = ctx.ast.identifier_name(SPAN, "c");
+        Expression::from(
+            ctx.ast
+                .member_expression_static(SPAN, apply_decs_call, c_property, false),
+        )
+    }
+
+    // Rewrites each statement in `statements` declaring a class with class
+    // decorators into `let C = class C { ... }; [C, _classInit] =
+    // _applyDecs(C, [], [dec]).c; _classInit();`, followed by `export { C };` or `export default C;` where the class was
+    // exported. The new statements keep the span of the declaration, so the
+    // class temporaries and edits still find it.
+    fn apply_class_decorators(
+        &self,
+        statements: &mut ArenaVec<'a, Statement<'a>>,
+        ctx: &mut TraverseCtx<'a, TransformerState>,
+    ) {
+        if self.classes_with_class_decorators.borrow().is_empty() {
+            return;
+        }
+        let mut i = 0;
+        while i < statements.len() {
+            let Some(class_span) = Self::declared_class(&statements[i]).map(|class| class.span)
+            else {
+                i += 1;
+                continue;
+            };
+            let infos = self.classes_with_class_decorators.borrow();
+            let Some(info) = infos.iter().find(|info| info.class_span == class_span) else {
+                i += 1;
+                continue;
+            };
+            let statement = statements[i].take_in(ctx.ast);
+            let replacement = self.build_class_decoration(statement, info, ctx);
+            let count = replacement.len();
+            statements.splice(i..=i, replacement);
+            i += count;
+        }
+    }
+
+    fn declared_class<'s>(statement: &'s Statement<'a>) -> Option<&'s Class<'a>> {
+        match statement {
+            Statement::ClassDeclaration(class) => Some(class),
+            Statement::ExportNamedDeclaration(export) => match &export.declaration {
+                Some(Declaration::ClassDeclaration(class)) => Some(class),
+                _ => None,
+            },
+            Statement::ExportDefaultDeclaration(export) => match &export.declaration {
+                ExportDefaultDeclarationKind::ClassDeclaration(class) => Some(class),
+                _ => None,
+            },
+            _ => None,
+        }
+    }
+
+    fn build_class_decoration(
+        &self,
+        statement: Statement<'a>,
+        info: &ClassDecoratorInfo<'a>,
+        ctx: &TraverseCtx<'a, TransformerState>,
+    ) -> Vec<Statement<'a>> {
+        let span = statement.span();
+        let name = ctx.ast.atom(&info.class_name);
+        let decorators = info
+            .decorators
+            .iter()
+            .map(|decorator| decorator.clone_in(ctx.ast.allocator))
+            .collect();
+        // Anonymous default exports are named by their binding, markers
+        // included: there is none to apply them to without rewriting the
+        // class.
+        if info.name_span.is_some() && self.is_marker_only(&info.decorators) {
+            // Markers never replace the class, so the declaration stays as
+            // written; reading `.c` still runs the decorators for their
+            // effects, and `.c[1]()` the initializers they add.
+            let c_member = self.build_class_decorators_call(self.identifier(name, ctx), decorators, ctx);
+            let index = ctx
+                .ast
+                .expression_numeric_literal(SPAN, 1.0, None, NumberBase::Decimal);
+            let class_init = ctx.ast.member_expression_computed(SPAN, c_member, index, false);
+            let call = ctx.ast.expression_call(
+                SPAN,
+                Expression::from(class_init),
+                NONE,
+                ctx.ast.vec(),
+                false,
+            );
+            return vec![statement, ctx.ast.statement_expression(span, call)];
+        }
+        let (mut class, export) = match statement {
+            Statement::ClassDeclaration(class) => (class, ClassExport::None),
+            Statement::ExportNamedDeclaration(export) => match export.unbox().declaration {
+                Some(Declaration::ClassDeclaration(class)) => (class, ClassExport::Named),
+                _ => unreachable!("declared_class matched a class"),
+            },
+            Statement::ExportDefaultDeclaration(export) => match export.unbox().declaration {
+                ExportDefaultDeclarationKind::ClassDeclaration(class) => {
+                    (class, ClassExport::Default)
+                }
+                _ => unreachable!("declared_class matched a class"),
+            },
+            _ => unreachable!("declared_class matched a class"),
+        };
+        class.r#type = ClassType::ClassExpression;
+        let mut class_expr = Expression::ClassExpression(class);
+        if info.name_span.is_none() {
+            let mut arguments = ctx.ast.vec();
+            arguments.push(Argument::from(class_expr));
+            arguments.push(Argument::from(
+                ctx.ast.expression_string_literal(SPAN, "default", None),
+            ));
+            class_expr = ctx.ast.expression_call(
+                SPAN,
+                self.identifier(self.helper("_setFunctionName"), ctx),
+                NONE,
+                arguments,
+                false,
+            );
+        }
+
+        let binding = ctx.ast.binding_pattern(
+            ctx.ast
+                .binding_pattern_kind_binding_identifier(info.name_span.unwrap_or(SPAN), name),
+            NONE,
+            false,
+        );
+        let declarator = ctx.ast.variable_declarator(
+            SPAN,
+            VariableDeclarationKind::Let,
+            binding,
+            Some(class_expr),
+            false,
+        );
+        let class_init = info
+            .class_init
+            .expect("a rewritten class has an initializer temporary");
+        let mut statements = Vec::new();
+        // `C = _C` or `[C, _classInit] = _applyDecs(C, [], [dec]).c`.
+        let (target, decorated) = match info.applied_in_class {
+            // `_classDecs = [dec];`, evaluating the decorators before the
+            // class as the spec does; its static block then stores the
+            // decorated class in `_C` and its initializer in `_classInit`.
+            Some((class_decs, decorated)) => {
+                let array = ctx.ast.expression_array(
+                    SPAN,
+                    ctx.ast
+                        .vec_from_iter(decorators.into_iter().map(ArrayExpressionElement::from)),
+                );
+                let assignment = self.build_temp_assignment(class_decs, array, ctx);
+                statements.push(ctx.ast.statement_expression(span, assignment));
+                let target = ctx
+                    .ast
+                    .simple_assignment_target_assignment_target_identifier(SPAN, name);
+                (AssignmentTarget::from(target), self.identifier(decorated, ctx))
+            }
+            None => (
+                self.array_assignment_target(&[Some(name), Some(class_init)], ctx),
+                self.build_class_decorators_call(self.identifier(name, ctx), decorators, ctx),
+            ),
+        };
+        statements.push(Statement::from(ctx.ast.declaration_variable(
+            span,
+            VariableDeclarationKind::Let,
+            ctx.ast.vec1(declarator),
+            false,
+        )));
+        let assignment =
+            ctx.ast
+                .expression_assignment(SPAN, AssignmentOperator::Assign, target, decorated);
+        statements.push(ctx.ast.statement_expression(span, assignment));
+        statements.push(ctx.ast.statement_expression(span, self.build_call(class_init, ctx)));
+        match export {
+            ClassExport::None => {}
+            ClassExport::Named => {
+                let specifier = ctx.ast.export_specifier(
+                    SPAN,
+                    ctx.ast.module_export_name_identifier_reference(SPAN, name),
+                    ctx.ast.module_export_name_identifier_reference(SPAN, name),
+                    ImportOrExportKind::Value,
+                );
+                statements.push(Statement::from(
+                    ctx.ast.module_declaration_export_named_declaration(
+                        span,
+                        None,
+                        ctx.ast.vec1(specifier),
+                        None,
+                        ImportOrExportKind::Value,
+                        NONE,
+                    ),
+                ));
+            }
+            ClassExport::Default => {
+                let local = ctx.ast.alloc(ctx.ast.identifier_reference(SPAN, name));
+                statements.push(Statement::from(
+                    ctx.ast.module_declaration_export_default_declaration(
+                        span,
+                        ExportDefaultDeclarationKind::Identifier(local),
+                    ),
+                ));
+            }
+        }
+        statements
+    }
+
+    // A decorator matches a marker by its callee, so `register` also covers
+    // `register("name")`.
+    fn is_marker_only(&self, decorators: &[Expression<'a>]) -> bool {
+        let markers = &self.options.marker_decorators;
+        !markers.is_empty()
+            && decorators.iter().all(|decorator| {
+                let callee = match decorator.without_parentheses() {
+                    Expression::CallExpression(call) => &call.callee,
+                    other => other,
+                };
+                let mut codegen = Codegen::new().with_options(self.options.codegen_options());
+                codegen.print_expression(callee);
+                let callee = codegen.into_source_text();
+                markers.contains(&callee)
+            })
+    }
+
+    fn create_decorator_static_block_from_class(
+        &self,
+        class: &Class<'a>,
+        computed_members: &[Option<ComputedMember<'a>>],
+        member_inits: &[Option<MemberInit<'a>>],
+        lazy: bool,
+        ctx: &mut TraverseCtx<'a, TransformerState>,
+    ) -> ClassElement<'a> {
+        let target = ctx.ast.expression_this(SPAN);
+        let statements = self.build_decoration_statements(
+            class,
+            target,
+            computed_members,
+            member_inits,
+            lazy,
+            ctx,
+        );
+        let scope_id = ctx.create_child_scope_of_current(ScopeFlags::ClassStaticBlock);
+        ctx.ast
+            .class_element_static_block_with_scope_id(SPAN, statements, scope_id)
+    }
+
+    // `[...] = _applyDecs(target, [...], []).e;` followed, when static
+    // members are decorated, by `if (_initClass) _initClass();`. When `lazy`,
+    // the assignment is deferred to the first construction by
+    // `build_lazy_init_proto`.
+    fn build_decoration_statements(
+        &self,
+        class: &Class<'a>,
+        target: Expression<'a>,
+        computed_members: &[Option<ComputedMember<'a>>],
+        member_inits: &[Option<MemberInit<'a>>],
+        lazy: bool,
+        ctx: &mut TraverseCtx<'a, TransformerState>,
+    ) -> ArenaVec<'a, Statement<'a>> {
+        let mut statements = ctx.ast.vec();
+        for storage in member_inits.iter().flatten().filter_map(|m| self.weak_storage(m)) {
+            statements.push(self.build_weak_storage_creation(storage, ctx));
+        }
+        statements.extend(self.build_define_property_statements(class, &target, ctx));
+        let mut member_desc_array = self.build_member_descriptor_array_from_class(
+            class,
+            computed_members,
+            member_inits,
+            ctx,
+        );
+        // Access closures name private fields, which are out of scope at the
+        // module level.
+        let has_private_access = member_inits
+            .iter()
+            .flatten()
+            .any(|m| m.private_storage().is_some() || m.call.is_some());
+        if self.options.hoist_descriptors
+            && !lazy
+            && !has_private_access
+            && computed_members.iter().all(Option::is_none)
+            && Self::has_inert_member_decorators(class)
+            && ctx.current_scope_id() == ctx.scoping().root_scope_id()
+        {
+            member_desc_array = self.hoist_descriptor_array(member_desc_array, class.span, ctx);
+        }
+        let class_temps = *self.class_decorator_temps.borrow();
+        let class_dec_array = match class_temps {
+            Some((class_decs, _, _)) => self.identifier(class_decs, ctx),
+            None => ctx.ast.expression_array(SPAN, ctx.ast.vec()),
+        };
+        let mut extra_arguments = Vec::new();
+        if let Some(brand) = member_inits
+            .iter()
+            .flatten()
+            .filter(|m| !m.is_static)
+            .find_map(|m| m.private_name)
+        {
+            extra_arguments.push(ctx.ast.void_0(SPAN));
+            extra_arguments.push(self.build_private_brand_check(brand, ctx));
+        }
+        let mut decoration = Vec::new();
+        // With `verbose_output`, the descriptors and the result of
+        // `_applyDecs` get named constants to inspect while stepping through:
+        // `const _descriptors = [...]; const _result = _applyDecs(this,
+        // _descriptors, []); [...] = _result.e;`.
+        if self.options.verbose_output && !matches!(member_desc_array, Expression::Identifier(_)) {
+            let descriptors = ctx.generate_uid_name("descriptors");
+            decoration.push(self.build_const_declaration(descriptors, member_desc_array, ctx));
+            member_desc_array = self.identifier(descriptors, ctx);
+        }
+        let mut apply_decs_call = self.build_apply_decs_call(
+            target,
+            member_desc_array,
+            class_dec_array,
+            extra_arguments,
+            ctx,
+        );
+        if self.options.verbose_output {
+            let result = ctx.generate_uid_name("result");
+            decoration.push(self.build_const_declaration(result, apply_decs_call, ctx));
+            apply_decs_call = self.identifier(result, ctx);
+        }
+        decoration.push(self.build_apply_decs_assignment(
+            &self.apply_decs_targets(member_inits),
+            apply_decs_call,
+            class_temps.map(|(_, decorated, class_init)| (decorated, class_init)),
+            ctx,
+        ));
+        if lazy {
+            statements.push(self.build_lazy_init_proto(decoration, ctx));
+            return statements;
+        }
+        statements.extend(decoration);
+        if member_inits.iter().flatten().any(|m| m.is_static) {
+            statements.push(self.build_init_class_if_statement(ctx));
+        }
+        statements
+    }
+
+    // For `node_compat`: rewrites each static block, generated or written,
+    // as `static #_ = (() => { ... })();`, which runs at the same point of
+    // class evaluation with the same `this` on runtimes predating static
+    // blocks.
+    fn replace_static_blocks(&self, class: &mut Class<'a>, ctx: &mut TraverseCtx<'a, TransformerState>) {
+        let mut private_names: Vec<Atom<'a>> = class
+            .body
+            .body
+            .iter()
+            .filter_map(|element| match element.property_key() {
+                Some(PropertyKey::PrivateIdentifier(id)) => Some(id.name),
+                _ => None,
+            })
+            .collect();
+        for element in class.body.body.iter_mut() {
+            let ClassElement::StaticBlock(block) = element else {
+                continue;
+            };
+            let statements = block.body.take_in(ctx.ast);
+            let body = ctx.ast.alloc_function_body(SPAN, ctx.ast.vec(), statements);
+            let params = self.build_formal_parameters(&[], ctx);
+            let arrow = ctx.ast.expression_arrow_function_with_scope_id_and_pure_and_pife(
+                SPAN,
+                false,
+                false,
+                NONE,
+                params,
+                NONE,
+                body,
+                block.scope_id(),
+                false,
+                false,
+            );
+            let call = ctx.ast.expression_call(
+                SPAN,
+                ctx.ast.expression_parenthesized(SPAN, arrow),
+                NONE,
+                ctx.ast.vec(),
+                false,
+            );
+            let name = Self::unique_private_name("_", &private_names, ctx);
+            private_names.push(name);
+            *element = ctx.ast.class_element_property_definition(
+                block.span,
+                PropertyDefinitionType::PropertyDefinition,
+                ctx.ast.vec(),
+                PropertyKey::PrivateIdentifier(ctx.ast.alloc_private_identifier(SPAN, name)),
+                NONE,
+                Some(call),
+                false,
+                true,
+                false,
+                false,
+                false,
+                false,
+                false,
+                None,
+            );
+        }
+    }
+
+    // Spans of the methods, getters and setters that `member_installation:
+    // "define-property"` moves out of the class body: public decorated ones
+    // with a literal key, along with the other half of an accessor pair.
+    // Members using `super` need their class as home object and stay.
+    fn members_defined_by_property(&self, class: &Class<'a>) -> Vec<Span> {
+        if self.options.member_installation != MemberInstallation::DefineProperty {
+            return Vec::new();
+        }
+        let methods: Vec<&MethodDefinition<'a>> = class
+            .body
+            .body
+            .iter()
+            .filter_map(|element| match element {
+                ClassElement::MethodDefinition(m)
+                    if m.kind != MethodDefinitionKind::Constructor
+                        && !m.computed
+                        && !m.key.is_private_identifier() =>
+                {
+                    Some(&**m)
+                }
+                _ => None,
+            })
+            .collect();
+        let uses_super = |m: &MethodDefinition<'a>| {
+            let mut finder = SuperFinder { found: false };
+            finder.visit_function(&m.value, ScopeFlags::empty());
+            finder.found
+        };
+        let mut spans = Vec::new();
+        for m in methods.iter().filter(|m| !m.decorators.is_empty()) {
+            let group: Vec<_> = methods
+                .iter()
+                .filter(|other| {
+                    other.r#static == m.r#static
+                        && other.key.static_name() == m.key.static_name()
+                        && (other.span == m.span || m.kind != MethodDefinitionKind::Method)
+                })
+                .collect();
+            if group.iter().all(|member| !uses_super(member)) {
+                spans.extend(group.iter().map(|member| member.span));
+            }
+        }
+        spans.sort_unstable_by_key(|span| span.start);
+        spans.dedup();
+        spans
+    }
+
+    // `Object.defineProperty(target, "m", { ...Object.getOwnPropertyDescriptor(
+    // { m() {} }, "m"), enumerable: false });` per member moved out of the
+    // class, or per accessor pair. The object literal gives methods the
+    // attributes and (non-constructible) function kind of class methods.
+    fn build_define_property_statements(
+        &self,
+        class: &Class<'a>,
+        target: &Expression<'a>,
+        ctx: &TraverseCtx<'a, TransformerState>,
+    ) -> Vec<Statement<'a>> {
+        let defined = self.members_defined_by_property(class);
+        let mut groups: Vec<(bool, Atom<'a>, ArenaVec<'a, ObjectPropertyKind<'a>>)> = Vec::new();
+        for element in &class.body.body {
+            let ClassElement::MethodDefinition(m) = element else {
+                continue;
+            };
+            if !defined.contains(&m.span) {
+                continue;
+            }
+            let name = Atom::from(ctx.ast.allocator.alloc_str(&m.key.static_name().unwrap()));
+            let kind = match m.kind {
+                MethodDefinitionKind::Get => PropertyKind::Get,
+                MethodDefinitionKind::Set => PropertyKind::Set,
+                _ => PropertyKind::Init,
+            };
+            let property = ctx.ast.object_property_kind_object_property(
+                SPAN,
+                kind,
+                m.key.clone_in(ctx.ast.allocator),
+                Expression::FunctionExpression(m.value.clone_in_with_semantic_ids(ctx.ast.allocator)),
+                kind == PropertyKind::Init,
+                false,
+                false,
+            );
+            match groups
+                .iter_mut()
+                .find(|(is_static, group, _)| *is_static == m.r#static && *group == name)
+            {
+                Some((_, _, properties)) => properties.push(property),
+                None => groups.push((m.r#static, name, ctx.ast.vec1(property))),
+            }
+        }
+        groups
+            .into_iter()
+            .map(|(is_static, name, properties)| {
+                let mut target = target.clone_in(ctx.ast.allocator);
+                if !is_static {
+                    target = Expression::from(ctx.ast.member_expression_static(
+                        SPAN,
+                        target,
+                        ctx.ast.identifier_name(SPAN, "prototype"),
+                        false,
+                    ));
+                }
+                let key = || ctx.ast.expression_string_literal(SPAN, name, None);
+                let descriptor = self.build_object_call(
+                    "getOwnPropertyDescriptor",
+                    [ctx.ast.expression_object(SPAN, properties), key()],
+                    ctx,
+                );
+                let attributes = ctx.ast.expression_object(
+                    SPAN,
+                    ctx.ast.vec_from_array([
+                        ctx.ast.object_property_kind_spread_property(SPAN, descriptor),
+                        ctx.ast.object_property_kind_object_property(
+                            SPAN,
+                            PropertyKind::Init,
+                            ctx.ast.property_key_static_identifier(SPAN, "enumerable"),
+                            ctx.ast.expression_boolean_literal(SPAN, false),
+                            false,
+                            false,
+                            false,
+                        ),
+                    ]),
+                );
+                let call = self.build_object_call("defineProperty", [target, key(), attributes], ctx);
+                ctx.ast.statement_expression(SPAN, call)
+            })
+            .collect()
+    }
+
+    // `Object.<method>(...arguments)`.
+    fn build_object_call<const N: usize>(
+        &self,
+        method: &'static str,
+        arguments: [Expression<'a>; N],
+        ctx: &TraverseCtx<'a, TransformerState>,
+    ) -> Expression<'a> {
+        let callee = ctx.ast.member_expression_static(
+            SPAN,
+            self.identifier(Atom::from("Object"), ctx),
+            ctx.ast.identifier_name(SPAN, method),
+            false,
+        );
+        let arguments = ctx.ast.vec_from_iter(arguments.into_iter().map(Argument::from));
+        ctx.ast
+            .expression_call(SPAN, Expression::from(callee), NONE, arguments, false)
+    }
+
+    // Whether every member decorator is an identifier or a chain of static
+    // member accesses on one, which evaluating before the class (and its
+    // `extends` clause) cannot reorder against other side effects.
+    fn has_inert_member_decorators(class: &Class<'a>) -> bool {
+        fn is_inert(expr: &Expression) -> bool {
+            match expr.without_parentheses() {
+                Expression::Identifier(_) => true,
+                Expression::StaticMemberExpression(member) => is_inert(&member.object),
+                _ => false,
+            }
+        }
+        class.body.body.iter().all(|element| {
+            let decorators = match element {
+                ClassElement::MethodDefinition(m) => &m.decorators,
+                ClassElement::PropertyDefinition(p) => &p.decorators,
+                ClassElement::AccessorProperty(a) => &a.decorators,
+                _ => return true,
+            };
+            decorators.iter().all(|decorator| is_inert(&decorator.expression))
+        })
+    }
+
+    // Replaces the descriptor array with a reference to a module-level
+    // constant, shared by every class whose descriptors print identically.
+    // Only done when `has_inert_member_decorators` and no decorated key is
+    // computed, since the class body assigns the temporaries those refer to.
+    fn hoist_descriptor_array(
+        &self,
+        descriptors: Expression<'a>,
+        class_span: Span,
+        ctx: &TraverseCtx<'a, TransformerState>,
+    ) -> Expression<'a> {
+        if matches!(&descriptors, Expression::ArrayExpression(array) if array.elements.is_empty())
+        {
+            return descriptors;
+        }
+        let mut codegen = Codegen::new();
+        codegen.print_expression(&descriptors);
+        let source = codegen.into_source_text();
+        let mut hoisted = self.hoisted_descriptors.borrow_mut();
+        let name = match hoisted.iter().find(|h| h.source == source) {
+            Some(existing) => existing.name.clone(),
+            None => {
+                let name = match hoisted.len() {
+                    0 => "_descriptors".to_string(),
+                    n => format!("_descriptors{}", n + 1),
+                };
+                hoisted.push(HoistedDescriptors {
+                    name: name.clone(),
+                    descriptors,
+                    class_span,
+                    source,
+                });
+                name
+            }
+        };
+        let name = ctx.ast.allocator.alloc_str(&name);
+        Expression::Identifier(ctx.ast.alloc(ctx.ast.identifier_reference(SPAN, name)))
+    }
+
+    fn build_member_descriptor_array_from_class(
+        &self,
+        class: &Class<'a>,
+        computed_members: &[Option<ComputedMember<'a>>],
+        member_inits: &[Option<MemberInit<'a>>],
+        ctx: &mut TraverseCtx<'a, TransformerState>,
+    ) -> Expression<'a> {
+        let mut descriptors = ctx.ast.vec();
+        for ((element, computed), member) in
+            class.body.body.iter().zip(computed_members).zip(member_inits)
+        {
+            let storage = member.as_ref().and_then(MemberInit::private_storage);
+            // Private methods, getters and setters hand their function to
+            // `_applyDecs`, which decorates it and returns it as `_call_*`.
+            let private_function = match element {
+                ClassElement::MethodDefinition(m)
+                    if member.as_ref().is_some_and(|m| m.call.is_some()) =>
+                {
+                    Some(Expression::FunctionExpression(
+                        m.value.clone_in_with_semantic_ids(ctx.ast.allocator),
+                    ))
+                }
+                _ => None,
+            };
+            let (decorators, kind, is_static, key) = match element {
+                ClassElement::MethodDefinition(m) if !m.decorators.is_empty() => {
+                    let kind = match m.kind {
+                        MethodDefinitionKind::Get => DecoratorKind::Getter,
+                        MethodDefinitionKind::Set => DecoratorKind::Setter,
+                        _ => DecoratorKind::Method,
+                    };
+                    (&m.decorators, kind, m.r#static, &m.key)
+                }
+                ClassElement::PropertyDefinition(p) if !p.decorators.is_empty() => {
+                    (&p.decorators, DecoratorKind::Field, p.r#static, &p.key)
+                }
+                ClassElement::AccessorProperty(a) if !a.decorators.is_empty() => {
+                    (&a.decorators, DecoratorKind::Accessor, a.r#static, &a.key)
+                }
+                _ => continue,
+            };
+            let mut descriptor = match computed {
+                Some(computed) => self.build_single_descriptor(
+                    computed
+                        .decorators
+                        .iter()
+                        .map(|&name| self.identifier(name, ctx))
+                        .collect(),
+                    kind,
+                    is_static,
+                    self.identifier(computed.key, ctx),
+                    false,
+                    None,
+                    ctx,
+                ),
+                None => {
+                    let key_str = self.extract_property_key_string(key, ctx);
+                    self.build_single_descriptor(
+                        decorators
+                            .iter()
+                            .map(|dec| self.clone_expression(&dec.expression, ctx))
+                            .collect(),
+                        kind,
+                        is_static,
+                        ctx.ast.expression_string_literal(SPAN, key_str, None),
+                        matches!(key, PropertyKey::PrivateIdentifier(_)),
+                        storage,
+                        ctx,
+                    )
+                }
+            };
+            if let (Some(function), Expression::ArrayExpression(array)) =
+                (private_function, &mut descriptor)
+            {
+                array.elements[3] = ArrayExpressionElement::from(function);
+            }
+            if let Some(hook) = self.descriptor_hook {
+                let metadata = DecoratorMetadata {
+                    name: computed
+                        .is_none()
+                        .then(|| self.extract_property_key_string(key, ctx).to_string()),
+                    kind,
+                    is_static,
+                    is_private: matches!(key, PropertyKey::PrivateIdentifier(_)),
+                    decorators: match computed {
+                        Some(computed) => {
+                            computed.decorators.iter().map(|name| name.to_string()).collect()
+                        }
+                        None => decorators
+                            .iter()
+                            .map(|dec| {
+                                let mut codegen = Codegen::new();
+                                codegen.print_expression(&dec.expression);
+                                codegen.into_source_text()
+                            })
+                            .collect(),
+                    },
+                };
+                if let (Some(extra), Expression::ArrayExpression(array)) =
+                    (hook(&metadata), &mut descriptor)
+                {
+                    for value in &extra.fields {
+                        let field = self.json_to_expression(value, ctx);
+                        array.elements.push(ArrayExpressionElement::from(field));
+                    }
+                }
+            }
+            descriptors.push(ArrayExpressionElement::from(descriptor));
+        }
+
+        ctx.ast.expression_array(SPAN, descriptors)
+    }
+
+    fn json_to_expression(
+        &self,
+        value: &serde_json::Value,
+        ctx: &TraverseCtx<'a, TransformerState>,
+    ) -> Expression<'a> {
+        use serde_json::Value;
+        match value {
+            Value::Null => ctx.ast.expression_null_literal(SPAN),
+            Value::Bool(b) => ctx.ast.expression_boolean_literal(SPAN, *b),
+            Value::Number(n) => ctx.ast.expression_numeric_literal(
+                SPAN,
+                n.as_f64().unwrap_or(f64::NAN),
+                None,
+                NumberBase::Decimal,
+            ),
+            Value::String(s) => {
+                let s = ctx.ast.allocator.alloc_str(s);
+                ctx.ast.expression_string_literal(SPAN, s, None)
+            }
+            Value::Array(items) => {
+                let elements = items
+                    .iter()
+                    .map(|item| ArrayExpressionElement::from(self.json_to_expression(item, ctx)));
+                ctx.ast.expression_array(SPAN, ctx.ast.vec_from_iter(elements))
+            }
+            Value::Object(entries) => {
+                let properties = entries.iter().map(|(key, item)| {
+                    let key = ctx.ast.allocator.alloc_str(key);
+                    ctx.ast.object_property_kind_object_property(
+                        SPAN,
+                        PropertyKind::Init,
+                        PropertyKey::from(ctx.ast.expression_string_literal(SPAN, key, None)),
+                        self.json_to_expression(item, ctx),
+                        false,
+                        false,
+                        false,
+                    )
+                });
+                ctx.ast.expression_object(SPAN, ctx.ast.vec_from_iter(properties))
+            }
+        }
+    }
+
+    /// Builds `[decorators, flags, key, isPrivate]` for one member.
+    ///
+    /// All decorators of a member share a single descriptor: `_applyDecs`
+    /// rejects two descriptors with the same name, and applies an array of
+    /// decorators in reverse source order as the spec requires.
+    ///
+    /// Private fields and accessors backed by `storage` instead end with
+    /// access closures, `[decorators, flags, key, (o) => o.#x, (o, v) => o.#x = v]`,
+    /// which `_applyDecs` uses to read and write the value.
+    #[allow(clippy::too_many_arguments)]
+    fn build_single_descriptor(
+        &self,
+        mut decorators: Vec<Expression<'a>>,
+        kind: DecoratorKind,
+        is_static: bool,
+        key_expr: Expression<'a>,
+        is_private: bool,
+        storage: Option<Atom<'a>>,
+        ctx: &mut TraverseCtx<'a, TransformerState>,
+    ) -> Expression<'a> {
+        let mut elements = ctx.ast.vec();
+        let decorator = if decorators.len() == 1 {
+            decorators.remove(0)
+        } else {
+            let list = decorators.into_iter().map(ArrayExpressionElement::from);
+            ctx.ast.expression_array(SPAN, ctx.ast.vec_from_iter(list))
+        };
+        elements.push(ArrayExpressionElement::from(decorator));
+        let flags = (kind as u8) | if is_static { 8 } else { 0 };
+        let flags_expr =
+            ctx.ast
+                .expression_numeric_literal(SPAN, flags as f64, None, NumberBase::Decimal);
+        elements.push(ArrayExpressionElement::from(flags_expr));
+        elements.push(ArrayExpressionElement::from(key_expr));
+        match storage {
+            Some(storage) if self.options.private_access == PrivateAccess::Weakmap => {
+                let getter = self.build_weak_storage_getter_closure(storage, ctx);
+                elements.push(ArrayExpressionElement::from(getter));
+                let setter = self.build_weak_storage_setter_closure(storage, ctx);
+                elements.push(ArrayExpressionElement::from(setter));
+            }
+            Some(storage) => {
+                let getter = self.build_private_getter_closure(storage, ctx);
+                elements.push(ArrayExpressionElement::from(getter));
+                let setter = self.build_private_setter_closure(storage, ctx);
+                elements.push(ArrayExpressionElement::from(setter));
+            }
+            None => {
+                let is_private_expr = ctx.ast.expression_boolean_literal(SPAN, is_private);
+                elements.push(ArrayExpressionElement::from(is_private_expr));
+            }
+        }
+        ctx.ast.expression_array(SPAN, elements)
+    }
+
+    // `(o) => o.#storage`
+    fn build_private_getter_closure(
+        &self,
+        storage: Atom<'a>,
+        ctx: &mut TraverseCtx<'a, TransformerState>,
+    ) -> Expression<'a> {
+        let read = self.private_field(self.identifier(Atom::from("o"), ctx), storage, ctx);
+        self.build_arrow_function(&["o"], Expression::from(read), ctx)
+    }
+
+    // `(o, v) => o.#storage = v`
+    fn build_private_setter_closure(
+        &self,
+        storage: Atom<'a>,
+        ctx: &mut TraverseCtx<'a, TransformerState>,
+    ) -> Expression<'a> {
+        let target = self.private_field(self.identifier(Atom::from("o"), ctx), storage, ctx);
+        let write = ctx.ast.expression_assignment(
+            SPAN,
+            AssignmentOperator::Assign,
+            AssignmentTarget::from(SimpleAssignmentTarget::from(target)),
+            self.identifier(Atom::from("v"), ctx),
+        );
+        self.build_arrow_function(&["o", "v"], write, ctx)
+    }
+
+    // The WeakMap temporary of a private accessor under
+    // `private_access: "weakmap"`.
+    fn weak_storage(&self, member: &MemberInit<'a>) -> Option<Atom<'a>> {
+        member
+            .private_storage()
+            .filter(|_| self.options.private_access == PrivateAccess::Weakmap)
+    }
+
+    // `(o) => _privateStorageGet(_x_storage, o)`
+    fn build_weak_storage_getter_closure(
+        &self,
+        storage: Atom<'a>,
+        ctx: &mut TraverseCtx<'a, TransformerState>,
+    ) -> Expression<'a> {
+        let read = self.build_helper_call(
+            "_privateStorageGet",
+            [self.identifier(storage, ctx), self.identifier(Atom::from("o"), ctx)],
+            ctx,
+        );
+        self.build_arrow_function(&["o"], read, ctx)
+    }
+
+    // `(o, v) => _privateStorageSet(_x_storage, o, v)`
+    fn build_weak_storage_setter_closure(
+        &self,
+        storage: Atom<'a>,
+        ctx: &mut TraverseCtx<'a, TransformerState>,
+    ) -> Expression<'a> {
+        let write = self.build_helper_call(
+            "_privateStorageSet",
+            [
+                self.identifier(storage, ctx),
+                self.identifier(Atom::from("o"), ctx),
+                self.identifier(Atom::from("v"), ctx),
+            ],
+            ctx,
+        );
+        self.build_arrow_function(&["o", "v"], write, ctx)
+    }
+
+    // `_x_storage = new WeakMap();`
+    fn build_weak_storage_creation(
+        &self,
+        storage: Atom<'a>,
+        ctx: &TraverseCtx<'a, TransformerState>,
+    ) -> Statement<'a> {
+        let weak_map = ctx.ast.expression_new(
+            SPAN,
+            self.identifier(Atom::from("WeakMap"), ctx),
+            NONE,
+            ctx.ast.vec(),
+        );
+        let assignment = ctx.ast.expression_assignment(
+            SPAN,
+            AssignmentOperator::Assign,
+            AssignmentTarget::from(ctx.ast.simple_assignment_target_assignment_target_identifier(
+                SPAN, storage,
+            )),
+            weak_map,
+        );
+        ctx.ast.statement_expression(SPAN, assignment)
+    }
+
+    // `_x_storage.set(this, value);`, giving an instance (or the class) its
+    // entry in a private accessor's WeakMap.
+    fn build_weak_storage_init(
+        &self,
+        storage: Atom<'a>,
+        value: Expression<'a>,
+        ctx: &TraverseCtx<'a, TransformerState>,
+    ) -> Statement<'a> {
+        let set = ctx.ast.member_expression_static(
+            SPAN,
+            self.identifier(storage, ctx),
+            ctx.ast.identifier_name(SPAN, "set"),
+            false,
+        );
+        let mut arguments = ctx.ast.vec();
+        arguments.push(Argument::from(ctx.ast.expression_this(SPAN)));
+        arguments.push(Argument::from(value));
+        let call = ctx
+            .ast
+            .expression_call(SPAN, Expression::from(set), NONE, arguments, false);
+        ctx.ast.statement_expression(SPAN, call)
+    }
+
+    fn build_helper_call<const N: usize>(
+        &self,
+        helper: &'static str,
+        arguments: [Expression<'a>; N],
+        ctx: &TraverseCtx<'a, TransformerState>,
+    ) -> Expression<'a> {
+        let arguments = ctx.ast.vec_from_iter(arguments.into_iter().map(Argument::from));
+        ctx.ast.expression_call(
+            SPAN,
+            self.identifier(self.helper(helper), ctx),
+            NONE,
+            arguments,
+            false,
+        )
+    }
+
+    // `(o) => #name in o`, letting `_applyDecs` reject objects that are not
+    // instances when decorators use `context.access`.
+    fn build_private_brand_check(
+        &self,
+        name: Atom<'a>,
+        ctx: &mut TraverseCtx<'a, TransformerState>,
+    ) -> Expression<'a> {
+        let check = ctx.ast.expression_private_in(
+            SPAN,
+            ctx.ast.private_identifier(SPAN, name),
+            self.identifier(Atom::from("o"), ctx),
+        );
+        self.build_arrow_function(&["o"], check, ctx)
+    }
+
+    fn private_field(
+        &self,
+        object: Expression<'a>,
+        name: Atom<'a>,
+        ctx: &TraverseCtx<'a, TransformerState>,
+    ) -> MemberExpression<'a> {
+        let field = ctx.ast.private_identifier(SPAN, name);
+        ctx.ast
+            .member_expression_private_field_expression(SPAN, object, field, false)
+    }
+
+    fn build_arrow_function(
+        &self,
+        params: &[&'a str],
+        body: Expression<'a>,
+        ctx: &mut TraverseCtx<'a, TransformerState>,
+    ) -> Expression<'a> {
+        let params = self.build_formal_parameters(params, ctx);
+        let body = ctx.ast.alloc_function_body(
+            SPAN,
+            ctx.ast.vec(),
+            ctx.ast.vec1(ctx.ast.statement_expression(SPAN, body)),
+        );
+        let scope_id =
+            ctx.create_child_scope_of_current(ScopeFlags::Function | ScopeFlags::Arrow);
+        ctx.ast.expression_arrow_function_with_scope_id_and_pure_and_pife(
+            SPAN, true, false, NONE, params, NONE, body, scope_id, false, false,
+        )
+    }
+
+    fn build_formal_parameters(
+        &self,
+        names: &[&'a str],
+        ctx: &TraverseCtx<'a, TransformerState>,
+    ) -> oxc_allocator::Box<'a, FormalParameters<'a>> {
+        let items = names.iter().map(|&name| {
+            let pattern = ctx.ast.binding_pattern(
+                ctx.ast.binding_pattern_kind_binding_identifier(SPAN, name),
+                NONE,
+                false,
+            );
+            ctx.ast
+                .formal_parameter(SPAN, ctx.ast.vec(), pattern, None, false, false)
+        });
+        ctx.ast.alloc_formal_parameters(
+            SPAN,
+            FormalParameterKind::FormalParameter,
+            ctx.ast.vec_from_iter(items),
+            NONE,
+        )
+    }
+
+    // Allocates the temporaries for every decorated member, aligned with the
+    // class body. Private accessors also get a private backing field, named
+    // after the accessor and distinct from every private name in the class.
+    fn plan_member_inits(
+        &self,
+        class: &Class<'a>,
+        ctx: &mut TraverseCtx<'a, TransformerState>,
+    ) -> Vec<Option<MemberInit<'a>>> {
+        let mut private_names: Vec<Atom<'a>> = class
+            .body
+            .body
+            .iter()
+            .filter_map(|element| match element.property_key() {
+                Some(PropertyKey::PrivateIdentifier(id)) => Some(id.name),
+                _ => None,
+            })
+            .collect();
+        let mut member_inits = Vec::with_capacity(class.body.body.len());
+        for element in &class.body.body {
+            let (kind, is_static, key) = match element {
+                ClassElement::MethodDefinition(m) if !m.decorators.is_empty() => {
+                    let kind = match m.kind {
+                        MethodDefinitionKind::Get => DecoratorKind::Getter,
+                        MethodDefinitionKind::Set => DecoratorKind::Setter,
+                        _ => DecoratorKind::Method,
+                    };
+                    (kind, m.r#static, &m.key)
+                }
+                ClassElement::PropertyDefinition(p) if !p.decorators.is_empty() => {
+                    (DecoratorKind::Field, p.r#static, &p.key)
+                }
+                ClassElement::AccessorProperty(a) if !a.decorators.is_empty() => {
+                    (DecoratorKind::Accessor, a.r#static, &a.key)
+                }
+                _ => {
+                    member_inits.push(None);
+                    continue;
+                }
+            };
+            let private_name = match key {
+                PropertyKey::PrivateIdentifier(id) => Some(id.name),
+                _ => None,
+            };
+            let mut member = MemberInit {
+                kind,
+                is_static,
+                private_name,
+                storage: None,
+                init: None,
+                get: None,
+                set: None,
+                call: None,
+            };
+            if matches!(kind, DecoratorKind::Field | DecoratorKind::Accessor) {
+                let hint = key_name_hint(key);
+                member.init = Some(ctx.generate_uid_name(&format!("init_{hint}")));
+                if kind == DecoratorKind::Accessor
+                    && private_name.is_some()
+                    && self.options.private_access == PrivateAccess::Weakmap
+                {
+                    member.get = Some(ctx.generate_uid_name(&format!("get_{hint}")));
+                    member.set = Some(ctx.generate_uid_name(&format!("set_{hint}")));
+                    member.storage = Some(ctx.generate_uid_name(&format!("{hint}_storage")));
+                    *self.uses_private_storage.borrow_mut() = true;
+                } else if kind == DecoratorKind::Accessor {
+                    if private_name.is_some() {
+                        member.get = Some(ctx.generate_uid_name(&format!("get_{hint}")));
+                        member.set = Some(ctx.generate_uid_name(&format!("set_{hint}")));
+                    }
+                    // Numeric keys give hints like `1`, which cannot start a
+                    // private name.
+                    let base = match hint.starts_with(|c: char| c.is_ascii_digit()) {
+                        true => format!("_{hint}_accessor_storage"),
+                        false => format!("{hint}_accessor_storage"),
+                    };
+                    let storage = Self::unique_private_name(&base, &private_names, ctx);
+                    private_names.push(storage);
+                    member.storage = Some(storage);
+                } else {
+                    member.storage = private_name;
+                }
+                let temps = [member.init, member.get, member.set, self.weak_storage(&member)];
+                self.record_class_temps(class.span, temps.into_iter().flatten());
+            } else if private_name.is_some() {
+                let call = ctx.generate_uid_name(&format!("call_{}", key_name_hint(key)));
+                member.call = Some(call);
+                self.record_class_temps(class.span, [call]);
+            }
+            member_inits.push(Some(member));
+        }
+        member_inits
+    }
+
+    fn unique_private_name(
+        base: &str,
+        taken: &[Atom<'a>],
+        ctx: &TraverseCtx<'a, TransformerState>,
+    ) -> Atom<'a> {
+        let mut name = base.to_string();
+        let mut n = 1;
+        while taken.iter().any(|t| t.as_str() == name) {
+            n += 1;
+            name = format!("{base}{n}");
+        }
+        Atom::from(ctx.ast.allocator.alloc_str(&name))
+    }
+
+    // The destructuring targets for `_applyDecs(...).e`, which lists, for
+    // static public, instance public, static private and instance private
+    // members in turn: `[init, extraInit]` per field or accessor (with the
+    // accessor's `get, set` before `extraInit` when private) and the
+    // decorated function per private method, getter or setter; followed by
+    // the proto and static initializers. Extra initializers are also run by
+    // the proto and static initializers, so they are skipped.
+    fn apply_decs_targets(&self, member_inits: &[Option<MemberInit<'a>>]) -> Vec<Option<Atom<'a>>> {
+        let mut targets = Vec::new();
+        for (is_static, is_private) in [(true, false), (false, false), (true, true), (false, true)]
+        {
+            for member in member_inits
+                .iter()
+                .flatten()
+                .filter(|m| m.is_static == is_static && m.is_private() == is_private)
+            {
+                match member.kind {
+                    DecoratorKind::Field | DecoratorKind::Accessor => {
+                        targets.push(member.init);
+                        if is_private && member.kind == DecoratorKind::Accessor {
+                            targets.push(member.get);
+                            targets.push(member.set);
+                        }
+                        targets.push(None);
+                    }
+                    _ if is_private => targets.push(member.call),
+                    _ => {}
+                }
+            }
+        }
+        let has_instance = member_inits.iter().flatten().any(|m| !m.is_static);
+        let has_static = member_inits.iter().flatten().any(|m| m.is_static);
+        let (init_proto, init_class) = *self.init_names.borrow();
+        targets.push(has_instance.then_some(init_proto));
+        targets.push(has_static.then_some(init_class));
+        while targets.last() == Some(&None) {
+            targets.pop();
+        }
+        targets
+    }
+
+    // Routes field and accessor initializers through their `_init_*`
+    // temporaries and replaces each decorated accessor with its backing
+    // field and a getter/setter pair: private ones call `_get_*`/`_set_*`,
+    // public ones read and write the backing field, which `_applyDecs`
+    // then finds on the prototype. Private methods, getters and setters,
+    // whose functions moved into their descriptors, call `_call_*` instead.
+    // Accessors with WeakMap storage are initialized by a static block in
+    // their place, or for instances by the returned constructor statements.
+    fn apply_member_inits(
+        &self,
+        class: &mut Class<'a>,
+        computed_members: &[Option<ComputedMember<'a>>],
+        member_inits: Vec<Option<MemberInit<'a>>>,
+        ctx: &mut TraverseCtx<'a, TransformerState>,
+    ) -> ArenaVec<'a, Statement<'a>> {
+        let mut instance_inits = ctx.ast.vec();
+        let elements = class.body.body.take_in(ctx.ast);
+        let mut body = ctx.ast.vec_with_capacity(elements.len());
+        for ((mut element, member), computed) in
+            elements.into_iter().zip(member_inits).zip(computed_members)
+        {
+            if let (
+                Some(MemberInit {
+                    kind,
+                    is_static,
+                    call: Some(call),
+                    ..
+                }),
+                ClassElement::MethodDefinition(m),
+            ) = (&member, &mut element)
+            {
+                let key = m.key.take_in(ctx.ast);
+                body.push(self.build_private_method_stub(*kind, key, *call, *is_static, ctx));
+                continue;
+            }
+            let weak_storage = member.as_ref().and_then(|m| self.weak_storage(m));
+            let Some(MemberInit {
+                is_static,
+                storage,
+                init: Some(init),
+                get,
+                set,
+                ..
+            }) = member
+            else {
+                body.push(element);
+                continue;
+            };
+            match &mut element {
+                ClassElement::PropertyDefinition(p) => {
+                    let value = p.value.take();
+                    p.value = Some(self.build_init_call(init, is_static, value, ctx));
+                }
+                ClassElement::AccessorProperty(a) => {
+                    let value = a.value.take();
+                    let value = self.build_init_call(init, is_static, value, ctx);
+                    if let Some(storage) = storage {
+                        let a = &mut **a;
+                        let key = a.key.take_in(ctx.ast);
+                        // Kept on the getter and setter, so that TypeScript
+                        // output still types the accessor.
+                        let type_annotation = a
+                            .type_annotation
+                            .as_ref()
+                            .map(|t| t.clone_in(ctx.ast.allocator));
+                        if let Some(weak_storage) = weak_storage {
+                            let init = self.build_weak_storage_init(weak_storage, value, ctx);
+                            if is_static {
+                                let scope_id = ctx
+                                    .create_child_scope_of_current(ScopeFlags::ClassStaticBlock);
+                                body.push(ctx.ast.class_element_static_block_with_scope_id(
+                                    SPAN,
+                                    ctx.ast.vec1(init),
+                                    scope_id,
+                                ));
+                            } else {
+                                instance_inits.push(init);
+                            }
+                        } else {
+                            body.push(ctx.ast.class_element_property_definition(
+                                a.span,
+                                PropertyDefinitionType::PropertyDefinition,
+                                ctx.ast.vec(),
+                                PropertyKey::PrivateIdentifier(
+                                    ctx.ast.alloc_private_identifier(SPAN, storage),
+                                ),
+                                a.type_annotation.take(),
+                                Some(value),
+                                false,
+                                is_static,
+                                false,
+                                false,
+                                false,
+                                a.definite,
+                                false,
+                                None,
+                            ));
+                        }
+                        if let (Some(get), Some(set)) = (get, set) {
+                            let getter = self.build_private_accessor_method(
+                                MethodDefinitionKind::Get,
+                                key.clone_in(ctx.ast.allocator),
+                                get,
+                                is_static,
+                                !is_static,
+                                ctx,
+                            );
+                            let setter = self.build_private_accessor_method(
+                                MethodDefinitionKind::Set,
+                                key,
+                                set,
+                                is_static,
+                                !is_static,
+                                ctx,
+                            );
+                            let getter = Self::with_accessor_type(getter, type_annotation.as_ref(), ctx);
+                            body.push(getter);
+                            let setter = Self::with_accessor_type(setter, type_annotation.as_ref(), ctx);
+                            body.push(setter);
+                            continue;
+                        }
+                        // A hoisted computed key must only be evaluated by
+                        // the getter; the setter reuses its temporary.
+                        let (setter_key, computed_key) = match computed {
+                            Some(computed) => (
+                                PropertyKey::from(self.identifier(computed.key, ctx)),
+                                true,
+                            ),
+                            None => (key.clone_in(ctx.ast.allocator), a.computed),
+                        };
+                        let getter = self.build_accessor_storage_method(
+                            MethodDefinitionKind::Get,
+                            key,
+                            computed_key,
+                            storage,
+                            is_static,
+                            ctx,
+                        );
+                        let setter = self.build_accessor_storage_method(
+                            MethodDefinitionKind::Set,
+                            setter_key,
+                            computed_key,
+                            storage,
+                            is_static,
+                            ctx,
+                        );
+                        body.push(Self::with_accessor_type(getter, type_annotation.as_ref(), ctx));
+                        body.push(Self::with_accessor_type(setter, type_annotation.as_ref(), ctx));
+                        continue;
+                    }
+                    a.value = Some(value);
+                }
+                _ => {}
+            }
+            body.push(element);
+        }
+        class.body.body = body;
+        instance_inits
+    }
+
+    // Types the getter (`get x(): T`) or setter (`set x(v: T)`) desugared
+    // from `accessor x: T`.
+    fn with_accessor_type(
+        mut element: ClassElement<'a>,
+        type_annotation: Option<&oxc_allocator::Box<'a, TSTypeAnnotation<'a>>>,
+        ctx: &TraverseCtx<'a, TransformerState>,
+    ) -> ClassElement<'a> {
+        let (ClassElement::MethodDefinition(method), Some(type_annotation)) =
+            (&mut element, type_annotation)
+        else {
+            return element;
+        };
+        let type_annotation = type_annotation.clone_in(ctx.ast.allocator);
+        match method.value.params.items.first_mut() {
+            Some(param) => param.pattern.type_annotation = Some(type_annotation),
+            None => method.value.return_type = Some(type_annotation),
+        }
+        element
+    }
+
+    // `_init_x(this, value)`, or `_init_x(value)` for static members, which
+    // `_applyDecs` applies to the class itself.
+    fn build_init_call(
+        &self,
+        init: Atom<'a>,
+        is_static: bool,
+        value: Option<Expression<'a>>,
+        ctx: &TraverseCtx<'a, TransformerState>,
+    ) -> Expression<'a> {
+        let mut arguments = ctx.ast.vec();
+        if !is_static {
+            arguments.push(Argument::from(ctx.ast.expression_this(SPAN)));
+        }
+        if let Some(value) = value {
+            arguments.push(Argument::from(value));
+        }
+        ctx.ast
+            .expression_call(SPAN, self.identifier(init, ctx), NONE, arguments, false)
+    }
+
+    // `get x() { return this.#x_accessor_storage; }` or
+    // `set x(v) { this.#x_accessor_storage = v; }`.
+    fn build_accessor_storage_method(
+        &self,
+        kind: MethodDefinitionKind,
+        key: PropertyKey<'a>,
+        computed: bool,
+        storage: Atom<'a>,
+        is_static: bool,
+        ctx: &mut TraverseCtx<'a, TransformerState>,
+    ) -> ClassElement<'a> {
+        let is_setter = kind == MethodDefinitionKind::Set;
+        let field = self.private_field(ctx.ast.expression_this(SPAN), storage, ctx);
+        let statement = if is_setter {
+            let assignment = ctx.ast.expression_assignment(
+                SPAN,
+                AssignmentOperator::Assign,
+                AssignmentTarget::from(field),
+                self.identifier(Atom::from("v"), ctx),
+            );
+            ctx.ast.statement_expression(SPAN, assignment)
+        } else {
+            ctx.ast.statement_return(SPAN, Some(Expression::from(field)))
+        };
+        let params = self.build_formal_parameters(if is_setter { &["v"] } else { &[] }, ctx);
+        let body = ctx
+            .ast
+            .alloc_function_body(SPAN, ctx.ast.vec(), ctx.ast.vec1(statement));
+        let scope_id = ctx.create_child_scope_of_current(ScopeFlags::Function);
+        let function = ctx.ast.alloc_function_with_scope_id(
+            SPAN,
+            FunctionType::FunctionExpression,
+            None,
+            false,
+            false,
+            false,
+            NONE,
+            NONE,
+            params,
+            NONE,
+            Some(body),
+            scope_id,
+        );
+        ctx.ast.class_element_method_definition(
+            SPAN,
+            MethodDefinitionType::MethodDefinition,
+            ctx.ast.vec(),
+            key,
+            function,
+            kind,
+            computed,
+            is_static,
+            false,
+            false,
+            None,
+        )
+    }
+
+    // `get #m() { return _call_m; }` for a private method, whose decorated
+    // function `_applyDecs` returns as is; getters and setters call theirs
+    // with the receiver, static ones included.
+    fn build_private_method_stub(
+        &self,
+        kind: DecoratorKind,
+        key: PropertyKey<'a>,
+        call: Atom<'a>,
+        is_static: bool,
+        ctx: &mut TraverseCtx<'a, TransformerState>,
+    ) -> ClassElement<'a> {
+        let method_kind = match kind {
+            DecoratorKind::Setter => MethodDefinitionKind::Set,
+            _ => MethodDefinitionKind::Get,
+        };
+        if kind != DecoratorKind::Method {
+            return self
+                .build_private_accessor_method(method_kind, key, call, is_static, true, ctx);
+        }
+        let statement = ctx
+            .ast
+            .statement_return(SPAN, Some(self.identifier(call, ctx)));
+        let params = self.build_formal_parameters(&[], ctx);
+        let body = ctx
+            .ast
+            .alloc_function_body(SPAN, ctx.ast.vec(), ctx.ast.vec1(statement));
+        let scope_id = ctx.create_child_scope_of_current(ScopeFlags::Function);
+        let function = ctx.ast.alloc_function_with_scope_id(
+            SPAN,
+            FunctionType::FunctionExpression,
+            None,
+            false,
+            false,
+            false,
+            NONE,
+            NONE,
+            params,
+            NONE,
+            Some(body),
+            scope_id,
+        );
+        ctx.ast.class_element_method_definition(
+            SPAN,
+            MethodDefinitionType::MethodDefinition,
+            ctx.ast.vec(),
+            key,
+            function,
+            method_kind,
+            false,
+            is_static,
+            false,
+            false,
+            None,
+        )
+    }
+
+    // `get #x() { return _get_x(this); }` or `set #x(v) { _set_x(this, v); }`;
+    // static accessors leave out `this`, which `_applyDecs` binds itself.
+    fn build_private_accessor_method(
+        &self,
+        kind: MethodDefinitionKind,
+        key: PropertyKey<'a>,
+        accessor: Atom<'a>,
+        is_static: bool,
+        pass_this: bool,
+        ctx: &mut TraverseCtx<'a, TransformerState>,
+    ) -> ClassElement<'a> {
+        let is_setter = kind == MethodDefinitionKind::Set;
+        let mut arguments = ctx.ast.vec();
+        if pass_this {
+            arguments.push(Argument::from(ctx.ast.expression_this(SPAN)));
+        }
+        if is_setter {
+            arguments.push(Argument::from(self.identifier(Atom::from("v"), ctx)));
+        }
+        let call = ctx
+            .ast
+            .expression_call(SPAN, self.identifier(accessor, ctx), NONE, arguments, false);
+        let statement = if is_setter {
+            ctx.ast.statement_expression(SPAN, call)
+        } else {
+            ctx.ast.statement_return(SPAN, Some(call))
+        };
+        let params = self.build_formal_parameters(if is_setter { &["v"] } else { &[] }, ctx);
+        let body = ctx
+            .ast
+            .alloc_function_body(SPAN, ctx.ast.vec(), ctx.ast.vec1(statement));
+        let scope_id = ctx.create_child_scope_of_current(ScopeFlags::Function);
+        let function = ctx.ast.alloc_function_with_scope_id(
+            SPAN,
+            FunctionType::FunctionExpression,
+            None,
+            false,
+            false,
+            false,
+            NONE,
+            NONE,
+            params,
+            NONE,
+            Some(body),
+            scope_id,
+        );
+        ctx.ast.class_element_method_definition(
+            SPAN,
+            MethodDefinitionType::MethodDefinition,
+            ctx.ast.vec(),
+            key,
+            function,
+            kind,
+            false,
+            is_static,
+            false,
+            false,
+            None,
+        )
+    }
+
+    // Computed keys are evaluated while the class is being defined, before
+    // its binding is initialized, so a decorated key that names the class
+    // (`@dec static [C.KEY]`) could only throw; report it instead.
+    fn check_self_referencing_keys(
         &self,
-        key: &PropertyKey<'a>,
+        class: &Class<'a>,
         ctx: &TraverseCtx<'a, TransformerState>,
-    ) -> &'a str {
-        match key {
-            PropertyKey::StaticIdentifier(id) => id.name.as_str(),
-            PropertyKey::PrivateIdentifier(id) => id.name.as_str(),
-            PropertyKey::StringLiteral(lit) => lit.value.as_str(),
-            PropertyKey::NumericLiteral(lit) => {
-                let s = lit.value.to_string();
-                ctx.ast.allocator.alloc_str(&s)
+    ) -> Option<Located> {
+        let id = class.id.as_ref()?;
+        let symbol = id.symbol_id.get()?;
+        class.body.body.iter().find_map(|element| {
+            let (decorators, key) = match element {
+                ClassElement::MethodDefinition(m) => (&m.decorators, &m.key),
+                ClassElement::PropertyDefinition(p) => (&p.decorators, &p.key),
+                ClassElement::AccessorProperty(a) => (&a.decorators, &a.key),
+                _ => return None,
+            };
+            if decorators.is_empty() || !is_computed_key(key) {
+                return None;
             }
-            _ => "computed",
-        }
+            let mut finder = SymbolReferenceFinder {
+                scoping: ctx.scoping(),
+                symbol,
+                found: false,
+            };
+            finder.visit_property_key(key);
+            finder.found.then(|| {
+                let message = format!(
+                    "Decorated computed key at offset {} references class \"{}\", which is not initialized while its keys are evaluated",
+                    key.span().start,
+                    id.name
+                );
+                (message, key.span().start)
+            })
+        })
     }
 
-    fn transform_class_with_decorators(
-        &mut self,
+    // Decorators and the key of a decorated computed member are evaluated
+    // once, in source order, inside the computed key itself:
+    // `[(_dec = dec(KEY), _computedKey = _toPropertyKey(KEY))]() {}`.
+    // The descriptor then refers to the temporaries.
+    //
+    // With `spec_compliant`, the decorators of other members written before
+    // a computed key are evaluated there too, ahead of it, so that every
+    // decorator is evaluated in source order with the keys; the members
+    // refer to their temporaries. Decorators after the last computed key are
+    // evaluated in the static block, which still follows every key.
+    fn hoist_computed_keys(
+        &self,
         class: &mut Class<'a>,
         ctx: &mut TraverseCtx<'a, TransformerState>,
-    ) -> bool {
-        if !self.has_decorators(class) {
-            return false;
-        }
-
-        *self.in_decorated_class.borrow_mut() = true;
-        *self.helpers_injected.borrow_mut() = true;
-        let class_decorators = self.collect_class_decorators(class, ctx);
-
-        if !class_decorators.is_empty() {
-            let class_name = class
-                .id
-                .as_ref()
-                .map(|id| id.name.to_string())
-                .unwrap_or_else(|| "default".to_string());
-            self.classes_with_class_decorators
-                .borrow_mut()
-                .push(ClassDecoratorInfo {
-                    class_name,
-                    decorators: class_decorators,
-                });
-        }
-
-        let static_block = self.create_decorator_static_block_from_class(class, ctx);
-        class.body.body.push(static_block);
-
-        let needs_instance_init = class.body.body.iter().any(|element| match element {
-            ClassElement::MethodDefinition(m) if !m.decorators.is_empty() => !m.r#static,
-            ClassElement::PropertyDefinition(p) if !p.decorators.is_empty() => !p.r#static,
-            ClassElement::AccessorProperty(a) if !a.decorators.is_empty() => !a.r#static,
-            _ => false,
-        });
+    ) -> Vec<Option<ComputedMember<'a>>> {
+        let last_computed_key = match self.options.spec_compliant {
+            true => class.body.body.iter().rposition(|element| {
+                !matches!(element, ClassElement::StaticBlock(_))
+                    && element.property_key().is_some_and(is_computed_key)
+            }),
+            false => None,
+        };
+        // Decorator temporaries assigned ahead of the next computed key.
+        let mut pending = ctx.ast.vec();
+        let mut computed_members = Vec::with_capacity(class.body.body.len());
+        for (index, element) in class.body.body.iter_mut().enumerate() {
+            let (decorators, key) = match element {
+                ClassElement::MethodDefinition(m) => {
+                    let m = &mut **m;
+                    (&mut m.decorators, &mut m.key)
+                }
+                ClassElement::PropertyDefinition(p) => {
+                    let p = &mut **p;
+                    (&mut p.decorators, &mut p.key)
+                }
+                ClassElement::AccessorProperty(a) => {
+                    let a = &mut **a;
+                    (&mut a.decorators, &mut a.key)
+                }
+                _ => {
+                    computed_members.push(None);
+                    continue;
+                }
+            };
+            let before_last_key = last_computed_key.is_some_and(|last| index < last);
+            if !is_computed_key(key) {
+                if before_last_key {
+                    for decorator in decorators.iter_mut() {
+                        let name = ctx.generate_uid_name("dec");
+                        let value = decorator.expression.take_in(ctx.ast);
+                        pending.push(self.build_temp_assignment(name, value, ctx));
+                        decorator.expression = self.identifier(name, ctx);
+                        self.record_class_temps(class.span, [name]);
+                    }
+                }
+                computed_members.push(None);
+                continue;
+            }
+            if decorators.is_empty() {
+                if !pending.is_empty() {
+                    let mut sequence = std::mem::replace(&mut pending, ctx.ast.vec());
+                    sequence.push(key.to_expression_mut().take_in(ctx.ast));
+                    *key = PropertyKey::from(ctx.ast.expression_sequence(SPAN, sequence));
+                }
+                computed_members.push(None);
+                continue;
+            }
+            let mut sequence = std::mem::replace(&mut pending, ctx.ast.vec());
+            let mut decorator_names = Vec::with_capacity(decorators.len());
+            for decorator in decorators.iter_mut() {
+                let name = ctx.generate_uid_name("dec");
+                let value = decorator.expression.take_in(ctx.ast);
+                sequence.push(self.build_temp_assignment(name, value, ctx));
+                decorator_names.push(name);
+            }
+            let key_name = ctx.generate_uid_name("computedKey");
+            let key_value = key.to_expression_mut().take_in(ctx.ast);
+            *self.uses_to_property_key.borrow_mut() = true;
+            let to_property_key = ctx.ast.expression_call(
+                SPAN,
+                self.identifier(self.helper("_toPropertyKey"), ctx),
+                NONE,
+                ctx.ast.vec1(Argument::from(key_value)),
+                false,
+            );
+            sequence.push(self.build_temp_assignment(key_name, to_property_key, ctx));
+            *key = PropertyKey::from(ctx.ast.expression_sequence(SPAN, sequence));
 
-        if needs_instance_init {
-            self.ensure_constructor_with_init(class, ctx);
+            self.record_class_temps(class.span, decorator_names.iter().copied());
+            self.record_class_temps(class.span, [key_name]);
+            computed_members.push(Some(ComputedMember {
+                decorators: decorator_names,
+                key: key_name,
+            }));
         }
+        computed_members
+    }
 
-        class.decorators.clear();
-        for element in &mut class.body.body {
-            match element {
-                ClassElement::MethodDefinition(m) => m.decorators.clear(),
-                ClassElement::PropertyDefinition(p) => p.decorators.clear(),
-                ClassElement::AccessorProperty(a) => a.decorators.clear(),
-                _ => {}
-            }
+    // Records the temporaries (including `_initProto`/`_initClass`) to
+    // declare before the statement containing the class.
+    fn record_class_temps(&self, class_span: Span, names: impl IntoIterator<Item = Atom<'a>>) {
+        let mut temps = self.class_temps.borrow_mut();
+        match temps.iter_mut().find(|t| t.class_span == class_span) {
+            Some(existing) => existing.names.extend(names),
+            None => temps.push(ClassTemps {
+                class_span,
+                names: names.into_iter().collect(),
+            }),
         }
-
-        true
     }
 
-    fn create_decorator_static_block_from_class(
+    fn build_temp_assignment(
         &self,
-        class: &Class<'a>,
-        ctx: &mut TraverseCtx<'a, TransformerState>,
-    ) -> ClassElement<'a> {
-        let mut statements = ctx.ast.vec();
-        let member_desc_array = self.build_member_descriptor_array_from_class(class, ctx);
-        let empty_class_dec_array = ctx.ast.expression_array(SPAN, ctx.ast.vec());
-        let assignment_stmt = self.build_apply_decs_assignment(
-            &["_initProto", "_initClass"],
-            member_desc_array,
-            empty_class_dec_array,
-            "e",
-            ctx,
-        );
-        statements.push(assignment_stmt);
-        let init_class_call = self.build_init_class_if_statement(ctx);
-        statements.push(init_class_call);
-        let scope_id = ctx.create_child_scope_of_current(ScopeFlags::ClassStaticBlock);
-        ctx.ast
-            .class_element_static_block_with_scope_id(SPAN, statements, scope_id)
+        name: Atom<'a>,
+        value: Expression<'a>,
+        ctx: &TraverseCtx<'a, TransformerState>,
+    ) -> Expression<'a> {
+        let target = ctx
+            .ast
+            .simple_assignment_target_assignment_target_identifier(SPAN, name);
+        ctx.ast.expression_assignment(
+            SPAN,
+            AssignmentOperator::Assign,
+            AssignmentTarget::from(target),
+            value,
+        )
     }
 
-    fn build_member_descriptor_array_from_class(
+    fn identifier(&self, name: Atom<'a>, ctx: &TraverseCtx<'a, TransformerState>) -> Expression<'a> {
+        Expression::Identifier(ctx.ast.alloc(ctx.ast.identifier_reference(SPAN, name)))
+    }
+
+    // `const name = init;`
+    fn build_const_declaration(
         &self,
-        class: &Class<'a>,
+        name: Atom<'a>,
+        init: Expression<'a>,
         ctx: &TraverseCtx<'a, TransformerState>,
-    ) -> Expression<'a> {
-        let mut descriptors = ctx.ast.vec();
-        for element in &class.body.body {
-            match element {
-                ClassElement::MethodDefinition(m) if !m.decorators.is_empty() => {
-                    let kind = match m.kind {
-                        MethodDefinitionKind::Get => DecoratorKind::Getter,
-                        MethodDefinitionKind::Set => DecoratorKind::Setter,
-                        _ => DecoratorKind::Method,
-                    };
-                    let is_static = m.r#static;
-                    let is_private = matches!(&m.key, PropertyKey::PrivateIdentifier(_));
-                    for decorator in m.decorators.iter() {
-                        let descriptor = self.build_single_descriptor(
-                            &decorator.expression,
-                            kind,
-                            is_static,
-                            is_private,
-                            &m.key,
-                            ctx,
-                        );
-                        descriptors.push(ArrayExpressionElement::from(descriptor));
-                    }
-                }
-                ClassElement::PropertyDefinition(p) if !p.decorators.is_empty() => {
-                    let kind = DecoratorKind::Field;
-                    let is_static = p.r#static;
-                    let is_private = matches!(&p.key, PropertyKey::PrivateIdentifier(_));
-
-                    for decorator in p.decorators.iter() {
-                        let descriptor = self.build_single_descriptor(
-                            &decorator.expression,
-                            kind,
-                            is_static,
-                            is_private,
-                            &p.key,
-                            ctx,
-                        );
-                        descriptors.push(ArrayExpressionElement::from(descriptor));
-                    }
-                }
-                ClassElement::AccessorProperty(a) if !a.decorators.is_empty() => {
-                    let kind = DecoratorKind::Accessor;
-                    let is_static = a.r#static;
-                    let is_private = matches!(&a.key, PropertyKey::PrivateIdentifier(_));
-
-                    for decorator in a.decorators.iter() {
-                        let descriptor = self.build_single_descriptor(
-                            &decorator.expression,
-                            kind,
-                            is_static,
-                            is_private,
-                            &a.key,
-                            ctx,
-                        );
-                        descriptors.push(ArrayExpressionElement::from(descriptor));
-                    }
-                }
-                _ => {}
-            }
-        }
+    ) -> Statement<'a> {
+        let binding = ctx.ast.binding_pattern(
+            ctx.ast.binding_pattern_kind_binding_identifier(SPAN, name),
+            NONE,
+            false,
+        );
+        let declarator = ctx.ast.variable_declarator(
+            SPAN,
+            VariableDeclarationKind::Const,
+            binding,
+            Some(init),
+            false,
+        );
+        Statement::from(ctx.ast.declaration_variable(
+            SPAN,
+            VariableDeclarationKind::Const,
+            ctx.ast.vec1(declarator),
+            false,
+        ))
+    }
 
-        ctx.ast.expression_array(SPAN, descriptors)
+    // `name()`
+    fn build_call(&self, name: Atom<'a>, ctx: &TraverseCtx<'a, TransformerState>) -> Expression<'a> {
+        ctx.ast
+            .expression_call(SPAN, self.identifier(name, ctx), NONE, ctx.ast.vec(), false)
     }
 
-    fn build_single_descriptor(
+    // `[a, , b]`, assigning each element to a temporary; `None` leaves a hole.
+    fn array_assignment_target(
         &self,
-        decorator_expr: &Expression<'a>,
-        kind: DecoratorKind,
-        is_static: bool,
-        is_private: bool,
-        key: &PropertyKey<'a>,
+        names: &[Option<Atom<'a>>],
         ctx: &TraverseCtx<'a, TransformerState>,
-    ) -> Expression<'a> {
-        let mut elements = ctx.ast.vec();
-        let decorator = self.clone_expression(decorator_expr, ctx);
-        elements.push(ArrayExpressionElement::from(decorator));
-        let flags = (kind as u8) | if is_static { 8 } else { 0 };
-        let flags_expr =
-            ctx.ast
-                .expression_numeric_literal(SPAN, flags as f64, None, NumberBase::Decimal);
-        elements.push(ArrayExpressionElement::from(flags_expr));
-        let key_str = self.extract_property_key_string(key, ctx);
-        let key_expr = ctx.ast.expression_string_literal(SPAN, key_str, None);
-        elements.push(ArrayExpressionElement::from(key_expr));
-        let is_private_expr = ctx.ast.expression_boolean_literal(SPAN, is_private);
-        elements.push(ArrayExpressionElement::from(is_private_expr));
-        ctx.ast.expression_array(SPAN, elements)
+    ) -> AssignmentTarget<'a> {
+        let elements = names.iter().map(|name| {
+            name.map(|name| {
+                let ident_ref = ctx.ast.alloc(ctx.ast.identifier_reference(SPAN, name));
+                AssignmentTargetMaybeDefault::from(
+                    SimpleAssignmentTarget::AssignmentTargetIdentifier(ident_ref),
+                )
+            })
+        });
+        AssignmentTarget::from(ctx.ast.assignment_target_pattern_array_assignment_target(
+            SPAN,
+            ctx.ast.vec_from_iter(elements),
+            NONE,
+        ))
     }
 
-    fn build_apply_decs_assignment(
+    // `_applyDecs(class_target, member_desc_array, class_dec_array, ...)`
+    fn build_apply_decs_call(
         &self,
-        target_names: &[&'a str],
+        class_target: Expression<'a>,
         member_desc_array: Expression<'a>,
         class_dec_array: Expression<'a>,
-        property_name: &'a str,
-        ctx: &mut TraverseCtx<'a, TransformerState>,
-    ) -> Statement<'a> {
+        extra_arguments: Vec<Expression<'a>>,
+        ctx: &TraverseCtx<'a, TransformerState>,
+    ) -> Expression<'a> {
         let apply_decs_callee = Expression::Identifier(
             ctx.ast
-                .alloc(ctx.ast.identifier_reference(SPAN, "_applyDecs")),
+                .alloc(ctx.ast.identifier_reference(SPAN, self.helper("_applyDecs"))),
         );
         let mut arguments = ctx.ast.vec();
-        arguments.push(Argument::from(ctx.ast.expression_this(SPAN)));
+        arguments.push(Argument::from(class_target));
         arguments.push(Argument::from(member_desc_array));
         arguments.push(Argument::from(class_dec_array));
-        let apply_decs_call =
-            ctx.ast
-                .expression_call(SPAN, apply_decs_callee, NONE, arguments, false);
-        let property = ctx.ast.identifier_name(SPAN, property_name);
-        let member_expr = ctx
-            .ast
-            .member_expression_static(SPAN, apply_decs_call, property, false);
-        let right = Expression::from(member_expr);
-        let mut assignment_elements = ctx.ast.vec();
-        for &name in target_names {
-            let ident_ref = ctx.ast.alloc(ctx.ast.identifier_reference(SPAN, name));
-            let target = AssignmentTargetMaybeDefault::from(
-                SimpleAssignmentTarget::AssignmentTargetIdentifier(ident_ref),
-            );
-            assignment_elements.push(Some(target));
-        }
-        let array_assignment_target = ctx.ast.assignment_target_pattern_array_assignment_target(
-            SPAN,
-            assignment_elements,
-            NONE,
-        );
-        let assignment_target = AssignmentTarget::from(array_assignment_target);
+        arguments.extend(extra_arguments.into_iter().map(Argument::from));
+        ctx.ast
+            .expression_call(SPAN, apply_decs_callee, NONE, arguments, false)
+    }
+
+    // Assigns the results of `apply_decs_call`, an `_applyDecs` call or the
+    // temporary holding its result, to the initializer temporaries and the
+    // decorated class.
+    fn build_apply_decs_assignment(
+        &self,
+        target_names: &[Option<Atom<'a>>],
+        apply_decs_call: Expression<'a>,
+        decorated_class: Option<(Atom<'a>, Atom<'a>)>,
+        ctx: &TraverseCtx<'a, TransformerState>,
+    ) -> Statement<'a> {
+        let property = |name: &'static str, target: AssignmentTarget<'a>| {
+            ctx.ast.assignment_target_property_assignment_target_property_property(
+                SPAN,
+                ctx.ast.property_key_static_identifier(SPAN, name),
+                AssignmentTargetMaybeDefault::from(target),
+                false,
+            )
+        };
+        // `[...] = _applyDecs(...).e`, `[_C, _classInit] = _applyDecs(...).c`
+        // or, when both are needed,
+        // `({ e: [...], c: [_C, _classInit] } = _applyDecs(...))`.
+        let (assignment_target, right) = match decorated_class {
+            Some((decorated, class_init)) if !target_names.is_empty() => {
+                let mut properties = ctx.ast.vec();
+                properties.push(property("e", self.array_assignment_target(target_names, ctx)));
+                properties.push(property(
+                    "c",
+                    self.array_assignment_target(&[Some(decorated), Some(class_init)], ctx),
+                ));
+                let pattern = ctx.ast.assignment_target_pattern_object_assignment_target(
+                    SPAN, properties, NONE,
+                );
+                (AssignmentTarget::from(pattern), apply_decs_call)
+            }
+            _ => {
+                let (names, property_name) = match decorated_class {
+                    Some((decorated, class_init)) => (vec![Some(decorated), Some(class_init)], "c"),
+                    None => (target_names.to_vec(), "e"),
+                };
+                let property = ctx.ast.identifier_name(SPAN, property_name);
+                let member_expr = ctx
+                    .ast
+                    .member_expression_static(SPAN, apply_decs_call, property, false);
+                (self.array_assignment_target(&names, ctx), Expression::from(member_expr))
+            }
+        };
         let assignment = ctx.ast.expression_assignment(
             SPAN,
             AssignmentOperator::Assign,
@@ -409,11 +2833,11 @@ impl<'a> DecoratorTransformer<'a> {
     ) -> Statement<'a> {
         let test = Expression::Identifier(
             ctx.ast
-                .alloc(ctx.ast.identifier_reference(SPAN, "_initClass")),
+                .alloc(ctx.ast.identifier_reference(SPAN, self.init_names.borrow().1)),
         );
         let callee = Expression::Identifier(
             ctx.ast
-                .alloc(ctx.ast.identifier_reference(SPAN, "_initClass")),
+                .alloc(ctx.ast.identifier_reference(SPAN, self.init_names.borrow().1)),
         );
         let call = ctx
             .ast
@@ -433,9 +2857,17 @@ impl<'a> DecoratorTransformer<'a> {
             .map(|dec| self.clone_expression(&dec.expression, ctx))
             .collect()
     }
+    // Runs `_initProto` (then `instance_inits`) at the start of the
+    // constructor, or right after its `super()` call, and after the
+    // parameter properties `strip_types` lowered there. A derived constructor
+    // calling `super()` elsewhere than in a statement of its own, such as in
+    // a branch or an arrow function, runs them after each such call; one
+    // that never calls it (returning another object) has no instance to
+    // initialize.
     fn ensure_constructor_with_init(
         &self,
         class: &mut Class<'a>,
+        instance_inits: ArenaVec<'a, Statement<'a>>,
         ctx: &mut TraverseCtx<'a, TransformerState>,
     ) {
         let constructor_index = class.body.body.iter().position(|element| {
@@ -445,14 +2877,38 @@ impl<'a> DecoratorTransformer<'a> {
 
         if let Some(index) = constructor_index {
             if let ClassElement::MethodDefinition(method) = &mut class.body.body[index] {
-                if let Some(ref mut body) = method.value.body {
+                let Function { params, body, .. } = &mut *method.value;
+                if let Some(body) = body {
                     let insert_pos = self.find_super_call_insert_position(&body.statements);
+                    if insert_pos == 0 && class.super_class.is_some() {
+                        let mut inits = vec![self.build_init_proto_call_expression(ctx)];
+                        inits.extend(instance_inits.into_iter().filter_map(|init| match init {
+                            Statement::ExpressionStatement(init) => {
+                                Some(init.unbox().expression)
+                            }
+                            _ => None,
+                        }));
+                        let mut wrapper = SuperCallWrapper {
+                            ast: ctx.ast,
+                            inits: &inits,
+                        };
+                        wrapper.visit_function_body(body);
+                        return;
+                    }
+                    let insert_pos = insert_pos
+                        + body.statements[insert_pos..]
+                            .iter()
+                            .take_while(|stmt| is_parameter_property(stmt, params))
+                            .count();
                     let init_stmt = self.build_init_proto_if_statement(ctx);
-                    body.statements.insert(insert_pos, init_stmt);
+                    body.statements.splice(
+                        insert_pos..insert_pos,
+                        std::iter::once(init_stmt).chain(instance_inits),
+                    );
                 }
             }
         } else {
-            let constructor = self.create_constructor_with_init(class, ctx);
+            let constructor = self.create_constructor_with_init(class, instance_inits, ctx);
             class.body.body.insert(0, constructor);
         }
     }
@@ -470,20 +2926,96 @@ impl<'a> DecoratorTransformer<'a> {
         0
     }
 
+    // For `lazy_decorators`: `_initProto = (_instance) => { _initProto =
+    // void 0; <decoration>; if (_initProto) _initProto(_instance); };`. The
+    // stub clears itself first so that an instance constructed by a
+    // decorator is not decorated twice.
+    fn build_lazy_init_proto(
+        &self,
+        decoration: Vec<Statement<'a>>,
+        ctx: &mut TraverseCtx<'a, TransformerState>,
+    ) -> Statement<'a> {
+        let init_proto = self.init_names.borrow().0;
+        let instance = ctx.generate_uid_name("instance");
+        let clear = ctx.ast.expression_assignment(
+            SPAN,
+            AssignmentOperator::Assign,
+            AssignmentTarget::from(
+                ctx.ast
+                    .simple_assignment_target_assignment_target_identifier(SPAN, init_proto),
+            ),
+            ctx.ast.void_0(SPAN),
+        );
+        let mut body = ctx.ast.vec();
+        body.push(ctx.ast.statement_expression(SPAN, clear));
+        body.extend(decoration);
+        body.push(self.build_init_proto_if_statement_for(
+            self.identifier(instance, ctx),
+            ctx,
+        ));
+        let body = ctx.ast.alloc_function_body(SPAN, ctx.ast.vec(), body);
+        let params = self.build_formal_parameters(&[instance.as_str()], ctx);
+        let scope_id =
+            ctx.create_child_scope_of_current(ScopeFlags::Function | ScopeFlags::Arrow);
+        let stub = ctx.ast.expression_arrow_function_with_scope_id_and_pure_and_pife(
+            SPAN, false, false, NONE, params, NONE, body, scope_id, false, false,
+        );
+        let assignment = ctx.ast.expression_assignment(
+            SPAN,
+            AssignmentOperator::Assign,
+            AssignmentTarget::from(
+                ctx.ast
+                    .simple_assignment_target_assignment_target_identifier(SPAN, init_proto),
+            ),
+            stub,
+        );
+        ctx.ast.statement_expression(SPAN, assignment)
+    }
+
     fn build_init_proto_if_statement(
         &self,
         ctx: &TraverseCtx<'a, TransformerState>,
+    ) -> Statement<'a> {
+        self.build_init_proto_if_statement_for(ctx.ast.expression_this(SPAN), ctx)
+    }
+
+    // `_initProto && _initProto(this)`
+    fn build_init_proto_call_expression(
+        &self,
+        ctx: &TraverseCtx<'a, TransformerState>,
+    ) -> Expression<'a> {
+        let init_proto = self.init_names.borrow().0;
+        let call = ctx.ast.expression_call(
+            SPAN,
+            self.identifier(init_proto, ctx),
+            NONE,
+            ctx.ast.vec1(Argument::from(ctx.ast.expression_this(SPAN))),
+            false,
+        );
+        ctx.ast.expression_logical(
+            SPAN,
+            self.identifier(init_proto, ctx),
+            LogicalOperator::And,
+            call,
+        )
+    }
+
+    // `if (_initProto) _initProto(instance);`
+    fn build_init_proto_if_statement_for(
+        &self,
+        instance: Expression<'a>,
+        ctx: &TraverseCtx<'a, TransformerState>,
     ) -> Statement<'a> {
         let test = Expression::Identifier(
             ctx.ast
-                .alloc(ctx.ast.identifier_reference(SPAN, "_initProto")),
+                .alloc(ctx.ast.identifier_reference(SPAN, self.init_names.borrow().0)),
         );
         let callee = Expression::Identifier(
             ctx.ast
-                .alloc(ctx.ast.identifier_reference(SPAN, "_initProto")),
+                .alloc(ctx.ast.identifier_reference(SPAN, self.init_names.borrow().0)),
         );
         let mut arguments = ctx.ast.vec();
-        arguments.push(Argument::from(ctx.ast.expression_this(SPAN)));
+        arguments.push(Argument::from(instance));
         let call = ctx
             .ast
             .expression_call(SPAN, callee, NONE, arguments, false);
@@ -494,21 +3026,35 @@ impl<'a> DecoratorTransformer<'a> {
     fn create_constructor_with_init(
         &self,
         class: &Class<'a>,
+        instance_inits: ArenaVec<'a, Statement<'a>>,
         ctx: &mut TraverseCtx<'a, TransformerState>,
     ) -> ClassElement<'a> {
         let mut statements = ctx.ast.vec();
+        // A derived class forwards its arguments: `constructor(...args) {
+        // super(...args); }`.
+        let mut rest = None;
         if class.super_class.is_some() {
+            let args = ctx.generate_uid_name("args");
+            rest = Some(ctx.ast.alloc_binding_rest_element(
+                SPAN,
+                ctx.ast.binding_pattern(
+                    ctx.ast.binding_pattern_kind_binding_identifier(SPAN, args),
+                    NONE,
+                    false,
+                ),
+            ));
             let super_call = ctx.ast.expression_call(
                 SPAN,
                 ctx.ast.expression_super(SPAN),
                 NONE,
-                ctx.ast.vec(),
+                ctx.ast.vec1(ctx.ast.argument_spread_element(SPAN, self.identifier(args, ctx))),
                 false,
             );
             statements.push(ctx.ast.statement_expression(SPAN, super_call));
         }
         let init_stmt = self.build_init_proto_if_statement(ctx);
         statements.push(init_stmt);
+        statements.extend(instance_inits);
         let body = ctx.ast.alloc_function_body(SPAN, ctx.ast.vec(), statements);
         let scope_id =
             ctx.create_child_scope_of_current(ScopeFlags::Function | ScopeFlags::Constructor);
@@ -516,7 +3062,7 @@ impl<'a> DecoratorTransformer<'a> {
             SPAN,
             FormalParameterKind::FormalParameter,
             ctx.ast.vec(),
-            NONE,
+            rest,
         );
         let function = ctx.ast.alloc_function_with_scope_id(
             SPAN,
@@ -550,6 +3096,14 @@ impl<'a> DecoratorTransformer<'a> {
 }
 
 impl<'a> Traverse<'a, TransformerState> for DecoratorTransformer<'a> {
+    fn enter_expression(
+        &mut self,
+        expr: &mut Expression<'a>,
+        ctx: &mut TraverseCtx<'a, TransformerState>,
+    ) {
+        self.transform_class_expression_decorators(expr, ctx);
+    }
+
     fn enter_class(&mut self, class: &mut Class<'a>, ctx: &mut TraverseCtx<'a, TransformerState>) {
         self.transform_class_with_decorators(class, ctx);
     }
@@ -557,6 +3111,14 @@ impl<'a> Traverse<'a, TransformerState> for DecoratorTransformer<'a> {
     fn exit_class(&mut self, _class: &mut Class<'a>, _ctx: &mut TraverseCtx<'a, TransformerState>) {
         *self.in_decorated_class.borrow_mut() = false;
     }
+
+    fn exit_statements(
+        &mut self,
+        statements: &mut ArenaVec<'a, Statement<'a>>,
+        ctx: &mut TraverseCtx<'a, TransformerState>,
+    ) {
+        self.apply_class_decorators(statements, ctx);
+    }
 }
 
 #[cfg(test)]
@@ -571,7 +3133,7 @@ mod tests {
     #[test]
     fn test_transformer_creation() {
         let allocator = Allocator::default();
-        let transformer = DecoratorTransformer::new(&allocator);
+        let transformer = DecoratorTransformer::new(&allocator, TransformOptions::default());
         assert_eq!(transformer.errors.len(), 0);
     }
 
@@ -586,7 +3148,7 @@ mod tests {
 
         let semantic_ret = SemanticBuilder::new().build(&parse_result.program);
         let scoping = semantic_ret.semantic.into_scoping();
-        let mut transformer = DecoratorTransformer::new(&allocator);
+        let mut transformer = DecoratorTransformer::new(&allocator, TransformOptions::default());
         let state = TransformerState;
         traverse_mut(
             &mut transformer,
@@ -596,11 +3158,23 @@ mod tests {
             state,
         );
         assert_eq!(transformer.errors.len(), 0);
-        if let Statement::ClassDeclaration(class_decl) = &parse_result.program.body[0] {
-            assert!(class_decl.decorators.is_empty());
-        } else {
-            panic!("Expected class declaration");
-        }
+        // Rebound as `let C = class C {}; [C, _classInit] = _applyDecs(C, [], [dec]).c;`.
+        let Some(Expression::ClassExpression(class)) = rebound_class(&parse_result.program.body[0])
+        else {
+            panic!("Expected `let C = class C {{}}`");
+        };
+        assert!(class.decorators.is_empty());
+        let Statement::ExpressionStatement(stmt) = &parse_result.program.body[1] else {
+            panic!("Expected `_applyDecs` assignment");
+        };
+        assert!(matches!(stmt.expression, Expression::AssignmentExpression(_)));
+    }
+
+    fn rebound_class<'s, 'a>(statement: &'s Statement<'a>) -> Option<&'s Expression<'a>> {
+        let Statement::VariableDeclaration(declaration) = statement else {
+            return None;
+        };
+        declaration.declarations[0].init.as_ref()
     }
 
     #[test]
@@ -612,7 +3186,7 @@ mod tests {
         let mut parse_result = parser.parse();
         let semantic_ret = SemanticBuilder::new().build(&parse_result.program);
         let scoping = semantic_ret.semantic.into_scoping();
-        let mut transformer = DecoratorTransformer::new(&allocator);
+        let mut transformer = DecoratorTransformer::new(&allocator, TransformOptions::default());
         let state = TransformerState;
         traverse_mut(
             &mut transformer,
@@ -621,7 +3195,67 @@ mod tests {
             scoping,
             state,
         );
-        assert!(parse_result.program.body.len() > 0);
+        assert!(!parse_result.program.body.is_empty());
+    }
+
+    #[test]
+    fn test_namespace_import_decorator() {
+        let allocator = Allocator::default();
+        let source_text = r#"import * as mod from "m"; @mod.dec class C { @mod.dec m() {} }"#;
+        let parser = Parser::new(&allocator, source_text, SourceType::mjs());
+        let mut parse_result = parser.parse();
+        let semantic_ret = SemanticBuilder::new().build(&parse_result.program);
+        let scoping = semantic_ret.semantic.into_scoping();
+        let mut transformer = DecoratorTransformer::new(&allocator, TransformOptions::default());
+        traverse_mut(
+            &mut transformer,
+            &allocator,
+            &mut parse_result.program,
+            scoping,
+            TransformerState,
+        );
+
+        let is_mod_dec = |expr: &Expression| {
+            matches!(expr, Expression::StaticMemberExpression(member)
+                if member.property.name == "dec"
+                    && matches!(&member.object, Expression::Identifier(id) if id.name == "mod"))
+        };
+        let class_decorators = transformer.classes_with_class_decorators.borrow();
+        assert!(is_mod_dec(&class_decorators[0].decorators[0]));
+
+        let Some(Expression::ClassExpression(class)) = rebound_class(&parse_result.program.body[1])
+        else {
+            panic!("Expected `let C = class C {{ ... }}`");
+        };
+        let Some(ClassElement::StaticBlock(block)) = class
+            .body
+            .body
+            .iter()
+            .find(|element| matches!(element, ClassElement::StaticBlock(_)))
+        else {
+            panic!("Expected decorator static block");
+        };
+        let Statement::ExpressionStatement(stmt) = &block.body[0] else {
+            panic!("Expected `_applyDecs` assignment");
+        };
+        let Expression::AssignmentExpression(assignment) = &stmt.expression else {
+            panic!("Expected `_applyDecs` assignment");
+        };
+        let Expression::StaticMemberExpression(e) = &assignment.right else {
+            panic!("Expected `.e` access");
+        };
+        let Expression::CallExpression(call) = &e.object else {
+            panic!("Expected `_applyDecs` call");
+        };
+        let Some(Expression::ArrayExpression(descriptors)) = call.arguments[1].as_expression()
+        else {
+            panic!("Expected descriptor array");
+        };
+        let Some(Expression::ArrayExpression(descriptor)) = descriptors.elements[0].as_expression()
+        else {
+            panic!("Expected member descriptor");
+        };
+        assert!(is_mod_dec(descriptor.elements[0].as_expression().unwrap()));
     }
 
     #[test]
@@ -633,7 +3267,7 @@ mod tests {
         let mut parse_result = parser.parse();
         let semantic_ret = SemanticBuilder::new().build(&parse_result.program);
         let scoping = semantic_ret.semantic.into_scoping();
-        let mut transformer = DecoratorTransformer::new(&allocator);
+        let mut transformer = DecoratorTransformer::new(&allocator, TransformOptions::default());
         let state = TransformerState;
         traverse_mut(
             &mut transformer,
@@ -642,6 +3276,6 @@ mod tests {
             scoping,
             state,
         );
-        assert!(parse_result.program.body.len() > 0);
+        assert!(!parse_result.program.body.is_empty());
     }
 }
@@ -41,7 +41,7 @@ describe('Class Decorator Replacement', () => {
     expect(output).toContain('let TestClass = class TestClass');
     
     // Should apply decorator after class definition
-    expect(output).toContain('TestClass = _applyDecs(TestClass, [], [logged]).c[0]');
+    expect(output).toContain('[TestClass, _classInit] = _applyDecs(TestClass, [], [logged]).c;\n_classInit();');
     
     // Static block should use .e for members only (empty in this case) with empty class decorators array
     expect(output).toContain('[_initProto, _initClass] = _applyDecs(this, [], []).e');
@@ -65,7 +65,7 @@ describe('Class Decorator Replacement', () => {
     expect(output).toContain('let MyClass = class MyClass');
     
     // Should apply decorator
-    expect(output).toContain('MyClass = _applyDecs(MyClass, [], [logged]).c[0]');
+    expect(output).toContain('[MyClass, _classInit] = _applyDecs(MyClass, [], [logged]).c;\n_classInit();');
     
     // Should export the transformed class
     expect(output).toContain('export default MyClass');
@@ -89,7 +89,7 @@ describe('Class Decorator Replacement', () => {
     expect(output).toContain('let MyClass = class MyClass');
     
     // Should apply decorator
-    expect(output).toContain('MyClass = _applyDecs(MyClass, [], [logged]).c[0]');
+    expect(output).toContain('[MyClass, _classInit] = _applyDecs(MyClass, [], [logged]).c;\n_classInit();');
     
     // Should export the transformed class
     expect(output).toContain('export { MyClass }');
@@ -120,7 +120,7 @@ describe('Class Decorator Replacement', () => {
     expect(output).toContain('let TestClass = class TestClass');
     
     // Should apply class decorator separately
-    expect(output).toContain('TestClass = _applyDecs(TestClass, [], [classDecorator]).c[0]');
+    expect(output).toContain('[TestClass, _classInit] = _applyDecs(TestClass, [], [classDecorator]).c;\n_classInit();');
     
     // Static block should handle member decorators with .e and empty class decorators array
     expect(output).toMatch(/\[_initProto, _initClass\] = _applyDecs\(this,[\s\S]*methodDecorator[\s\S]*,\s*\[\]\)\.e/);